@@ -13,7 +13,14 @@
 //  limitations under the License.
 // -------------------------------------------------------------------------------------------------
 
-use std::{ffi::c_void, fmt::Display, ptr::null};
+use std::{
+    ffi::c_void,
+    fmt::Display,
+    marker::PhantomData,
+    ops::Deref,
+    ptr::{null, NonNull},
+    slice,
+};
 
 /// `CVec` is a C compatible struct that stores an opaque pointer to a block of
 /// memory, it's length and the capacity of the vector it was allocated from.
@@ -83,6 +90,63 @@ impl Display for CVec {
     }
 }
 
+/// A borrowed view of the elements of a [`CVec`] as a slice of `T`, which reads
+/// the memory in place rather than copying it into a `Vec`.
+///
+/// The view borrows the [`CVec`] for `'a`, such as one owned by a Python capsule,
+/// so the memory cannot be dropped through the [`CVec`] while the view is alive.
+#[derive(Clone, Copy, Debug)]
+pub struct CVecView<'a, T> {
+    ptr: NonNull<T>,
+    len: usize,
+    _marker: PhantomData<&'a [T]>,
+}
+
+impl<'a, T> CVecView<'a, T> {
+    /// Creates a new [`CVecView`] of the elements of `cvec`.
+    ///
+    /// # Safety
+    ///
+    /// The `cvec` must have been created from a `Vec<T>` of the same `T`, and its
+    /// memory must not be freed or mutated while the view is alive.
+    #[must_use]
+    pub unsafe fn from_cvec(cvec: &'a CVec) -> Self {
+        // An empty `CVec` has a null pointer, which is not valid for a slice
+        let ptr = NonNull::new(cvec.ptr.cast::<T>()).unwrap_or(NonNull::dangling());
+        let len = if cvec.ptr.is_null() { 0 } else { cvec.len };
+        Self {
+            ptr,
+            len,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the elements of the view as a slice.
+    #[must_use]
+    pub fn as_slice(&self) -> &'a [T] {
+        // SAFETY: The pointer and length were checked on construction, and the
+        // memory is borrowed for `'a`
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T> Deref for CVecView<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl<'a, T> IntoIterator for CVecView<'a, T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.as_slice().iter()
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // C API
 ////////////////////////////////////////////////////////////////////////////////
@@ -106,7 +170,7 @@ mod tests {
 
     use rstest::*;
 
-    use super::CVec;
+    use super::{CVec, CVecView};
 
     /// Access values from a vector converted into a [`CVec`].
     #[rstest]
@@ -166,6 +230,41 @@ mod tests {
         }
     }
 
+    /// Allocating and freeing a [`CVec`] repeatedly must not leak, which is
+    /// checked when running under a sanitizer.
+    #[rstest]
+    fn alloc_and_drop_in_loop_test() {
+        for i in 0..1_000_u64 {
+            let cvec: CVec = vec![i; 100].into();
+            let CVec { ptr, len, cap } = cvec;
+            assert_eq!(len, 100);
+
+            let data: Vec<u64> = unsafe { Vec::from_raw_parts(ptr.cast::<u64>(), len, cap) };
+            assert_eq!(data[99], i);
+            drop(data);
+        }
+    }
+
+    /// A view of a [`CVec`] reads the same elements as the vector it was
+    /// converted from.
+    #[rstest]
+    fn view_matches_copied_vec_test() {
+        let test_data: Vec<u64> = (0..1_000).collect();
+        let cvec: CVec = test_data.clone().into();
+
+        let view = unsafe { CVecView::<u64>::from_cvec(&cvec) };
+        let copied: Vec<u64> = view.into_iter().copied().collect();
+        assert_eq!(view.len(), test_data.len());
+        assert_eq!(view.as_slice(), test_data.as_slice());
+        assert_eq!(copied, test_data);
+
+        let empty = CVec::empty();
+        assert!(unsafe { CVecView::<u64>::from_cvec(&empty) }.is_empty());
+
+        let CVec { ptr, len, cap } = cvec;
+        drop(unsafe { Vec::from_raw_parts(ptr.cast::<u64>(), len, cap) });
+    }
+
     /// An empty vector gets converted to a null pointer wrapped in a [`CVec`].
     #[rstest]
     fn empty_vec_should_give_null_ptr() {
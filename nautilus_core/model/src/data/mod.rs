@@ -75,10 +75,70 @@ impl Data {
         }
     }
 
+    /// Returns the UNIX timestamp (nanoseconds) when the data event occurred.
+    pub fn ts_event(&self) -> UnixNanos {
+        match self {
+            Self::Delta(delta) => delta.ts_event,
+            Self::Deltas(deltas) => deltas.ts_event,
+            Self::Depth10(depth) => depth.ts_event,
+            Self::Quote(quote) => quote.ts_event,
+            Self::Trade(trade) => trade.ts_event,
+            Self::Bar(bar) => bar.ts_event,
+        }
+    }
+
     /// Returns whether the data is a type of order book data.
     pub fn is_order_book_data(&self) -> bool {
         matches!(self, Self::Delta(_) | Self::Deltas(_) | Self::Depth10(_))
     }
+
+    /// Returns the order book delta if the data is a [`Data::Delta`].
+    pub fn as_delta(&self) -> Option<&OrderBookDelta> {
+        match self {
+            Self::Delta(delta) => Some(delta),
+            _ => None,
+        }
+    }
+
+    /// Returns the order book deltas if the data is a [`Data::Deltas`].
+    pub fn as_deltas(&self) -> Option<&OrderBookDeltas_API> {
+        match self {
+            Self::Deltas(deltas) => Some(deltas),
+            _ => None,
+        }
+    }
+
+    /// Returns the order book depth if the data is a [`Data::Depth10`].
+    pub fn as_depth10(&self) -> Option<&OrderBookDepth10> {
+        match self {
+            Self::Depth10(depth) => Some(depth),
+            _ => None,
+        }
+    }
+
+    /// Returns the quote tick if the data is a [`Data::Quote`].
+    pub fn as_quote(&self) -> Option<&QuoteTick> {
+        match self {
+            Self::Quote(quote) => Some(quote),
+            _ => None,
+        }
+    }
+
+    /// Returns the trade tick if the data is a [`Data::Trade`].
+    pub fn as_trade(&self) -> Option<&TradeTick> {
+        match self {
+            Self::Trade(trade) => Some(trade),
+            _ => None,
+        }
+    }
+
+    /// Returns the bar if the data is a [`Data::Bar`].
+    pub fn as_bar(&self) -> Option<&Bar> {
+        match self {
+            Self::Bar(bar) => Some(bar),
+            _ => None,
+        }
+    }
 }
 
 pub trait GetTsInit {
@@ -98,9 +158,56 @@ impl GetTsInit for Data {
     }
 }
 
+/// Describes the order of a slice of data by `ts_init`, as returned by
+/// [`monotonicity_report`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MonotonicityReport {
+    /// The number of elements with a `ts_init` earlier than the element before it.
+    pub inversions: usize,
+    /// The index of the first element with a `ts_init` earlier than the element
+    /// before it, if any.
+    pub first_offending_index: Option<usize>,
+    /// The `ts_init` of the element before the first offending element, and of
+    /// the offending element itself.
+    pub offending_timestamps: Option<(UnixNanos, UnixNanos)>,
+}
+
+impl MonotonicityReport {
+    /// Returns whether the data is monotonically increasing by `ts_init`.
+    #[must_use]
+    pub const fn is_monotonic(&self) -> bool {
+        self.inversions == 0
+    }
+}
+
+/// Returns whether `data` is monotonically increasing by `ts_init`.
 pub fn is_monotonically_increasing_by_init<T: GetTsInit>(data: &[T]) -> bool {
-    data.windows(2)
-        .all(|window| window[0].ts_init() <= window[1].ts_init())
+    monotonicity_report(data).is_monotonic()
+}
+
+/// Checks the order of `data` by `ts_init`, counting the elements which are
+/// earlier than the element before them and locating the first of them.
+pub fn monotonicity_report<T: GetTsInit>(data: &[T]) -> MonotonicityReport {
+    let mut report = MonotonicityReport::default();
+    for (i, window) in data.windows(2).enumerate() {
+        let (prev, next) = (window[0].ts_init(), window[1].ts_init());
+        if next < prev {
+            report.inversions += 1;
+            if report.first_offending_index.is_none() {
+                report.first_offending_index = Some(i + 1);
+                report.offending_timestamps = Some((prev, next));
+            }
+        }
+    }
+    report
+}
+
+/// Returns the `ts_init` of the first and last elements of `data`, which is its
+/// time range when ordered by `ts_init`, or `None` if `data` is empty.
+pub fn time_range<T: GetTsInit>(data: &[T]) -> Option<(u64, u64)> {
+    let first = data.first()?.ts_init().as_u64();
+    let last = data.last()?.ts_init().as_u64();
+    Some((first, last))
 }
 
 impl From<OrderBookDelta> for Data {
@@ -401,6 +508,74 @@ mod tests {
     use rstest::*;
 
     use super::*;
+    use crate::data::stubs::*;
+
+    #[rstest]
+    fn test_monotonicity_report_unsorted(quote_tick_audusd_sim: QuoteTick) {
+        let data: Vec<Data> = [1_u64, 2, 5, 3, 4, 4, 2]
+            .into_iter()
+            .map(|ts| {
+                let mut quote = quote_tick_audusd_sim;
+                quote.ts_init = ts.into();
+                Data::Quote(quote)
+            })
+            .collect();
+
+        let report = monotonicity_report(&data);
+
+        assert_eq!(report.inversions, 2);
+        assert_eq!(report.first_offending_index, Some(3));
+        assert_eq!(
+            report.offending_timestamps,
+            Some((UnixNanos::from(5), UnixNanos::from(3)))
+        );
+        assert!(!report.is_monotonic());
+        assert!(!is_monotonically_increasing_by_init(&data));
+        assert!(monotonicity_report(&data[..3]).is_monotonic());
+    }
+
+    #[rstest]
+    fn test_data_accessors(
+        stub_delta: OrderBookDelta,
+        stub_deltas: deltas::OrderBookDeltas,
+        stub_depth10: OrderBookDepth10,
+        quote_tick_audusd_sim: QuoteTick,
+        trade_tick_audusd_sim: TradeTick,
+        stub_bar: Bar,
+    ) {
+        let delta = Data::from(stub_delta);
+        let deltas = Data::from(OrderBookDeltas_API::new(stub_deltas));
+        let depth = Data::from(stub_depth10);
+        let quote = Data::from(quote_tick_audusd_sim);
+        let trade = Data::from(trade_tick_audusd_sim);
+        let bar = Data::from(stub_bar);
+        let all = [&delta, &deltas, &depth, &quote, &trade, &bar];
+
+        assert_eq!(delta.as_delta(), Some(&stub_delta));
+        assert_eq!(depth.as_depth10(), Some(&stub_depth10));
+        assert_eq!(quote.as_quote(), Some(&quote_tick_audusd_sim));
+        assert_eq!(trade.as_trade(), Some(&trade_tick_audusd_sim));
+        assert_eq!(bar.as_bar(), Some(&stub_bar));
+        assert!(deltas.as_deltas().is_some());
+        for (i, data) in all.iter().enumerate() {
+            assert_eq!(data.as_delta().is_some(), i == 0);
+            assert_eq!(data.as_deltas().is_some(), i == 1);
+            assert_eq!(data.as_depth10().is_some(), i == 2);
+            assert_eq!(data.as_quote().is_some(), i == 3);
+            assert_eq!(data.as_trade().is_some(), i == 4);
+            assert_eq!(data.as_bar().is_some(), i == 5);
+        }
+    }
+
+    #[rstest]
+    fn test_time_range(quote_tick_audusd_sim: QuoteTick) {
+        let mut last = quote_tick_audusd_sim;
+        last.ts_init = 5.into();
+
+        assert_eq!(time_range::<QuoteTick>(&[]), None);
+        assert_eq!(time_range(&[quote_tick_audusd_sim]), Some((0, 0)));
+        assert_eq!(time_range(&[quote_tick_audusd_sim, last]), Some((0, 5)));
+    }
 
     #[rstest]
     fn test_data_type_creation_with_metadata() {
@@ -29,7 +29,7 @@ fn single_stream_bench(c: &mut Criterion) {
     group.bench_function("persistence v2", |b| {
         b.iter_batched_ref(
             || {
-                let mut catalog = DataBackendSession::new(chunk_size);
+                let mut catalog = DataBackendSession::new(chunk_size).unwrap();
                 catalog
                     .add_file::<QuoteTick>("quote_tick", file_path, None)
                     .unwrap();
@@ -54,7 +54,7 @@ fn multi_stream_bench(c: &mut Criterion) {
     group.bench_function("persistence v2", |b| {
         b.iter_batched_ref(
             || {
-                let mut catalog = DataBackendSession::new(chunk_size);
+                let mut catalog = DataBackendSession::new(chunk_size).unwrap();
 
                 for entry in fs::read_dir(dir_path).expect("No such directory") {
                     let entry = entry.expect("Failed to read directory");
@@ -86,5 +86,55 @@ fn multi_stream_bench(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, single_stream_bench, multi_stream_bench);
+fn decode_threads_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode_threads");
+    group.sample_size(10);
+    let chunk_size = 5000;
+    // about 72 M records, with streams split across multiple files
+    let dir_path = "../../bench_data/multi_stream_data";
+
+    for decode_threads in [1, 2, 4, 8] {
+        group.bench_function(format!("persistence v2 ({decode_threads} threads)"), |b| {
+            b.iter_batched_ref(
+                || {
+                    let mut catalog = DataBackendSession::new(chunk_size).unwrap();
+                    catalog.set_decode_threads(decode_threads).unwrap();
+
+                    for entry in fs::read_dir(dir_path).expect("No such directory") {
+                        let entry = entry.expect("Failed to read directory");
+                        let path = entry.path();
+
+                        if path.is_file() && path.extension().unwrap() == "parquet" {
+                            let file_name = path.file_stem().unwrap().to_str().unwrap();
+
+                            if file_name.contains("quotes") {
+                                catalog
+                                    .add_file::<QuoteTick>(file_name, path.to_str().unwrap(), None)
+                                    .unwrap();
+                            } else if file_name.contains("trades") {
+                                catalog
+                                    .add_file::<TradeTick>(file_name, path.to_str().unwrap(), None)
+                                    .unwrap();
+                            }
+                        }
+                    }
+
+                    catalog.get_query_result()
+                },
+                |query_result: &mut QueryResult| {
+                    let count: usize = query_result.count();
+                    assert_eq!(count, 72_536_038);
+                },
+                BatchSize::SmallInput,
+            );
+        });
+    }
+}
+
+criterion_group!(
+    benches,
+    single_stream_bench,
+    multi_stream_bench,
+    decode_threads_bench
+);
 criterion_main!(benches);
@@ -0,0 +1,204 @@
+// -------------------------------------------------------------------------------------------------
+//  Copyright (C) 2015-2024 Nautech Systems Pty Ltd. All rights reserved.
+//  https://nautechsystems.io
+//
+//  Licensed under the GNU Lesser General Public License Version 3.0 (the "License");
+//  You may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at https://www.gnu.org/licenses/lgpl-3.0.en.html
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+// -------------------------------------------------------------------------------------------------
+
+//! Provides the encoding of instrument definitions to Arrow record batches.
+//!
+//! The instrument types have different fields, so each definition is held as a
+//! JSON string alongside the columns common to all of them, which can be
+//! filtered on in a query.
+
+use std::{collections::HashMap, sync::Arc};
+
+use datafusion::arrow::{
+    array::{StringArray, StringBuilder, UInt64Array},
+    datatypes::{DataType, Field, Schema},
+    error::ArrowError,
+    record_batch::RecordBatch,
+};
+use nautilus_model::instruments::any::InstrumentAny;
+use serde::Serialize;
+
+use super::{extract_column, ArrowSchemaProvider, EncodeToRecordBatch, EncodingError};
+use crate::error::PersistenceError;
+
+impl ArrowSchemaProvider for InstrumentAny {
+    fn get_schema(metadata: Option<HashMap<String, String>>) -> Schema {
+        let fields = vec![
+            Field::new("instrument_id", DataType::Utf8, false),
+            Field::new("instrument_type", DataType::Utf8, false),
+            Field::new("definition", DataType::Utf8, false),
+            Field::new("ts_event", DataType::UInt64, false),
+            Field::new("ts_init", DataType::UInt64, false),
+        ];
+
+        match metadata {
+            Some(metadata) => Schema::new_with_metadata(fields, metadata),
+            None => Schema::new(fields),
+        }
+    }
+}
+
+impl EncodeToRecordBatch for InstrumentAny {
+    fn encode_batch(
+        metadata: &HashMap<String, String>,
+        data: &[Self],
+    ) -> Result<RecordBatch, ArrowError> {
+        let mut instrument_id_builder = StringBuilder::new();
+        let mut instrument_type_builder = StringBuilder::new();
+        let mut definition_builder = StringBuilder::new();
+        let mut ts_event_builder = UInt64Array::builder(data.len());
+        let mut ts_init_builder = UInt64Array::builder(data.len());
+
+        for instrument in data {
+            let (instrument_type, definition) = match instrument {
+                Self::CryptoFuture(inst) => ("CryptoFuture", to_json(inst)?),
+                Self::CryptoPerpetual(inst) => ("CryptoPerpetual", to_json(inst)?),
+                Self::CurrencyPair(inst) => ("CurrencyPair", to_json(inst)?),
+                Self::Equity(inst) => ("Equity", to_json(inst)?),
+                Self::FuturesContract(inst) => ("FuturesContract", to_json(inst)?),
+                Self::FuturesSpread(inst) => ("FuturesSpread", to_json(inst)?),
+                Self::OptionsContract(inst) => ("OptionsContract", to_json(inst)?),
+                Self::OptionsSpread(inst) => ("OptionsSpread", to_json(inst)?),
+            };
+            let instrument = instrument.clone().into_instrument();
+            instrument_id_builder.append_value(instrument.id().to_string());
+            instrument_type_builder.append_value(instrument_type);
+            definition_builder.append_value(definition);
+            ts_event_builder.append_value(instrument.ts_event().as_u64());
+            ts_init_builder.append_value(instrument.ts_init().as_u64());
+        }
+
+        RecordBatch::try_new(
+            Self::get_schema(Some(metadata.clone())).into(),
+            vec![
+                Arc::new(instrument_id_builder.finish()),
+                Arc::new(instrument_type_builder.finish()),
+                Arc::new(definition_builder.finish()),
+                Arc::new(ts_event_builder.finish()),
+                Arc::new(ts_init_builder.finish()),
+            ],
+        )
+    }
+}
+
+/// Decodes the instrument definitions of the record `batch`, which must have the
+/// schema of [`InstrumentAny`].
+///
+/// # Errors
+///
+/// This function returns an error:
+/// - If a column is missing or of the wrong type.
+/// - If an instrument type is unknown, or its definition fails to parse.
+pub fn decode_instruments(batch: &RecordBatch) -> Result<Vec<InstrumentAny>, PersistenceError> {
+    let cols = batch.columns();
+    let instrument_type_values =
+        extract_column::<StringArray>(cols, "instrument_type", 1, DataType::Utf8)?;
+    let definition_values = extract_column::<StringArray>(cols, "definition", 2, DataType::Utf8)?;
+
+    let instruments = (0..batch.num_rows())
+        .map(|i| {
+            let definition = definition_values.value(i);
+            let instrument = match instrument_type_values.value(i) {
+                "CryptoFuture" => InstrumentAny::CryptoFuture(from_json(definition)?),
+                "CryptoPerpetual" => InstrumentAny::CryptoPerpetual(from_json(definition)?),
+                "CurrencyPair" => InstrumentAny::CurrencyPair(from_json(definition)?),
+                "Equity" => InstrumentAny::Equity(from_json(definition)?),
+                "FuturesContract" => InstrumentAny::FuturesContract(from_json(definition)?),
+                "FuturesSpread" => InstrumentAny::FuturesSpread(from_json(definition)?),
+                "OptionsContract" => InstrumentAny::OptionsContract(from_json(definition)?),
+                "OptionsSpread" => InstrumentAny::OptionsSpread(from_json(definition)?),
+                other => {
+                    return Err(EncodingError::ParseError(
+                        "instrument_type",
+                        format!("Unknown instrument type, was {other}"),
+                    ))
+                }
+            };
+            Ok(instrument)
+        })
+        .collect::<Result<_, EncodingError>>()?;
+    Ok(instruments)
+}
+
+fn to_json<T: Serialize>(instrument: &T) -> Result<String, ArrowError> {
+    serde_json::to_string(instrument).map_err(|e| ArrowError::JsonError(e.to_string()))
+}
+
+fn from_json<T: serde::de::DeserializeOwned>(definition: &str) -> Result<T, EncodingError> {
+    serde_json::from_str(definition)
+        .map_err(|e| EncodingError::ParseError("definition", e.to_string()))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod tests {
+    use nautilus_model::instruments::stubs::{
+        currency_pair_btcusdt, equity_aapl, options_contract_appl,
+    };
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    fn test_encode_decode_round_trip() {
+        let instruments = vec![
+            InstrumentAny::CurrencyPair(currency_pair_btcusdt()),
+            InstrumentAny::Equity(equity_aapl()),
+            InstrumentAny::OptionsContract(options_contract_appl()),
+        ];
+
+        let batch = InstrumentAny::encode_batch(&HashMap::new(), &instruments).unwrap();
+        let decoded = decode_instruments(&batch).unwrap();
+
+        assert_eq!(batch.schema().as_ref(), &InstrumentAny::get_schema(None));
+        assert_eq!(decoded, instruments);
+        let definitions = batch
+            .column(2)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        let InstrumentAny::Equity(equity) = &decoded[1] else {
+            panic!("Expected equity");
+        };
+        assert_eq!(definitions.value(1), to_json(equity).unwrap());
+    }
+
+    #[rstest]
+    fn test_decode_unknown_type_errors() {
+        let batch = RecordBatch::try_new(
+            InstrumentAny::get_schema(None).into(),
+            vec![
+                Arc::new(StringArray::from(vec!["AAPL.XNAS"])),
+                Arc::new(StringArray::from(vec!["Bond"])),
+                Arc::new(StringArray::from(vec!["{}"])),
+                Arc::new(UInt64Array::from(vec![0])),
+                Arc::new(UInt64Array::from(vec![0])),
+            ],
+        )
+        .unwrap();
+
+        let result = decode_instruments(&batch);
+
+        assert!(matches!(
+            result,
+            Err(PersistenceError::Encoding(EncodingError::ParseError(
+                "instrument_type",
+                _
+            )))
+        ));
+    }
+}
@@ -18,6 +18,7 @@
 pub mod bar;
 pub mod delta;
 pub mod depth;
+pub mod instrument;
 pub mod quote;
 pub mod trade;
 
@@ -33,14 +34,19 @@ use datafusion::arrow::{
     ipc::writer::StreamWriter,
     record_batch::RecordBatch,
 };
-use nautilus_model::data::Data;
+use nautilus_model::data::{
+    bar::Bar, delta::OrderBookDelta, depth::OrderBookDepth10, quote::QuoteTick, trade::TradeTick,
+    Data,
+};
 use pyo3::prelude::*;
 
+use crate::error::PersistenceError;
+
 // Define metadata key constants constants
-const KEY_BAR_TYPE: &str = "bar_type";
-const KEY_INSTRUMENT_ID: &str = "instrument_id";
-const KEY_PRICE_PRECISION: &str = "price_precision";
-const KEY_SIZE_PRECISION: &str = "size_precision";
+pub(crate) const KEY_BAR_TYPE: &str = "bar_type";
+pub(crate) const KEY_INSTRUMENT_ID: &str = "instrument_id";
+pub(crate) const KEY_PRICE_PRECISION: &str = "price_precision";
+pub(crate) const KEY_SIZE_PRECISION: &str = "size_precision";
 
 #[derive(thiserror::Error, Debug)]
 pub enum DataStreamingError {
@@ -50,6 +56,28 @@ pub enum DataStreamingError {
     IoError(#[from] io::Error),
     #[error("Python error: {0}")]
     PythonError(#[from] PyErr),
+    #[error("Parquet error: {0}")]
+    ParquetError(#[from] datafusion::parquet::errors::ParquetError),
+    #[error("Encoding error: {0}")]
+    EncodingError(#[from] EncodingError),
+    #[error("Unsupported data type: `{0}`")]
+    UnsupportedType(&'static str),
+    #[error("`data` was empty")]
+    EmptyData,
+    #[error("`data` contained more than one data type")]
+    MixedDataTypes,
+    #[error("`data` contained more than one {0}")]
+    MixedMetadata(&'static str),
+    #[error("`data` was not monotonically increasing by the `ts_init` field")]
+    NotMonotonic,
+    #[error("Invalid ZSTD compression level {0}: must be within 1 to 22")]
+    InvalidCompressionLevel(i32),
+    #[error("Appended `ts_init` {ts_init} is before the file's max `ts_init` {max_ts_init}")]
+    AppendNotMonotonic { max_ts_init: u64, ts_init: u64 },
+    #[error("Appended data schema does not match the file schema")]
+    AppendSchemaMismatch,
+    #[error("Input files have different schema metadata")]
+    MetadataMismatch,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -82,6 +110,45 @@ pub trait ArrowSchemaProvider {
     }
 }
 
+/// Returns the Arrow schema of the [`QuoteTick`] records of a file, without
+/// metadata.
+///
+/// A file written with this schema must also carry the `instrument_id`,
+/// `price_precision` and `size_precision` schema metadata to be decoded, as
+/// for each of the schemas below, with the `bar_type` in place of the
+/// `instrument_id` for bars.
+#[must_use]
+pub fn quote_schema() -> Schema {
+    QuoteTick::get_schema(None)
+}
+
+/// Returns the Arrow schema of the [`TradeTick`] records of a file, without
+/// metadata.
+#[must_use]
+pub fn trade_schema() -> Schema {
+    TradeTick::get_schema(None)
+}
+
+/// Returns the Arrow schema of the [`Bar`] records of a file, without metadata.
+#[must_use]
+pub fn bar_schema() -> Schema {
+    Bar::get_schema(None)
+}
+
+/// Returns the Arrow schema of the [`OrderBookDelta`] records of a file, without
+/// metadata.
+#[must_use]
+pub fn delta_schema() -> Schema {
+    OrderBookDelta::get_schema(None)
+}
+
+/// Returns the Arrow schema of the [`OrderBookDepth10`] records of a file,
+/// without metadata.
+#[must_use]
+pub fn depth_schema() -> Schema {
+    OrderBookDepth10::get_schema(None)
+}
+
 pub trait EncodeToRecordBatch
 where
     Self: Sized + ArrowSchemaProvider,
@@ -92,6 +159,11 @@ where
     ) -> Result<RecordBatch, ArrowError>;
 }
 
+/// Decodes a record batch into values of `Self`.
+///
+/// The instrument ID or bar type is parsed once per batch from the schema
+/// metadata. Identifiers wrap interned `Ustr` strings, so every decoded value
+/// shares the same storage for them rather than holding a copy.
 pub trait DecodeFromRecordBatch
 where
     Self: Sized + Into<Data> + ArrowSchemaProvider,
@@ -125,6 +197,213 @@ impl<T: EncodeToRecordBatch + Write> WriteStream for T {
     }
 }
 
+/// Decodes the record `batch` into [`Data`] of type `T`, independently of a
+/// DataFusion session.
+///
+/// The columns of `batch` are matched by name to the fields of the Arrow schema
+/// of `T`, so they may be in any order and additional columns are ignored. The
+/// instrument ID or bar type and the precisions are read from the schema
+/// metadata of `batch`, as written by `get_metadata` for `T`.
+///
+/// # Errors
+///
+/// This function returns an error:
+/// - If a field of `T` has no column in `batch`, or a column of another type.
+/// - If the schema metadata of `batch` is missing or invalid.
+pub fn decode_batch<T: DecodeDataFromRecordBatch>(
+    batch: &RecordBatch,
+) -> Result<Vec<Data>, PersistenceError> {
+    let schema = batch.schema();
+    let projection = T::get_schema(None)
+        .fields()
+        .iter()
+        .map(|field| schema.index_of(field.name()))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(T::decode_data_batch(
+        schema.metadata(),
+        batch.project(&projection)?,
+    )?)
+}
+
+/// Encodes the given `data` into a single record batch with the Arrow schema of
+/// its data type, as the inverse of [`decode_batch`].
+///
+/// The schema metadata holds the instrument ID or bar type and the precisions
+/// of the first element, so the batch can be written to a parquet file and read
+/// back with a [`crate::backend::session::DataBackendSession`].
+///
+/// # Errors
+///
+/// This function returns an error:
+/// - If `data` is empty.
+/// - If `data` contains more than one data type.
+/// - If `data` contains more than one instrument ID, bar type or precision,
+///   which the schema metadata cannot represent.
+/// - If `data` contains `OrderBookDeltas`, which have no Arrow schema.
+pub fn encode_batch(data: &[Data]) -> Result<RecordBatch, PersistenceError> {
+    let first = data.first().ok_or(DataStreamingError::EmptyData)?;
+
+    let batch = match first {
+        Data::Delta(_) => {
+            let deltas = collect_variant(data, |data| match data {
+                Data::Delta(delta) => Some(*delta),
+                _ => None,
+            })?;
+            // Use the precision of the first delta with an order, as a clear has none
+            let (price_precision, size_precision) = deltas
+                .iter()
+                .find(|delta| delta.order.price.precision != 0 || delta.order.size.precision != 0)
+                .map_or((0, 0), |delta| {
+                    (delta.order.price.precision, delta.order.size.precision)
+                });
+            check_metadata(
+                deltas.iter().map(|delta| delta.instrument_id),
+                deltas[0].instrument_id,
+                "instrument ID",
+            )?;
+            check_metadata(
+                deltas
+                    .iter()
+                    .map(|delta| (delta.order.price.precision, delta.order.size.precision))
+                    .filter(|precisions| *precisions != (0, 0)),
+                (price_precision, size_precision),
+                "precision",
+            )?;
+            let metadata = OrderBookDelta::get_metadata(
+                &deltas[0].instrument_id,
+                price_precision,
+                size_precision,
+            );
+            OrderBookDelta::encode_batch(&metadata, &deltas)?
+        }
+        Data::Depth10(_) => {
+            let depths = collect_variant(data, |data| match data {
+                Data::Depth10(depth) => Some(*depth),
+                _ => None,
+            })?;
+            check_metadata(
+                depths.iter().map(|depth| depth.instrument_id),
+                depths[0].instrument_id,
+                "instrument ID",
+            )?;
+            check_metadata(
+                depths
+                    .iter()
+                    .map(|depth| (depth.bids[0].price.precision, depth.bids[0].size.precision)),
+                (
+                    depths[0].bids[0].price.precision,
+                    depths[0].bids[0].size.precision,
+                ),
+                "precision",
+            )?;
+            let metadata = OrderBookDepth10::get_metadata(
+                &depths[0].instrument_id,
+                depths[0].bids[0].price.precision,
+                depths[0].bids[0].size.precision,
+            );
+            OrderBookDepth10::encode_batch(&metadata, &depths)?
+        }
+        Data::Quote(_) => {
+            let quotes = collect_variant(data, |data| match data {
+                Data::Quote(quote) => Some(*quote),
+                _ => None,
+            })?;
+            check_metadata(
+                quotes.iter().map(|quote| quote.instrument_id),
+                quotes[0].instrument_id,
+                "instrument ID",
+            )?;
+            check_metadata(
+                quotes
+                    .iter()
+                    .map(|quote| (quote.bid_price.precision, quote.bid_size.precision)),
+                (quotes[0].bid_price.precision, quotes[0].bid_size.precision),
+                "precision",
+            )?;
+            let metadata = QuoteTick::get_metadata(
+                &quotes[0].instrument_id,
+                quotes[0].bid_price.precision,
+                quotes[0].bid_size.precision,
+            );
+            QuoteTick::encode_batch(&metadata, &quotes)?
+        }
+        Data::Trade(_) => {
+            let trades = collect_variant(data, |data| match data {
+                Data::Trade(trade) => Some(*trade),
+                _ => None,
+            })?;
+            check_metadata(
+                trades.iter().map(|trade| trade.instrument_id),
+                trades[0].instrument_id,
+                "instrument ID",
+            )?;
+            check_metadata(
+                trades
+                    .iter()
+                    .map(|trade| (trade.price.precision, trade.size.precision)),
+                (trades[0].price.precision, trades[0].size.precision),
+                "precision",
+            )?;
+            let metadata = TradeTick::get_metadata(
+                &trades[0].instrument_id,
+                trades[0].price.precision,
+                trades[0].size.precision,
+            );
+            TradeTick::encode_batch(&metadata, &trades)?
+        }
+        Data::Bar(_) => {
+            let bars = collect_variant(data, |data| match data {
+                Data::Bar(bar) => Some(*bar),
+                _ => None,
+            })?;
+            check_metadata(
+                bars.iter().map(|bar| bar.bar_type),
+                bars[0].bar_type,
+                "bar type",
+            )?;
+            check_metadata(
+                bars.iter()
+                    .map(|bar| (bar.open.precision, bar.volume.precision)),
+                (bars[0].open.precision, bars[0].volume.precision),
+                "precision",
+            )?;
+            let metadata = Bar::get_metadata(
+                &bars[0].bar_type,
+                bars[0].open.precision,
+                bars[0].volume.precision,
+            );
+            Bar::encode_batch(&metadata, &bars)?
+        }
+        Data::Deltas(_) => return Err(PersistenceError::UnsupportedType("OrderBookDeltas")),
+    };
+
+    Ok(batch)
+}
+
+/// Collects the inner values of `data`, returning an error if any element is not
+/// of the variant matched by `extract`.
+pub(crate) fn collect_variant<'a, T>(
+    data: &'a [Data],
+    extract: impl Fn(&'a Data) -> Option<T>,
+) -> Result<Vec<T>, DataStreamingError> {
+    data.iter()
+        .map(|item| extract(item).ok_or(DataStreamingError::MixedDataTypes))
+        .collect()
+}
+
+/// Returns an error naming `field` if any of the `values` differs from the
+/// `expected` value written to the schema metadata.
+fn check_metadata<T: PartialEq>(
+    mut values: impl Iterator<Item = T>,
+    expected: T,
+    field: &'static str,
+) -> Result<(), DataStreamingError> {
+    if values.any(|value| value != expected) {
+        return Err(DataStreamingError::MixedMetadata(field));
+    }
+    Ok(())
+}
+
 pub fn extract_column<'a, T: Array + 'static>(
     cols: &'a [ArrayRef],
     column_key: &'static str,
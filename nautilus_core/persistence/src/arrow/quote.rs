@@ -178,6 +178,7 @@ mod tests {
     use rstest::rstest;
 
     use super::*;
+    use crate::arrow::decode_batch;
 
     #[rstest]
     fn test_get_schema() {
@@ -295,4 +296,27 @@ mod tests {
         let decoded_data = QuoteTick::decode_batch(&metadata, record_batch).unwrap();
         assert_eq!(decoded_data.len(), 2);
     }
+
+    #[rstest]
+    fn test_decode_batch_to_data() {
+        let instrument_id = InstrumentId::from("AAPL.XNAS");
+        let metadata = QuoteTick::get_metadata(&instrument_id, 2, 0);
+        let quote = QuoteTick {
+            instrument_id,
+            bid_price: Price::from("100.10"),
+            ask_price: Price::from("101.50"),
+            bid_size: Quantity::from(1000),
+            ask_size: Quantity::from(500),
+            ts_event: 1.into(),
+            ts_init: 3.into(),
+        };
+        let batch = QuoteTick::encode_batch(&metadata, &[quote, quote]).unwrap();
+
+        // Reverse the columns, which are matched by name
+        let reversed: Vec<usize> = (0..batch.num_columns()).rev().collect();
+        let batch = batch.project(&reversed).unwrap();
+        let decoded = decode_batch::<QuoteTick>(&batch).unwrap();
+
+        assert_eq!(decoded, vec![Data::Quote(quote), Data::Quote(quote)]);
+    }
 }
@@ -0,0 +1,247 @@
+// -------------------------------------------------------------------------------------------------
+//  Copyright (C) 2015-2024 Nautech Systems Pty Ltd. All rights reserved.
+//  https://nautechsystems.io
+//
+//  Licensed under the GNU Lesser General Public License Version 3.0 (the "License");
+//  You may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at https://www.gnu.org/licenses/lgpl-3.0.en.html
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+// -------------------------------------------------------------------------------------------------
+
+//! Provides a least recently used cache of decoded query results.
+
+use std::{collections::HashMap, mem::size_of, sync::Arc};
+
+use nautilus_model::data::Data;
+
+use super::session::NautilusDataType;
+
+/// The key of a cached query, which determines the data it decodes to.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct QueryCacheKey {
+    table_name: String,
+    sql_query: String,
+    data_type: NautilusDataType,
+    metadata: Vec<(String, String)>,
+}
+
+impl QueryCacheKey {
+    /// Creates a new [`QueryCacheKey`] instance.
+    ///
+    /// The whitespace of `sql_query` is normalized, so queries which only differ
+    /// in formatting share an entry.
+    pub(crate) fn new(
+        table_name: &str,
+        sql_query: &str,
+        data_type: NautilusDataType,
+        metadata: Option<&HashMap<String, String>>,
+    ) -> Self {
+        let mut metadata: Vec<_> = metadata
+            .into_iter()
+            .flatten()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        metadata.sort();
+        Self {
+            table_name: table_name.to_string(),
+            sql_query: sql_query.split_whitespace().collect::<Vec<_>>().join(" "),
+            data_type,
+            metadata,
+        }
+    }
+}
+
+/// A decoded query result held in the cache.
+#[derive(Clone, Debug)]
+pub(crate) struct CachedQuery {
+    pub(crate) chunks: Arc<Vec<Vec<Data>>>,
+    pub(crate) size_hint: Option<u64>,
+    size_bytes: usize,
+    last_used: u64,
+}
+
+/// The statistics of the query cache of a session.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// The number of queries served from the cache.
+    pub hits: u64,
+    /// The number of queries which were decoded as they were not cached.
+    pub misses: u64,
+    /// The number of cached queries.
+    pub entries: usize,
+    /// The estimated size of the cached data in bytes.
+    pub size_bytes: usize,
+}
+
+/// A cache of decoded query results, bounded by an estimate of their size in
+/// bytes and evicting the least recently used results first.
+///
+/// The size of a result is estimated as the in-memory size of its [`Data`]
+/// elements, which is exact for all data types other than `OrderBookDeltas`.
+#[derive(Debug)]
+pub(crate) struct QueryCache {
+    capacity_bytes: usize,
+    entries: HashMap<QueryCacheKey, CachedQuery>,
+    clock: u64,
+    stats: CacheStats,
+}
+
+impl QueryCache {
+    /// Creates a new empty [`QueryCache`] instance.
+    pub(crate) fn new(capacity_bytes: usize) -> Self {
+        Self {
+            capacity_bytes,
+            entries: HashMap::new(),
+            clock: 0,
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Returns the cached result for `key`, counting a hit or a miss.
+    pub(crate) fn get(&mut self, key: &QueryCacheKey) -> Option<CachedQuery> {
+        self.clock += 1;
+        match self.entries.get_mut(key) {
+            Some(entry) => {
+                entry.last_used = self.clock;
+                self.stats.hits += 1;
+                Some(entry.clone())
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Inserts the decoded `chunks` of the query for `key`, evicting the least
+    /// recently used results until they fit.
+    ///
+    /// A result larger than the whole capacity is not cached.
+    pub(crate) fn insert(
+        &mut self,
+        key: QueryCacheKey,
+        chunks: Vec<Vec<Data>>,
+        size_hint: Option<u64>,
+    ) {
+        let size_bytes = chunks.iter().map(Vec::len).sum::<usize>() * size_of::<Data>();
+        if size_bytes > self.capacity_bytes {
+            return;
+        }
+
+        self.remove(&key);
+        while self.stats.size_bytes + size_bytes > self.capacity_bytes {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            self.remove(&oldest);
+        }
+
+        self.clock += 1;
+        self.stats.size_bytes += size_bytes;
+        self.entries.insert(
+            key,
+            CachedQuery {
+                chunks: Arc::new(chunks),
+                size_hint,
+                size_bytes,
+                last_used: self.clock,
+            },
+        );
+        self.stats.entries = self.entries.len();
+    }
+
+    /// Removes the cached results of the queries on `table_name`.
+    pub(crate) fn invalidate_table(&mut self, table_name: &str) {
+        self.entries.retain(|key, _| key.table_name != table_name);
+        self.update_size();
+    }
+
+    /// Removes all cached results, keeping the hit and miss counts.
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.update_size();
+    }
+
+    pub(crate) const fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    fn remove(&mut self, key: &QueryCacheKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.stats.size_bytes -= entry.size_bytes;
+            self.stats.entries = self.entries.len();
+        }
+    }
+
+    fn update_size(&mut self) {
+        self.stats.entries = self.entries.len();
+        self.stats.size_bytes = self.entries.values().map(|entry| entry.size_bytes).sum();
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod tests {
+    use nautilus_model::data::stubs::quote_tick_audusd_sim;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn key(table_name: &str) -> QueryCacheKey {
+        QueryCacheKey::new(
+            table_name,
+            &format!("SELECT *  FROM\n{table_name}"),
+            NautilusDataType::QuoteTick,
+            None,
+        )
+    }
+
+    fn chunks(len: usize) -> Vec<Vec<Data>> {
+        vec![vec![Data::Quote(quote_tick_audusd_sim()); len]]
+    }
+
+    #[rstest]
+    fn test_key_normalizes_whitespace() {
+        let normalized =
+            QueryCacheKey::new("q", "SELECT * FROM q", NautilusDataType::QuoteTick, None);
+
+        assert_eq!(key("q"), normalized);
+    }
+
+    #[rstest]
+    fn test_insert_evicts_least_recently_used() {
+        let mut cache = QueryCache::new(10 * size_of::<Data>());
+        cache.insert(key("a"), chunks(4), None);
+        cache.insert(key("b"), chunks(4), None);
+        assert!(cache.get(&key("a")).is_some());
+
+        cache.insert(key("c"), chunks(4), None);
+
+        assert!(cache.get(&key("a")).is_some());
+        assert!(cache.get(&key("b")).is_none());
+        assert!(cache.get(&key("c")).is_some());
+        assert_eq!(cache.stats().entries, 2);
+        assert_eq!(cache.stats().size_bytes, 8 * size_of::<Data>());
+    }
+
+    #[rstest]
+    fn test_insert_larger_than_capacity_is_skipped() {
+        let mut cache = QueryCache::new(2 * size_of::<Data>());
+        cache.insert(key("a"), chunks(3), None);
+
+        assert!(cache.get(&key("a")).is_none());
+        assert_eq!(cache.stats().size_bytes, 0);
+    }
+}
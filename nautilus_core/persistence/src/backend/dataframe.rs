@@ -0,0 +1,149 @@
+// -------------------------------------------------------------------------------------------------
+//  Copyright (C) 2015-2024 Nautech Systems Pty Ltd. All rights reserved.
+//  https://nautechsystems.io
+//
+//  Licensed under the GNU Lesser General Public License Version 3.0 (the "License");
+//  You may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at https://www.gnu.org/licenses/lgpl-3.0.en.html
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+// -------------------------------------------------------------------------------------------------
+
+//! Provides the conversion of query results into [polars](https://pola.rs) data frames.
+
+use nautilus_model::data::{
+    bar::Bar, delta::OrderBookDelta, depth::OrderBookDepth10, quote::QuoteTick, trade::TradeTick,
+    Data,
+};
+use polars::{frame::DataFrame, prelude::NamedFrom, series::Series};
+
+use super::session::QueryResult;
+use crate::{arrow::collect_variant, error::PersistenceError};
+
+impl QueryResult {
+    /// Consumes the result into a polars [`DataFrame`] with a column per field
+    /// of its data type.
+    ///
+    /// Prices and quantities are converted to `f64`, enums and identifiers to
+    /// strings, and timestamps kept as `u64` nanoseconds. The levels of order
+    /// book depth are flattened into columns such as `bid_price_0`. An empty
+    /// result gives an empty data frame.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error:
+    /// - If a record batch of the result failed to decode.
+    /// - If the result holds more than one data type.
+    /// - If the result holds `OrderBookDeltas`, which has no columnar form.
+    pub fn to_polars(self) -> Result<DataFrame, PersistenceError> {
+        let data = self.flatten_results().collect::<Result<Vec<_>, _>>()?;
+        let Some(first) = data.first() else {
+            return Ok(DataFrame::empty());
+        };
+
+        let columns = match first {
+            Data::Delta(_) => delta_columns(&collect_variant(&data, Data::as_delta)?),
+            Data::Depth10(_) => depth_columns(&collect_variant(&data, Data::as_depth10)?),
+            Data::Quote(_) => quote_columns(&collect_variant(&data, Data::as_quote)?),
+            Data::Trade(_) => trade_columns(&collect_variant(&data, Data::as_trade)?),
+            Data::Bar(_) => bar_columns(&collect_variant(&data, Data::as_bar)?),
+            Data::Deltas(_) => return Err(PersistenceError::UnsupportedType("OrderBookDeltas")),
+        };
+        Ok(DataFrame::new(columns)?)
+    }
+}
+
+/// Returns a column named `name` of `f` applied to each of `items`.
+fn column<T, V>(name: &str, items: &[&T], f: impl Fn(&T) -> V) -> Series
+where
+    Series: NamedFrom<Vec<V>, [V]>,
+{
+    Series::new(name, items.iter().map(|item| f(item)).collect::<Vec<_>>())
+}
+
+fn delta_columns(deltas: &[&OrderBookDelta]) -> Vec<Series> {
+    vec![
+        column("instrument_id", deltas, |d| d.instrument_id.to_string()),
+        column("action", deltas, |d| d.action.to_string()),
+        column("side", deltas, |d| d.order.side.to_string()),
+        column("price", deltas, |d| d.order.price.as_f64()),
+        column("size", deltas, |d| d.order.size.as_f64()),
+        column("order_id", deltas, |d| d.order.order_id),
+        column("flags", deltas, |d| u32::from(d.flags)),
+        column("sequence", deltas, |d| d.sequence),
+        column("ts_event", deltas, |d| d.ts_event.as_u64()),
+        column("ts_init", deltas, |d| d.ts_init.as_u64()),
+    ]
+}
+
+fn depth_columns(depths: &[&OrderBookDepth10]) -> Vec<Series> {
+    let mut columns = vec![column("instrument_id", depths, |d| {
+        d.instrument_id.to_string()
+    })];
+    for i in 0..depths[0].bids.len() {
+        columns.extend([
+            column(&format!("bid_price_{i}"), depths, |d| {
+                d.bids[i].price.as_f64()
+            }),
+            column(&format!("ask_price_{i}"), depths, |d| {
+                d.asks[i].price.as_f64()
+            }),
+            column(&format!("bid_size_{i}"), depths, |d| {
+                d.bids[i].size.as_f64()
+            }),
+            column(&format!("ask_size_{i}"), depths, |d| {
+                d.asks[i].size.as_f64()
+            }),
+            column(&format!("bid_count_{i}"), depths, |d| d.bid_counts[i]),
+            column(&format!("ask_count_{i}"), depths, |d| d.ask_counts[i]),
+        ]);
+    }
+    columns.extend([
+        column("flags", depths, |d| u32::from(d.flags)),
+        column("sequence", depths, |d| d.sequence),
+        column("ts_event", depths, |d| d.ts_event.as_u64()),
+        column("ts_init", depths, |d| d.ts_init.as_u64()),
+    ]);
+    columns
+}
+
+fn quote_columns(quotes: &[&QuoteTick]) -> Vec<Series> {
+    vec![
+        column("instrument_id", quotes, |q| q.instrument_id.to_string()),
+        column("bid_price", quotes, |q| q.bid_price.as_f64()),
+        column("ask_price", quotes, |q| q.ask_price.as_f64()),
+        column("bid_size", quotes, |q| q.bid_size.as_f64()),
+        column("ask_size", quotes, |q| q.ask_size.as_f64()),
+        column("ts_event", quotes, |q| q.ts_event.as_u64()),
+        column("ts_init", quotes, |q| q.ts_init.as_u64()),
+    ]
+}
+
+fn trade_columns(trades: &[&TradeTick]) -> Vec<Series> {
+    vec![
+        column("instrument_id", trades, |t| t.instrument_id.to_string()),
+        column("price", trades, |t| t.price.as_f64()),
+        column("size", trades, |t| t.size.as_f64()),
+        column("aggressor_side", trades, |t| t.aggressor_side.to_string()),
+        column("trade_id", trades, |t| t.trade_id.to_string()),
+        column("ts_event", trades, |t| t.ts_event.as_u64()),
+        column("ts_init", trades, |t| t.ts_init.as_u64()),
+    ]
+}
+
+fn bar_columns(bars: &[&Bar]) -> Vec<Series> {
+    vec![
+        column("bar_type", bars, |b| b.bar_type.to_string()),
+        column("open", bars, |b| b.open.as_f64()),
+        column("high", bars, |b| b.high.as_f64()),
+        column("low", bars, |b| b.low.as_f64()),
+        column("close", bars, |b| b.close.as_f64()),
+        column("volume", bars, |b| b.volume.as_f64()),
+        column("ts_event", bars, |b| b.ts_event.as_u64()),
+        column("ts_init", bars, |b| b.ts_init.as_u64()),
+    ]
+}
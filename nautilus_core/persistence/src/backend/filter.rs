@@ -0,0 +1,172 @@
+// -------------------------------------------------------------------------------------------------
+//  Copyright (C) 2015-2024 Nautech Systems Pty Ltd. All rights reserved.
+//  https://nautechsystems.io
+//
+//  Licensed under the GNU Lesser General Public License Version 3.0 (the "License");
+//  You may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at https://www.gnu.org/licenses/lgpl-3.0.en.html
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+// -------------------------------------------------------------------------------------------------
+
+//! Provides a builder of structured query predicates, as an alternative to SQL strings.
+
+use datafusion::{
+    common::{Column, ScalarValue},
+    logical_expr::{BinaryExpr, Expr, Operator},
+    prelude::lit,
+    sql::unparser::expr_to_sql,
+};
+use nautilus_model::types::fixed::FIXED_SCALAR;
+
+use super::session::FIXED_POINT_FIELDS;
+use crate::error::PersistenceError;
+
+/// A predicate on the columns of a query, such as
+/// `Filter::col("price").gt(lit(1.05))`.
+///
+/// Literals compared with a price or size field are given as decimal values,
+/// and are scaled to the fixed-point integers stored in the file. Column names
+/// are never parsed, so a filter cannot inject SQL into a query.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Filter {
+    expr: Expr,
+}
+
+/// A column of a [`Filter`], to be compared with a literal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FilterColumn {
+    name: String,
+}
+
+impl Filter {
+    /// Returns the column `name` to build a comparison on.
+    #[must_use]
+    pub fn col(name: &str) -> FilterColumn {
+        FilterColumn {
+            name: name.to_string(),
+        }
+    }
+
+    /// Returns a filter matching the rows which match both this and `other`.
+    #[must_use]
+    pub fn and(self, other: Self) -> Self {
+        Self {
+            expr: self.expr.and(other.expr),
+        }
+    }
+
+    /// Returns a filter matching the rows which match either this or `other`.
+    #[must_use]
+    pub fn or(self, other: Self) -> Self {
+        Self {
+            expr: self.expr.or(other.expr),
+        }
+    }
+
+    /// Returns the DataFusion expression of the filter.
+    #[must_use]
+    pub const fn expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    /// Returns the filter as the SQL text of a `WHERE` clause.
+    pub(crate) fn to_sql(&self) -> Result<String, PersistenceError> {
+        Ok(expr_to_sql(&self.expr)?.to_string())
+    }
+}
+
+impl FilterColumn {
+    /// Returns a filter matching the rows where the column equals `value`.
+    #[must_use]
+    pub fn eq(self, value: Expr) -> Filter {
+        self.compare(Operator::Eq, value)
+    }
+
+    /// Returns a filter matching the rows where the column differs from `value`.
+    #[must_use]
+    pub fn not_eq(self, value: Expr) -> Filter {
+        self.compare(Operator::NotEq, value)
+    }
+
+    /// Returns a filter matching the rows where the column is less than `value`.
+    #[must_use]
+    pub fn lt(self, value: Expr) -> Filter {
+        self.compare(Operator::Lt, value)
+    }
+
+    /// Returns a filter matching the rows where the column is at most `value`.
+    #[must_use]
+    pub fn lt_eq(self, value: Expr) -> Filter {
+        self.compare(Operator::LtEq, value)
+    }
+
+    /// Returns a filter matching the rows where the column is greater than `value`.
+    #[must_use]
+    pub fn gt(self, value: Expr) -> Filter {
+        self.compare(Operator::Gt, value)
+    }
+
+    /// Returns a filter matching the rows where the column is at least `value`.
+    #[must_use]
+    pub fn gt_eq(self, value: Expr) -> Filter {
+        self.compare(Operator::GtEq, value)
+    }
+
+    fn compare(self, op: Operator, value: Expr) -> Filter {
+        let value = if FIXED_POINT_FIELDS.contains(&self.name.as_str()) {
+            to_fixed_point(value)
+        } else {
+            value
+        };
+        let column = Expr::Column(Column::new_unqualified(self.name));
+        Filter {
+            expr: Expr::BinaryExpr(BinaryExpr::new(Box::new(column), op, Box::new(value))),
+        }
+    }
+}
+
+/// Scales a decimal literal `value` to a fixed-point integer literal, returning
+/// any other expression unchanged.
+fn to_fixed_point(value: Expr) -> Expr {
+    let decimal = match &value {
+        Expr::Literal(ScalarValue::Float64(Some(v))) => *v,
+        Expr::Literal(ScalarValue::Int64(Some(v))) => *v as f64,
+        Expr::Literal(ScalarValue::Int32(Some(v))) => f64::from(*v),
+        _ => return value,
+    };
+    lit((decimal * FIXED_SCALAR).round() as i64)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    fn test_fixed_point_literal_is_scaled() {
+        let filter = Filter::col("price").gt(lit(1.05));
+
+        assert_eq!(filter.to_sql().unwrap(), "(price > 1050000000)");
+    }
+
+    #[rstest]
+    fn test_column_name_is_not_parsed() {
+        let filter = Filter::col("ts_init; DROP TABLE t")
+            .gt_eq(lit(1_u64))
+            .and(Filter::col("flags").eq(lit(0_u8)));
+
+        assert_eq!(
+            filter.to_sql().unwrap(),
+            "((\"ts_init; DROP TABLE t\" >= 1) AND (flags = 0))"
+        );
+    }
+}
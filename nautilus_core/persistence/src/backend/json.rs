@@ -0,0 +1,106 @@
+// -------------------------------------------------------------------------------------------------
+//  Copyright (C) 2015-2024 Nautech Systems Pty Ltd. All rights reserved.
+//  https://nautechsystems.io
+//
+//  Licensed under the GNU Lesser General Public License Version 3.0 (the "License");
+//  You may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at https://www.gnu.org/licenses/lgpl-3.0.en.html
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+// -------------------------------------------------------------------------------------------------
+
+//! Provides the export of query results as JSON, for debugging.
+
+use std::{fs::File, io::BufWriter, path::Path};
+
+use nautilus_model::data::Data;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use super::session::QueryResult;
+use crate::error::PersistenceError;
+
+/// The key of the variant tag in the JSON object of a [`Data`] element.
+pub const JSON_TYPE_TAG: &str = "type";
+
+/// Converts `data` into a JSON array with an object per element.
+///
+/// Each object holds the fields of the element as serialized by serde, and a
+/// [`JSON_TYPE_TAG`] field naming its type, such as `"Bar"`, so the output is
+/// self-describing.
+///
+/// # Errors
+///
+/// This function returns an error if an element fails to serialize.
+pub fn data_to_json(data: &[Data]) -> Result<Value, PersistenceError> {
+    data.iter()
+        .map(data_to_json_object)
+        .collect::<Result<Vec<_>, _>>()
+        .map(Value::Array)
+}
+
+impl QueryResult {
+    /// Consumes the result, writing up to its first `limit` elements, or all of
+    /// them if `None`, to the file at `path` as a pretty-printed JSON array.
+    ///
+    /// See [`data_to_json`] for the format of the elements.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error:
+    /// - If a record batch of the result failed to decode.
+    /// - If an element fails to serialize.
+    /// - If the file cannot be created or written.
+    pub fn write_json<P: AsRef<Path>>(
+        self,
+        path: P,
+        limit: Option<usize>,
+    ) -> Result<(), PersistenceError> {
+        let data = self
+            .flatten_results()
+            .take(limit.unwrap_or(usize::MAX))
+            .collect::<Result<Vec<_>, _>>()?;
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, &data_to_json(&data)?)?;
+        Ok(())
+    }
+}
+
+fn data_to_json_object(data: &Data) -> Result<Value, PersistenceError> {
+    match data {
+        Data::Delta(delta) => tagged("OrderBookDelta", delta),
+        Data::Deltas(deltas) => tagged(
+            "OrderBookDeltas",
+            &json!({
+                "instrument_id": deltas.instrument_id,
+                "deltas": deltas.deltas,
+                "flags": deltas.flags,
+                "sequence": deltas.sequence,
+                "ts_event": deltas.ts_event,
+                "ts_init": deltas.ts_init,
+            }),
+        ),
+        Data::Depth10(depth) => tagged("OrderBookDepth10", depth),
+        Data::Quote(quote) => tagged("QuoteTick", quote),
+        Data::Trade(trade) => tagged("TradeTick", trade),
+        Data::Bar(bar) => tagged("Bar", bar),
+    }
+}
+
+/// Serializes `value` into a JSON object, with its type `name` under the
+/// [`JSON_TYPE_TAG`] key.
+fn tagged<T: Serialize>(name: &str, value: &T) -> Result<Value, PersistenceError> {
+    let mut object = Map::new();
+    object.insert(JSON_TYPE_TAG.to_string(), Value::from(name));
+    match serde_json::to_value(value)? {
+        Value::Object(fields) => object.extend(fields),
+        value => {
+            object.insert("value".to_string(), value);
+        }
+    }
+    Ok(Value::Object(object))
+}
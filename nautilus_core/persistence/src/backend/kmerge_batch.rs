@@ -72,6 +72,9 @@ where
     I: Iterator<Item = IntoIter<T>>,
 {
     pub item: T,
+    /// The index of the iterator in the order it was pushed to the [`KMerge`],
+    /// which comparators can use to break ties deterministically.
+    pub index: usize,
     batch: I::Item,
     iter: I,
 }
@@ -80,12 +83,17 @@ impl<I, T> ElementBatchIter<I, T>
 where
     I: Iterator<Item = IntoIter<T>>,
 {
-    fn new_from_iter(mut iter: I) -> Option<Self> {
+    fn new_from_iter(mut iter: I, index: usize) -> Option<Self> {
         loop {
             match iter.next() {
                 Some(mut batch) => match batch.next() {
                     Some(item) => {
-                        break Some(Self { item, batch, iter });
+                        break Some(Self {
+                            item,
+                            index,
+                            batch,
+                            iter,
+                        });
                     }
                     None => continue,
                 },
@@ -100,6 +108,7 @@ where
     I: Iterator<Item = IntoIter<T>>,
 {
     heap: BinaryHeap<ElementBatchIter<I, T>, C>,
+    pushed: usize,
 }
 
 impl<I, T, C> KMerge<I, T, C>
@@ -111,11 +120,14 @@ where
     pub fn new(cmp: C) -> Self {
         Self {
             heap: BinaryHeap::from_vec_cmp(Vec::new(), cmp),
+            pushed: 0,
         }
     }
 
     pub fn push_iter(&mut self, s: I) {
-        if let Some(heap_elem) = ElementBatchIter::new_from_iter(s) {
+        let index = self.pushed;
+        self.pushed += 1;
+        if let Some(heap_elem) = ElementBatchIter::new_from_iter(s, index) {
             self.heap.push(heap_elem);
         }
     }
@@ -157,11 +169,7 @@ where
                                 None => continue,
                             }
                         } else {
-                            let ElementBatchIter {
-                                item,
-                                batch: _,
-                                iter: _,
-                            } = PeekMut::pop(heap_elem);
+                            let ElementBatchIter { item, .. } = PeekMut::pop(heap_elem);
                             break Some(item);
                         }
                     },
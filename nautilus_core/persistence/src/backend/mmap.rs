@@ -0,0 +1,238 @@
+// -------------------------------------------------------------------------------------------------
+//  Copyright (C) 2015-2024 Nautech Systems Pty Ltd. All rights reserved.
+//  https://nautechsystems.io
+//
+//  Licensed under the GNU Lesser General Public License Version 3.0 (the "License");
+//  You may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at https://www.gnu.org/licenses/lgpl-3.0.en.html
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+// -------------------------------------------------------------------------------------------------
+
+//! Provides an object store for local files which serves reads from memory maps.
+
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Display},
+    fs::{self, File},
+    ops::Range,
+    path::PathBuf,
+    sync::Mutex,
+    time::SystemTime,
+};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use memmap2::Mmap;
+use object_store::{
+    local::LocalFileSystem, path::Path, GetOptions, GetResult, ListResult, MultipartUpload,
+    ObjectMeta, ObjectStore, PutMultipartOpts, PutOptions, PutPayload, PutResult, Result,
+};
+
+/// A local file system object store which serves byte range reads from a memory
+/// map of the file, rather than reading them into new buffers.
+///
+/// The returned bytes are slices of the map, so the pages are shared with the
+/// operating system page cache and can be evicted under memory pressure. All
+/// other operations are delegated to a [`LocalFileSystem`].
+///
+/// The map of a file is kept for later reads, and is replaced once the length or
+/// modification time of the file changes.
+#[derive(Default)]
+pub struct MmapFileSystem {
+    inner: LocalFileSystem,
+    maps: Mutex<HashMap<PathBuf, (FileVersion, Bytes)>>,
+}
+
+/// The length and modification time of a mapped file.
+type FileVersion = (u64, Option<SystemTime>);
+
+impl MmapFileSystem {
+    /// Creates a new [`MmapFileSystem`] instance.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn map(&self, location: &Path) -> Result<Bytes> {
+        let path = self.inner.path_to_filesystem(location)?;
+        let metadata = fs::metadata(&path).map_err(|e| to_error(&path, e))?;
+        let version = (metadata.len(), metadata.modified().ok());
+
+        let mut maps = self.maps.lock().unwrap();
+        if let Some((_, bytes)) = maps.get(&path).filter(|(v, _)| *v == version) {
+            return Ok(bytes.clone());
+        }
+
+        let file = File::open(&path).map_err(|e| to_error(&path, e))?;
+        // SAFETY: The map is read only, and the parquet writers of the crate never
+        // modify a file in place, as they write a temporary file which replaces it,
+        // so an existing map keeps the contents of the file it was created from
+        let mmap = unsafe { Mmap::map(&file) }.map_err(|e| to_error(&path, e))?;
+        let bytes = Bytes::from_owner(mmap);
+        maps.insert(path, (version, bytes.clone()));
+        Ok(bytes)
+    }
+}
+
+impl Debug for MmapFileSystem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(stringify!(MmapFileSystem))
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Display for MmapFileSystem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "MmapFileSystem")
+    }
+}
+
+#[async_trait]
+impl ObjectStore for MmapFileSystem {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        self.inner.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        self.inner.put_multipart_opts(location, opts).await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        self.inner.get_opts(location, options).await
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        let bytes = self.map(location)?;
+        check_range(location, &range, bytes.len())?;
+        Ok(bytes.slice(range))
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        let bytes = self.map(location)?;
+        ranges
+            .iter()
+            .map(|range| {
+                check_range(location, range, bytes.len())?;
+                Ok(bytes.slice(range.clone()))
+            })
+            .collect()
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.inner.head(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.inner.delete(location).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.inner.list(prefix)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy_if_not_exists(from, to).await
+    }
+}
+
+/// Returns an error if `range` is not within an object of `len` bytes.
+fn check_range(location: &Path, range: &Range<usize>, len: usize) -> Result<()> {
+    if range.start > range.end || range.end > len {
+        return Err(object_store::Error::Generic {
+            store: "MmapFileSystem",
+            source: format!("Range {range:?} is out of bounds for `{location}` of {len} bytes")
+                .into(),
+        });
+    }
+    Ok(())
+}
+
+fn to_error(path: &std::path::Path, e: std::io::Error) -> object_store::Error {
+    if e.kind() == std::io::ErrorKind::NotFound {
+        return object_store::Error::NotFound {
+            path: path.display().to_string(),
+            source: e.into(),
+        };
+    }
+    object_store::Error::Generic {
+        store: "MmapFileSystem",
+        source: e.into(),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_get_ranges_matches_local_file_system() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("data.bin");
+        std::fs::write(&file_path, (0..=255u8).collect::<Vec<_>>()).unwrap();
+        let location = Path::from_filesystem_path(&file_path).unwrap();
+        let store = MmapFileSystem::new();
+        let ranges = [0..4, 100..110, 250..256];
+
+        let mapped = store.get_ranges(&location, &ranges).await.unwrap();
+        let read = LocalFileSystem::new()
+            .get_ranges(&location, &ranges)
+            .await
+            .unwrap();
+
+        assert_eq!(mapped, read);
+        assert!(store.get_range(&location, 250..257).await.is_err());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_map_is_reused_until_file_replaced() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("data.bin");
+        std::fs::write(&file_path, [1u8; 16]).unwrap();
+        let location = Path::from_filesystem_path(&file_path).unwrap();
+        let store = MmapFileSystem::new();
+
+        let first = store.get_range(&location, 0..16).await.unwrap();
+        let second = store.get_range(&location, 0..16).await.unwrap();
+        assert_eq!(first.as_ptr(), second.as_ptr());
+
+        let temp_path = temp_dir.path().join("data.bin.tmp");
+        std::fs::write(&temp_path, [2u8; 32]).unwrap();
+        std::fs::rename(&temp_path, &file_path).unwrap();
+        let replaced = store.get_range(&location, 0..32).await.unwrap();
+
+        assert_eq!(first, vec![1u8; 16]);
+        assert_eq!(replaced, vec![2u8; 32]);
+    }
+}
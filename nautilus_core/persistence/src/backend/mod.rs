@@ -15,5 +15,16 @@
 
 //! Provides an Apache Parquet backend powered by [DataFusion](https://arrow.apache.org/datafusion).
 
+pub mod cache;
+#[cfg(feature = "polars")]
+pub mod dataframe;
+pub mod filter;
+pub mod json;
 pub mod kmerge_batch;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+#[cfg(feature = "object_store")]
+pub mod retry;
 pub mod session;
+pub mod snapshot;
+pub mod spill;
@@ -0,0 +1,267 @@
+// -------------------------------------------------------------------------------------------------
+//  Copyright (C) 2015-2024 Nautech Systems Pty Ltd. All rights reserved.
+//  https://nautechsystems.io
+//
+//  Licensed under the GNU Lesser General Public License Version 3.0 (the "License");
+//  You may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at https://www.gnu.org/licenses/lgpl-3.0.en.html
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+// -------------------------------------------------------------------------------------------------
+
+//! Provides an object store which retries reads failing with transient errors.
+
+use std::{
+    error::Error as StdError, fmt::Display, future::Future, io, ops::Range, sync::Arc,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use object_store::{
+    path::Path, GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore,
+    PutMultipartOpts, PutOptions, PutPayload, PutResult, Result,
+};
+use reqwest::StatusCode;
+
+/// The policy by which a [`RetryingObjectStore`] retries failed reads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The maximum number of retries of a read after its first attempt.
+    pub max_retries: usize,
+    /// The delay before the first retry, which doubles for each later retry.
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            backoff: Duration::from_millis(100),
+        }
+    }
+}
+
+/// An object store which retries the reads of an inner store according to a
+/// [`RetryPolicy`], so a transient network error doesn't fail a whole query.
+///
+/// Only errors which may succeed on retry are retried: connection failures and
+/// timeouts, server errors, and throttled requests. Errors such as a missing
+/// object or a client error status, such as of a failed authentication, fail on
+/// the first attempt. The range reads and metadata requests of a scan are
+/// retried, while errors in the body stream of a [`ObjectStore::get_opts`]
+/// result, listings and writes are passed through.
+#[derive(Debug)]
+pub struct RetryingObjectStore {
+    inner: Arc<dyn ObjectStore>,
+    policy: RetryPolicy,
+}
+
+impl RetryingObjectStore {
+    /// Creates a new [`RetryingObjectStore`] instance.
+    #[must_use]
+    pub fn new(inner: Arc<dyn ObjectStore>, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    async fn retry<T, F, Fut>(&self, f: F) -> Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut backoff = self.policy.backoff;
+        let mut retries = 0;
+        loop {
+            match f().await {
+                Err(e) if retries < self.policy.max_retries && is_retryable(&e) => {
+                    log::warn!("Retrying object store read after error: {e}");
+                    tokio::time::sleep(backoff).await;
+                    backoff = backoff.saturating_mul(2);
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Display for RetryingObjectStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RetryingObjectStore({})", self.inner)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for RetryingObjectStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        self.inner.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        self.inner.put_multipart_opts(location, opts).await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        self.retry(|| self.inner.get_opts(location, clone_options(&options)))
+            .await
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        self.retry(|| self.inner.get_range(location, range.clone()))
+            .await
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        self.retry(|| self.inner.get_ranges(location, ranges)).await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.retry(|| self.inner.head(location)).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.inner.delete(location).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.inner.list(prefix)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy_if_not_exists(from, to).await
+    }
+}
+
+/// Returns a copy of `options`, which do not implement `Clone`.
+fn clone_options(options: &GetOptions) -> GetOptions {
+    GetOptions {
+        if_match: options.if_match.clone(),
+        if_none_match: options.if_none_match.clone(),
+        if_modified_since: options.if_modified_since,
+        if_unmodified_since: options.if_unmodified_since,
+        range: options.range.clone(),
+        version: options.version.clone(),
+        head: options.head,
+    }
+}
+
+/// Returns whether a read failing with `e` may succeed on retry.
+///
+/// The stores report transport failures as generic errors, so their sources are
+/// searched for the underlying HTTP or I/O error. A generic error without one,
+/// such as of a client error status, is not retried.
+fn is_retryable(e: &object_store::Error) -> bool {
+    match e {
+        object_store::Error::Generic { source, .. } => {
+            let mut source: Option<&(dyn StdError + 'static)> = Some(source.as_ref());
+            while let Some(e) = source {
+                if let Some(e) = e.downcast_ref::<reqwest::Error>() {
+                    return is_retryable_request(e);
+                }
+                if let Some(e) = e.downcast_ref::<io::Error>() {
+                    return is_retryable_io(e);
+                }
+                source = e.source();
+            }
+            false
+        }
+        object_store::Error::JoinError { .. } => true,
+        _ => false,
+    }
+}
+
+/// Returns whether a request failing with `e` may succeed on retry.
+fn is_retryable_request(e: &reqwest::Error) -> bool {
+    match e.status() {
+        Some(status) => status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS,
+        None => e.is_connect() || e.is_timeout() || e.is_request() || e.is_body(),
+    }
+}
+
+/// Returns whether an I/O operation failing with `e` may succeed on retry.
+fn is_retryable_io(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::Interrupted
+            | io::ErrorKind::TimedOut
+            | io::ErrorKind::UnexpectedEof
+    )
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    /// An error wrapping `source`, as the stores wrap the errors of a request.
+    #[derive(Debug)]
+    struct RequestError(Box<dyn StdError + Send + Sync>);
+
+    impl Display for RequestError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "Error performing request: {}", self.0)
+        }
+    }
+
+    impl StdError for RequestError {
+        fn source(&self) -> Option<&(dyn StdError + 'static)> {
+            Some(self.0.as_ref())
+        }
+    }
+
+    fn generic(source: impl StdError + Send + Sync + 'static) -> object_store::Error {
+        object_store::Error::Generic {
+            store: "S3",
+            source: Box::new(RequestError(Box::new(source))),
+        }
+    }
+
+    #[rstest]
+    #[case(generic(io::Error::from(io::ErrorKind::ConnectionReset)), true)]
+    #[case(generic(io::Error::from(io::ErrorKind::PermissionDenied)), false)]
+    #[case(object_store::Error::Generic {
+        store: "S3",
+        source: "Client error with status 403 Forbidden: No Body".into(),
+    }, false)]
+    #[case(object_store::Error::NotFound { path: "quotes.parquet".into(), source: "".into() }, false)]
+    #[case(object_store::Error::Precondition { path: "quotes.parquet".into(), source: "".into() }, false)]
+    fn test_is_retryable(#[case] error: object_store::Error, #[case] expected: bool) {
+        assert_eq!(is_retryable(&error), expected);
+    }
+
+    #[rstest]
+    fn test_invalid_request_is_not_retryable() {
+        let e = reqwest::Client::new().get("not a url").build().unwrap_err();
+
+        assert!(!is_retryable(&generic(e)));
+    }
+}
@@ -13,168 +13,4198 @@
 //  limitations under the License.
 // -------------------------------------------------------------------------------------------------
 
-use std::{collections::HashMap, sync::Arc, vec::IntoIter};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::{Display, Formatter},
+    fs::{self, File},
+    mem::size_of,
+    path::Path,
+    pin::Pin,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+    thread,
+    time::{Duration, Instant},
+    vec::IntoIter,
+};
+
+use compare::Compare;
+#[cfg(feature = "mmap")]
+use datafusion::execution::object_store::ObjectStoreUrl;
+use datafusion::{
+    arrow::{
+        array::{Array, ArrayRef, Int64Array, Scalar, UInt64Array},
+        compute::{
+            and, cast, concat_batches, filter_record_batch, is_not_null, is_null,
+            kernels::zip::zip, max, min,
+        },
+        datatypes::{DataType, Field, Schema, TimeUnit},
+        error::ArrowError,
+        record_batch::RecordBatch,
+    },
+    common::stats::Precision,
+    datasource::{
+        file_format::{
+            file_compression_type::FileCompressionType,
+            options::{ArrowReadOptions, ReadOptions},
+        },
+        listing::{ListingTable, ListingTableConfig, ListingTableUrl},
+    },
+    error::Result,
+    logical_expr::expr::Sort,
+    parquet::{
+        arrow::{arrow_reader::ParquetRecordBatchReaderBuilder, ProjectionMask},
+        basic::{ConvertedType, LogicalType, TimeUnit as ParquetTimeUnit},
+        errors::ParquetError,
+        file::{footer::parse_metadata, metadata::RowGroupMetaData, statistics::Statistics},
+        schema::types::ColumnDescriptor,
+    },
+    physical_plan::{displayable, execute_stream, SendableRecordBatchStream},
+    prelude::*,
+};
+use futures::{Stream, StreamExt};
+use nautilus_core::{ffi::cvec::CVec, nanos::UnixNanos};
+use nautilus_model::{
+    data::{
+        bar::{get_bar_interval_ns, Bar, BarSpecification, BarType},
+        delta::OrderBookDelta,
+        depth::{OrderBookDepth10, DEPTH10_LEN},
+        quote::QuoteTick,
+        trade::TradeTick,
+        Data, GetTsInit,
+    },
+    enums::{AggregationSource, BarAggregation, PriceType},
+    identifiers::InstrumentId,
+    instruments::any::InstrumentAny,
+    types::fixed::{FIXED_PRECISION, FIXED_SCALAR},
+};
+use tokio::sync::mpsc::{self, Receiver};
+use tokio_util::sync::CancellationToken;
+
+#[cfg(feature = "mmap")]
+use super::mmap::MmapFileSystem;
+#[cfg(feature = "object_store")]
+use super::retry::{RetryPolicy, RetryingObjectStore};
+use super::{
+    cache::{CacheStats, QueryCache, QueryCacheKey},
+    filter::Filter,
+    kmerge_batch::{EagerStream, ElementBatchIter, KMerge},
+    snapshot::{DepthDecoder, SnapshotSampler},
+    spill::{self, SpilledResult},
+};
+use crate::{
+    arrow::{
+        instrument::decode_instruments, ArrowSchemaProvider, DecodeDataFromRecordBatch,
+        DecodeFromRecordBatch, EncodeToRecordBatch, EncodingError, WriteStream, KEY_BAR_TYPE,
+        KEY_INSTRUMENT_ID, KEY_PRICE_PRECISION, KEY_SIZE_PRECISION,
+    },
+    error::PersistenceError,
+};
+
+/// The timestamp field by which the data of a [`DataBackendSession`] is ordered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum SortKey {
+    /// Order by the time the data was initialized, as persisted.
+    #[default]
+    TsInit,
+    /// Order by the time the data event occurred.
+    TsEvent,
+}
+
+impl SortKey {
+    /// Returns the name of the column of the key.
+    #[must_use]
+    pub const fn column(&self) -> &'static str {
+        match self {
+            Self::TsInit => "ts_init",
+            Self::TsEvent => "ts_event",
+        }
+    }
+
+    /// Returns the timestamp of `data` for the key.
+    #[must_use]
+    pub fn timestamp(&self, data: &Data) -> UnixNanos {
+        match self {
+            Self::TsInit => data.ts_init(),
+            Self::TsEvent => data.ts_event(),
+        }
+    }
+
+    /// Returns whether `data` is monotonically increasing by the key.
+    #[must_use]
+    pub fn is_monotonically_increasing(&self, data: &[Data]) -> bool {
+        data.windows(2)
+            .all(|window| self.timestamp(&window[0]) <= self.timestamp(&window[1]))
+    }
+}
+
+impl Display for SortKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.column())
+    }
+}
+
+/// What to do when decoding a null in a price or size field of a
+/// [`DataBackendSession`] query, such as from a feed which encodes missing
+/// prices as null.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum NullPolicy {
+    /// Fail to decode the record batch with a [`PersistenceError::NullValue`].
+    #[default]
+    Error,
+    /// Skip the rows with a null.
+    Skip,
+    /// Decode the nulls as zero.
+    Zero,
+}
+
+/// How the price and size precisions of the data decoded by a
+/// [`DataBackendSession`] query are determined, which are otherwise read from
+/// the `price_precision` and `size_precision` metadata of each file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PrecisionPolicy {
+    /// Use the precisions of the file.
+    #[default]
+    FromFile,
+    /// Use the precisions of the file, failing a query with a
+    /// [`PersistenceError::PrecisionMismatch`] if they differ from these, such
+    /// as for a file of another instrument.
+    Expect {
+        price_precision: u8,
+        size_precision: u8,
+    },
+    /// Decode with these precisions, ignoring those of the file.
+    Override {
+        price_precision: u8,
+        size_precision: u8,
+    },
+}
+
+/// The default chunk size of a [`DataBackendSession`], which is also the chunk
+/// size under [`ChunkMode::RowGroup`] for data without known row groups.
+pub const DEFAULT_CHUNK_SIZE: usize = 5_000;
+
+/// How the merged result of a [`DataBackendSession`] is split into chunks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ChunkMode {
+    /// Chunks of at most the given number of data items.
+    RowCount(usize),
+    /// A chunk per row group of the registered parquet files, taken in the order
+    /// the files were registered.
+    ///
+    /// The chunks match the row groups exactly when a single file is queried in
+    /// full. Only files registered with [`DataBackendSession::add_file`] and its
+    /// variants have known row groups, and the data of other tables, or beyond
+    /// the known row groups, is chunked by the session chunk size.
+    RowGroup,
+}
+
+impl Default for ChunkMode {
+    fn default() -> Self {
+        Self::RowCount(DEFAULT_CHUNK_SIZE)
+    }
+}
+
+/// Orders data by a [`SortKey`], breaking ties by data type and then by the
+/// registration order of the queries.
+///
+/// For equal timestamps, order book deltas come first, followed by order book
+/// depths, quotes, trades and bars, in the order of [`NautilusDataType`]. Data of
+/// the same type and timestamp from different queries is yielded in the order
+/// the queries were registered, so the merge is stable and deterministic.
+#[derive(Debug, Default)]
+pub struct SortKeyComparator(pub SortKey);
+
+impl<I> Compare<ElementBatchIter<I, Data>> for SortKeyComparator
+where
+    I: Iterator<Item = IntoIter<Data>>,
+{
+    fn compare(
+        &self,
+        l: &ElementBatchIter<I, Data>,
+        r: &ElementBatchIter<I, Data>,
+    ) -> std::cmp::Ordering {
+        // Max heap ordering must be reversed
+        self.0
+            .timestamp(&l.item)
+            .cmp(&self.0.timestamp(&r.item))
+            .then_with(|| (data_type(&l.item) as u8).cmp(&(data_type(&r.item) as u8)))
+            .then_with(|| l.index.cmp(&r.index))
+            .reverse()
+    }
+}
+
+/// Returns the [`NautilusDataType`] of `data`, with a batch of deltas having the
+/// type of its elements.
+pub(crate) const fn data_type(data: &Data) -> NautilusDataType {
+    match data {
+        Data::Delta(_) | Data::Deltas(_) => NautilusDataType::OrderBookDelta,
+        Data::Depth10(_) => NautilusDataType::OrderBookDepth10,
+        Data::Quote(_) => NautilusDataType::QuoteTick,
+        Data::Trade(_) => NautilusDataType::TradeTick,
+        Data::Bar(_) => NautilusDataType::Bar,
+    }
+}
+
+/// The merged result of all queries registered with a [`DataBackendSession`].
+///
+/// Each registered query is a sorted stream, and a binary heap keyed on the
+/// [`SortKey`] of the session yields the smallest head across all streams. The
+/// output is globally ordered by the key regardless of how the ranges of the
+/// individual files overlap, with ties between data types broken as documented
+/// on [`SortKeyComparator`]. Consecutive exact duplicates are dropped when the
+/// session is built with [`DataBackendSessionBuilder::dedup`].
+///
+/// Iterating the result panics if a record batch fails to decode, while
+/// [`QueryResult::flatten_results`] returns the error and skips the batch.
+pub struct QueryResult {
+    merge: KMerge<EagerStream<IntoIter<Data>>, Data, SortKeyComparator>,
+    size_hint_total: Option<u64>,
+    time_range: Option<(u64, u64)>,
+    chunk_size: usize,
+    row_groups: VecDeque<usize>,
+    errors: DecodeErrors,
+    metrics: SharedMetrics,
+    bounds_probes: Vec<Option<BoundsProbe>>,
+    session_ctx: SessionContext,
+    runtime: Arc<tokio::runtime::Runtime>,
+    sort_key: SortKey,
+    dedup: bool,
+    last: Option<Data>,
+    sort_on_read: bool,
+    sorted: Option<IntoIter<Data>>,
+    progress: Option<Progress>,
+    cancellation_token: Option<CancellationToken>,
+}
+
+/// The progress callback of a [`QueryResult`], reported at the end of each chunk.
+struct Progress {
+    callback: ProgressCallback,
+    chunk_size: usize,
+    row_groups: VecDeque<usize>,
+    chunk_len: usize,
+    chunk_rows: usize,
+    rows: u64,
+    finished: bool,
+}
+
+impl Progress {
+    /// Returns the length of the next chunk, which is the next row group under
+    /// [`ChunkMode::RowGroup`], as chunked by [`DataQueryResult`].
+    fn next_chunk_len(&mut self) -> usize {
+        self.row_groups.pop_front().unwrap_or(self.chunk_size)
+    }
+}
+
+/// A callback invoked with the number of rows yielded so far, and the estimated
+/// total number of rows if known.
+pub type ProgressCallback = Box<dyn Fn(u64, Option<u64>) + Send>;
+
+impl QueryResult {
+    /// Returns an estimate of the total number of data items the result yields
+    /// over its whole iteration, or `None` if unknown.
+    ///
+    /// The estimate sums the row counts DataFusion derives from the file
+    /// statistics of each query plan, and is unknown if any query filters rows,
+    /// such as with a `WHERE` clause. It is not reduced as the result is consumed.
+    #[must_use]
+    pub const fn size_hint_total(&self) -> Option<u64> {
+        self.size_hint_total
+    }
+
+    /// Returns an estimate of the memory in bytes taken by the decoded data of
+    /// the whole result if collected, or `None` if the number of rows is unknown.
+    ///
+    /// The estimate is [`QueryResult::size_hint_total`] times the size of a
+    /// [`Data`] element, which is exact for all data types other than
+    /// `OrderBookDeltas`. Callers can use it to decide whether to stream the
+    /// result rather than collect it.
+    #[must_use]
+    pub const fn estimated_memory_bytes(&self) -> Option<u64> {
+        match self.size_hint_total {
+            Some(rows) => Some(rows * size_of::<Data>() as u64),
+            None => None,
+        }
+    }
+
+    /// Returns the first and last `ts_init` of the result, read from the `ts_init`
+    /// statistics of its tables without scanning any data, or `None` if any
+    /// table has no statistics.
+    ///
+    /// The range covers the whole of each table, so it can be wider than the
+    /// result of a query which filters rows. Use
+    /// [`nautilus_model::data::time_range`] on the consumed data for the exact
+    /// range.
+    #[must_use]
+    pub const fn time_range(&self) -> Option<(u64, u64)> {
+        self.time_range
+    }
+
+    /// Returns the metrics of the scan and decode of the result so far.
+    ///
+    /// The record batches of the queries are decoded ahead of the iteration, so
+    /// the metrics are only complete once the result has been consumed. A query
+    /// served from the cache of the session adds nothing to them.
+    #[must_use]
+    pub fn metrics(&self) -> QueryMetrics {
+        *self.metrics.lock().unwrap()
+    }
+
+    /// Returns the first and last data of the result, without decoding the data
+    /// in between, or `None` if the result is empty.
+    ///
+    /// The first row of each query is read with a `LIMIT 1` query, and its last
+    /// row by the session [`SortKey`] with an `ORDER BY .. DESC LIMIT 1` query,
+    /// so only two rows are decoded per query and the result is not consumed.
+    /// The smallest first and largest last row across the queries are returned.
+    /// `None` is also returned if a query of the result is not over a registered
+    /// table, such as the snapshots replayed by
+    /// [`DataBackendSession::deltas_to_snapshots`].
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if a query fails, such as after its table
+    /// was deregistered, or if its rows fail to decode.
+    pub fn bounds(&self) -> Result<Option<(Data, Data)>, PersistenceError> {
+        let mut bounds: Option<(Data, Data)> = None;
+        for probe in &self.bounds_probes {
+            let Some(probe) = probe else {
+                return Ok(None);
+            };
+            let Some((first, last)) = probe.bounds(&self.session_ctx, &self.runtime)? else {
+                continue;
+            };
+            let key = self.sort_key;
+            bounds = Some(match bounds {
+                None => (first, last),
+                Some((min, max)) => (
+                    if key.timestamp(&first) < key.timestamp(&min) {
+                        first
+                    } else {
+                        min
+                    },
+                    if key.timestamp(&last) >= key.timestamp(&max) {
+                        last
+                    } else {
+                        max
+                    },
+                ),
+            });
+        }
+        Ok(bounds)
+    }
+
+    /// Clears the underlying streams, stopping any further queries.
+    pub fn clear(&mut self) {
+        self.merge.clear();
+        self.sorted = None;
+        self.errors.lock().unwrap().clear();
+    }
+
+    /// Returns an iterator of the merged data, which also yields an error for
+    /// each record batch that failed to decode.
+    ///
+    /// The data of a failed batch is skipped, and the remaining batches of its
+    /// query are still decoded. Batches are decoded ahead of the merge, so an
+    /// error is yielded as soon as it occurs rather than in timestamp order.
+    pub fn flatten_results(mut self) -> impl Iterator<Item = Result<Data, PersistenceError>> {
+        std::iter::from_fn(move || self.next_result())
+    }
+
+    /// Folds the merged data into an accumulator, starting from `init`, without
+    /// collecting the result, such as to compute an aggregate of a large file in
+    /// bounded memory.
+    ///
+    /// Unlike [`Iterator::fold`], which panics if a record batch failed to
+    /// decode, the fold stops at the first such error.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if a record batch of the result failed to
+    /// decode.
+    pub fn fold<B, F>(self, init: B, mut f: F) -> Result<B, PersistenceError>
+    where
+        F: FnMut(B, Data) -> B,
+    {
+        let mut acc = init;
+        for data in self.flatten_results() {
+            acc = f(acc, data?);
+        }
+        Ok(acc)
+    }
+
+    /// Consumes the result into a [`ReusableQueryResult`], which holds all of its
+    /// decoded data so it can be iterated any number of times.
+    ///
+    /// The whole result is decoded and kept in memory, as estimated by
+    /// [`QueryResult::estimated_memory_bytes`], so prefer iterating the result
+    /// directly unless more than one pass is needed.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if a record batch of the result failed to
+    /// decode.
+    pub fn into_reusable(self) -> Result<ReusableQueryResult, PersistenceError> {
+        let chunk_size = self.chunk_size;
+        let mut chunks: Vec<Vec<Data>> = Vec::new();
+        for data in self.flatten_results() {
+            match chunks.last_mut() {
+                Some(chunk) if chunk.len() < chunk_size => chunk.push(data?),
+                _ => {
+                    let mut chunk = Vec::with_capacity(chunk_size);
+                    chunk.push(data?);
+                    chunks.push(chunk);
+                }
+            }
+        }
+        Ok(ReusableQueryResult { chunks })
+    }
+
+    /// Consumes the result into a [`SpilledResult`], for results too large to
+    /// collect into memory.
+    ///
+    /// The decoded data is buffered until its estimated size exceeds
+    /// `threshold_bytes`, when it is written to temporary parquet files in a
+    /// new directory under `dir`, which is removed when the [`SpilledResult`]
+    /// is dropped. `OrderBookDeltas` cannot be spilled, and return an error.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error:
+    /// - If a record batch of the result failed to decode.
+    /// - If the spilled data cannot be encoded or written under `dir`.
+    pub fn collect_to_spill(
+        self,
+        dir: &str,
+        threshold_bytes: usize,
+    ) -> Result<SpilledResult, PersistenceError> {
+        spill::collect_to_spill(self.flatten_results(), Path::new(dir), threshold_bytes)
+    }
+
+    /// Sets a `callback` reporting the progress of the iteration, which replaces
+    /// any previous callback.
+    ///
+    /// The callback is invoked with the number of rows yielded so far and the
+    /// [`QueryResult::size_hint_total`] at the end of each chunk, and after the
+    /// final partial chunk, rather than for every row. The chunks are those of
+    /// [`DataBackendSession::get_chunked_query_result`]: of the session chunk
+    /// size, or of each row group under [`ChunkMode::RowGroup`].
+    pub fn set_progress_callback(&mut self, callback: ProgressCallback) {
+        let mut progress = Progress {
+            callback,
+            chunk_size: self.chunk_size,
+            row_groups: self.row_groups.clone(),
+            chunk_len: 0,
+            chunk_rows: 0,
+            rows: 0,
+            finished: false,
+        };
+        progress.chunk_len = progress.next_chunk_len();
+        self.progress = Some(progress);
+    }
+
+    fn next_result(&mut self) -> Option<Result<Data, PersistenceError>> {
+        let result = self.next_data();
+        if let Some(progress) = &mut self.progress {
+            match &result {
+                Some(Ok(_)) => {
+                    progress.rows += 1;
+                    progress.chunk_rows += 1;
+                    if progress.chunk_rows >= progress.chunk_len {
+                        (progress.callback)(progress.rows, self.size_hint_total);
+                        progress.chunk_rows = 0;
+                        progress.chunk_len = progress.next_chunk_len();
+                    }
+                }
+                Some(Err(_)) => {}
+                None => {
+                    if !progress.finished && progress.chunk_rows > 0 {
+                        (progress.callback)(progress.rows, self.size_hint_total);
+                    }
+                    progress.finished = true;
+                }
+            }
+        }
+        result
+    }
+
+    /// Sets a `token` which cancels the iteration from another task or thread,
+    /// replacing any previous token.
+    ///
+    /// Once the token is cancelled, the result yields no more data and drops its
+    /// underlying streams, which aborts their decoding. Data already yielded is
+    /// owned by the caller and stays valid. A [`DataQueryStream`] created from
+    /// the result also stops as soon as the token is cancelled.
+    pub fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation_token = Some(token);
+    }
+
+    fn next_data(&mut self) -> Option<Result<Data, PersistenceError>> {
+        if self
+            .cancellation_token
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+        {
+            self.clear();
+            return None;
+        }
+
+        if let Some(e) = self.errors.lock().unwrap().pop_front() {
+            return Some(Err(e));
+        }
+
+        loop {
+            match self.next_merged() {
+                Some(data) if self.dedup => {
+                    if self.last.as_ref() == Some(&data) {
+                        continue;
+                    }
+                    self.last = Some(data.clone());
+                    return Some(Ok(data));
+                }
+                Some(data) => return Some(Ok(data)),
+                // Every stream has finished, so no more errors can occur
+                None => return self.errors.lock().unwrap().pop_front().map(Err),
+            }
+        }
+    }
+}
+
+impl QueryResult {
+    /// Returns the next data of the merge, which under sort on read is first
+    /// collected in full and stably sorted by the session [`SortKey`].
+    fn next_merged(&mut self) -> Option<Data> {
+        if !self.sort_on_read {
+            return self.merge.next();
+        }
+        let sort_key = self.sort_key;
+        self.sorted
+            .get_or_insert_with(|| {
+                let mut data: Vec<Data> = self.merge.by_ref().collect();
+                data.sort_by_key(|data| sort_key.timestamp(data));
+                data.into_iter()
+            })
+            .next()
+    }
+}
+
+impl Iterator for QueryResult {
+    type Item = Data;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_result()? {
+            Ok(data) => Some(data),
+            Err(e) => panic!("Failed to decode record batch of query result: {e}"),
+        }
+    }
+}
+
+/// The decoded data of a [`QueryResult`], in chunks of the session chunk size,
+/// which can be iterated any number of times.
+///
+/// Created by [`QueryResult::into_reusable`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReusableQueryResult {
+    chunks: Vec<Vec<Data>>,
+}
+
+impl ReusableQueryResult {
+    /// Returns an iterator over the data of the result, in merged order.
+    pub fn iter(&self) -> impl Iterator<Item = &Data> {
+        self.chunks.iter().flatten()
+    }
+
+    /// Returns the chunks of the data of the result.
+    #[must_use]
+    pub fn chunks(&self) -> &[Vec<Data>] {
+        &self.chunks
+    }
+
+    /// Returns the number of data items of the result.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.chunks.iter().map(Vec::len).sum()
+    }
+
+    /// Returns whether the result holds no data.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+}
+
+/// The metrics of the scan and decode of the record batches of a [`QueryResult`],
+/// summed over all of its queries.
+///
+/// The scan time is the wall time spent waiting for DataFusion to produce each
+/// record batch, which includes reading and decompressing the files, and the
+/// decode time is the time spent converting the batches into [`Data`]. The
+/// queries run concurrently, so either may exceed the elapsed time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QueryMetrics {
+    /// The time spent waiting for record batches to be scanned.
+    pub scan_time: Duration,
+    /// The time spent decoding record batches into data.
+    pub decode_time: Duration,
+    /// The number of chunks decoded.
+    pub chunks: u64,
+    /// The number of rows decoded.
+    pub rows: u64,
+    /// The in-memory size of the scanned record batches in bytes.
+    pub bytes: u64,
+}
+
+/// The metrics of a query result, shared between the decoding tasks and the
+/// [`QueryResult`].
+type SharedMetrics = Arc<Mutex<QueryMetrics>>;
+
+/// The errors of the record batches which failed to decode, shared between the
+/// decoding tasks and the [`QueryResult`].
+type DecodeErrors = Arc<Mutex<VecDeque<PersistenceError>>>;
+
+#[repr(C)]
+#[cfg_attr(
+    feature = "python",
+    pyo3::pyclass(module = "nautilus_trader.core.nautilus_pyo3.persistence")
+)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NautilusDataType {
+    // Custom = 0,  # First slot reserved for custom data
+    OrderBookDelta = 1,
+    OrderBookDepth10 = 2,
+    QuoteTick = 3,
+    TradeTick = 4,
+    Bar = 5,
+    InstrumentAny = 6,
+}
+
+impl NautilusDataType {
+    /// Returns the snake case name of the data type, such as `quote_tick`.
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::OrderBookDelta => "order_book_delta",
+            Self::OrderBookDepth10 => "order_book_depth10",
+            Self::QuoteTick => "quote_tick",
+            Self::TradeTick => "trade_tick",
+            Self::Bar => "bar",
+            Self::InstrumentAny => "instrument_any",
+        }
+    }
+}
+
+impl Display for NautilusDataType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for NautilusDataType {
+    type Err = PersistenceError;
+
+    /// Parses a data type from its snake case name, as written by [`Display`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "order_book_delta" => Ok(Self::OrderBookDelta),
+            "order_book_depth10" => Ok(Self::OrderBookDepth10),
+            "quote_tick" => Ok(Self::QuoteTick),
+            "trade_tick" => Ok(Self::TradeTick),
+            "bar" => Ok(Self::Bar),
+            "instrument_any" => Ok(Self::InstrumentAny),
+            _ => Err(PersistenceError::UnknownDataType(s.to_string())),
+        }
+    }
+}
+
+/// Associates a type decoded by the session with its [`NautilusDataType`].
+pub trait NautilusDataTypeProvider {
+    const DATA_TYPE: NautilusDataType;
+}
+
+impl NautilusDataTypeProvider for OrderBookDelta {
+    const DATA_TYPE: NautilusDataType = NautilusDataType::OrderBookDelta;
+}
+
+impl NautilusDataTypeProvider for OrderBookDepth10 {
+    const DATA_TYPE: NautilusDataType = NautilusDataType::OrderBookDepth10;
+}
+
+impl NautilusDataTypeProvider for QuoteTick {
+    const DATA_TYPE: NautilusDataType = NautilusDataType::QuoteTick;
+}
+
+impl NautilusDataTypeProvider for TradeTick {
+    const DATA_TYPE: NautilusDataType = NautilusDataType::TradeTick;
+}
+
+impl NautilusDataTypeProvider for Bar {
+    const DATA_TYPE: NautilusDataType = NautilusDataType::Bar;
+}
+
+impl NautilusDataTypeProvider for InstrumentAny {
+    const DATA_TYPE: NautilusDataType = NautilusDataType::InstrumentAny;
+}
+
+/// Describes a table registered with a [`DataBackendSession`].
+///
+/// The row count and `ts_init` bounds are read from the parquet footer when
+/// registering the table, and are `None` when unavailable. The `chunk_size` is
+/// the maximum number of rows decoded from the table at a time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TableInfo {
+    pub table_name: String,
+    pub data_type: NautilusDataType,
+    pub chunk_size: usize,
+    pub row_count: Option<u64>,
+    pub min_ts_init: Option<UnixNanos>,
+    pub max_ts_init: Option<UnixNanos>,
+}
+
+/// Describes the structure of a parquet file, as read from its footer by
+/// [`DataBackendSession::file_metadata`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileMetadata {
+    pub num_rows: u64,
+    pub column_names: Vec<String>,
+    pub row_groups: Vec<RowGroupInfo>,
+}
+
+/// Describes a row group of a parquet file, with the `ts_init` bounds of its
+/// column statistics.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RowGroupInfo {
+    pub num_rows: u64,
+    pub min_ts_init: Option<UnixNanos>,
+    pub max_ts_init: Option<UnixNanos>,
+}
+
+/// The result of validating the order of a parquet file with [`validate_catalog`].
+#[derive(Debug)]
+pub struct ValidationReport {
+    pub file_path: String,
+    /// The first error found, which is a [`PersistenceError::NotMonotonic`] for an
+    /// out of order file, or any error reading the file.
+    pub result: Result<(), PersistenceError>,
+}
+
+impl ValidationReport {
+    /// Returns whether the file is monotonically increasing by `ts_init`.
+    #[must_use]
+    pub const fn passed(&self) -> bool {
+        self.result.is_ok()
+    }
+
+    /// Returns the index of the first out of order row, if the file was read and
+    /// is not monotonically increasing.
+    #[must_use]
+    pub const fn first_offending_index(&self) -> Option<u64> {
+        match &self.result {
+            Err(PersistenceError::NotMonotonic { index, .. }) => Some(*index),
+            _ => None,
+        }
+    }
+}
+
+/// The result of comparing the data of two parquet files with
+/// [`compare_catalogs`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CatalogComparison {
+    /// The index of the first element which differs between the files, or the
+    /// length of the shorter file if it is a prefix of the other, or `None` if
+    /// the files hold the same data.
+    pub first_difference: Option<u64>,
+}
+
+impl CatalogComparison {
+    /// Returns whether the files hold the same data.
+    #[must_use]
+    pub const fn is_equal(&self) -> bool {
+        self.first_difference.is_none()
+    }
+}
+
+/// A registered query of instrument definitions, which are collected by
+/// [`DataBackendSession::get_instruments`] rather than merged with the data.
+struct InstrumentQuery {
+    table_name: String,
+    sql_query: String,
+}
+
+/// A registered query whose decoded stream is yet to be merged.
+struct PendingQuery {
+    table_name: String,
+    sql_query: String,
+    stream: EagerStream<IntoIter<Data>>,
+    size_hint: Option<u64>,
+    time_range: Option<(u64, u64)>,
+    row_groups: Vec<usize>,
+    bounds_probe: Option<BoundsProbe>,
+}
+
+/// The queries reading the first and last rows of a [`PendingQuery`] for
+/// [`QueryResult::bounds`], with the functions to decode them as its data type.
+struct BoundsProbe {
+    sql_query: String,
+    sort_key: SortKey,
+    metadata: Option<HashMap<String, String>>,
+    null_policy: NullPolicy,
+    projection: fn(&Schema) -> Result<Vec<usize>, PersistenceError>,
+    decode: DecodeFn,
+}
+
+/// Decodes a record batch into [`Data`], as [`DecodeDataFromRecordBatch`].
+type DecodeFn = fn(&HashMap<String, String>, RecordBatch) -> Result<Vec<Data>, EncodingError>;
+
+impl BoundsProbe {
+    fn new<T: DecodeDataFromRecordBatch>(
+        sql_query: &str,
+        sort_key: SortKey,
+        metadata: Option<HashMap<String, String>>,
+        null_policy: NullPolicy,
+    ) -> Self {
+        Self {
+            sql_query: sql_query.to_string(),
+            sort_key,
+            metadata,
+            null_policy,
+            projection: decode_projection::<T>,
+            decode: T::decode_data_batch,
+        }
+    }
+
+    /// Returns the first and last rows of the query, or `None` if it is empty.
+    fn bounds(
+        &self,
+        session_ctx: &SessionContext,
+        runtime: &tokio::runtime::Runtime,
+    ) -> Result<Option<(Data, Data)>, PersistenceError> {
+        let head = format!("SELECT * FROM ({}) LIMIT 1", self.sql_query);
+        let tail = format!(
+            "SELECT * FROM ({}) ORDER BY {} DESC LIMIT 1",
+            self.sql_query, self.sort_key
+        );
+        let first = self.first_row(session_ctx, runtime, &head)?;
+        let last = self.first_row(session_ctx, runtime, &tail)?;
+        Ok(first.zip(last))
+    }
+
+    fn first_row(
+        &self,
+        session_ctx: &SessionContext,
+        runtime: &tokio::runtime::Runtime,
+        sql_query: &str,
+    ) -> Result<Option<Data>, PersistenceError> {
+        let query = runtime.block_on(session_ctx.sql(sql_query))?;
+        let projection = (self.projection)(query.schema().as_arrow())?;
+        for batch in runtime.block_on(query.collect())? {
+            let batch = batch.project(&projection).map_err(EncodingError::from)?;
+            let batch = apply_null_policy(normalize_timestamps(batch)?, self.null_policy)?;
+            let mut metadata = batch.schema().metadata().clone();
+            if let Some(extra) = &self.metadata {
+                metadata.extend(extra.clone());
+            }
+            if let Some(data) = (self.decode)(&metadata, batch)?.into_iter().next() {
+                return Ok(Some(data));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// The fields of Nautilus data types which are stored as fixed-point integers,
+/// and are read from CSV files and compared in filters as decimal values.
+pub(crate) const FIXED_POINT_FIELDS: [&str; 11] = [
+    "price",
+    "size",
+    "bid_price",
+    "ask_price",
+    "bid_size",
+    "ask_size",
+    "open",
+    "high",
+    "low",
+    "close",
+    "volume",
+];
+
+/// The mapping of the columns of a CSV file to the fields of a Nautilus data type,
+/// for [`DataBackendSession::add_csv_file`].
+///
+/// Each field of the Arrow schema of the data type is read from the CSV column
+/// mapped with [`CsvSchema::column`], or else from the column of the same name.
+/// Prices and quantities are read as decimal values, timestamps as integer UNIX
+/// nanoseconds, and enums as their integer values.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CsvSchema {
+    columns: HashMap<String, String>,
+    metadata: HashMap<String, String>,
+}
+
+impl CsvSchema {
+    /// Creates a new [`CsvSchema`] instance.
+    ///
+    /// `metadata`: The schema metadata of the data type, such as the instrument
+    /// ID and precisions, as returned by `get_metadata` for the data type.
+    #[must_use]
+    pub fn new(metadata: HashMap<String, String>) -> Self {
+        Self {
+            columns: HashMap::new(),
+            metadata,
+        }
+    }
+
+    /// Maps the Nautilus `field` to the CSV column `csv_column`.
+    #[must_use]
+    pub fn column(mut self, field: &str, csv_column: &str) -> Self {
+        self.columns
+            .insert(field.to_string(), csv_column.to_string());
+        self
+    }
+
+    /// Returns the SQL select list which reads the fields of `schema` from the
+    /// CSV columns, cast to their Arrow data types.
+    fn select_list(&self, schema: &Schema) -> String {
+        schema
+            .fields()
+            .iter()
+            .map(|field| {
+                let name = field.name();
+                let column = self.columns.get(name).unwrap_or(name);
+                let column = format!("\"{}\"", column.replace('"', "\"\""));
+                let value = if FIXED_POINT_FIELDS.contains(&name.as_str()) {
+                    format!("ROUND({column} * {FIXED_SCALAR})")
+                } else {
+                    column
+                };
+                format!("arrow_cast({value}, '{}') AS {name}", field.data_type())
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Configures and builds a [`DataBackendSession`].
+///
+/// Options which are not set take their default value, a chunk size of 5,000,
+/// a decode thread per CPU core and ordering by `ts_init`.
+#[derive(Clone, Debug, Default)]
+pub struct DataBackendSessionBuilder {
+    chunk_mode: ChunkMode,
+    decode_threads: Option<usize>,
+    sort_key: SortKey,
+    use_mmap: bool,
+    dedup: bool,
+    allow_missing_optional: bool,
+    reject_extra_columns: bool,
+    sort_on_read: bool,
+    null_policy: NullPolicy,
+    precision_policy: PrecisionPolicy,
+}
+
+impl DataBackendSessionBuilder {
+    /// Sets the maximum number of data items in each chunk of the merged result.
+    ///
+    /// This is equivalent to `chunk_mode(ChunkMode::RowCount(chunk_size))`.
+    #[must_use]
+    pub const fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_mode = ChunkMode::RowCount(chunk_size);
+        self
+    }
+
+    /// Sets the [`ChunkMode`] by which the merged result is split into chunks.
+    ///
+    /// Under [`ChunkMode::RowGroup`] the session chunk size keeps its default,
+    /// and bounds the rows decoded at a time.
+    #[must_use]
+    pub const fn chunk_mode(mut self, chunk_mode: ChunkMode) -> Self {
+        self.chunk_mode = chunk_mode;
+        self
+    }
+
+    /// Sets the number of worker threads used to decode the queries of the
+    /// session, as for [`DataBackendSession::set_decode_threads`].
+    #[must_use]
+    pub const fn decode_threads(mut self, n: usize) -> Self {
+        self.decode_threads = Some(n);
+        self
+    }
+
+    /// Sets the [`SortKey`] by which the queries of the session are ordered and
+    /// merged.
+    ///
+    /// Files are expected to be stored ordered by `ts_init`, so ordering by any
+    /// other key sorts each query in DataFusion, which buffers the whole query.
+    #[must_use]
+    pub const fn sort_key(mut self, sort_key: SortKey) -> Self {
+        self.sort_key = sort_key;
+        self
+    }
+
+    /// Sets whether local files are read through memory maps, rather than into
+    /// buffers, which reduces the resident memory of queries on large files.
+    ///
+    /// Only local paths are memory mapped, and object store URIs are read as
+    /// usual.
+    #[cfg(feature = "mmap")]
+    #[must_use]
+    pub const fn use_mmap(mut self, use_mmap: bool) -> Self {
+        self.use_mmap = use_mmap;
+        self
+    }
+
+    /// Sets whether consecutive exact duplicates are dropped from the merged
+    /// results of the session.
+    ///
+    /// Only data identical in every field to the data before it is dropped, so
+    /// distinct data sharing a timestamp is kept.
+    #[must_use]
+    pub const fn dedup(mut self, dedup: bool) -> Self {
+        self.dedup = dedup;
+        self
+    }
+
+    /// Sets whether the merged results of the session are stably sorted by the
+    /// session [`SortKey`], to repair files which are not fully ordered.
+    ///
+    /// The merge expects each file to be ordered, so otherwise a result is
+    /// only partially ordered. Sorting on read collects the whole merged result
+    /// into memory before yielding its first data, as estimated by
+    /// [`QueryResult::estimated_memory_bytes`], and sorts it in `O(n log n)`,
+    /// so a large unordered file is better rewritten in order once. Data
+    /// sharing a timestamp keeps its merged order.
+    #[must_use]
+    pub const fn sort_on_read(mut self, sort_on_read: bool) -> Self {
+        self.sort_on_read = sort_on_read;
+        self
+    }
+
+    /// Sets whether optional fields missing from a file, such as from an older
+    /// schema, are decoded as zero rather than failing the query.
+    ///
+    /// The optional fields are those which may be projected away with
+    /// [`DataBackendSession::add_file_projected`]: the integer fields other than
+    /// `ts_init`, such as prices, sizes, `ts_event` and sequence numbers. A
+    /// missing required field still returns a [`PersistenceError::SchemaMismatch`].
+    #[must_use]
+    pub const fn allow_missing_optional(mut self, allow_missing_optional: bool) -> Self {
+        self.allow_missing_optional = allow_missing_optional;
+        self
+    }
+
+    /// Sets whether columns of a file which are not decoded for its data type,
+    /// such as vendor columns, are ignored, which is the default.
+    ///
+    /// When disallowed, a query projecting any column other than those decoded
+    /// and the `instrument_id` or `bar_type` columns of a file returns a
+    /// [`PersistenceError::UnexpectedColumns`] error naming them.
+    #[must_use]
+    pub const fn allow_extra_columns(mut self, allow_extra_columns: bool) -> Self {
+        self.reject_extra_columns = !allow_extra_columns;
+        self
+    }
+
+    /// Sets the [`NullPolicy`] for nulls in the price and size fields of the
+    /// queries of the session, which by default fail to decode.
+    #[must_use]
+    pub const fn null_policy(mut self, null_policy: NullPolicy) -> Self {
+        self.null_policy = null_policy;
+        self
+    }
+
+    /// Sets the [`PrecisionPolicy`] for the price and size precisions of the
+    /// queries of the session, which by default are read from each file.
+    #[must_use]
+    pub const fn precision_policy(mut self, precision_policy: PrecisionPolicy) -> Self {
+        self.precision_policy = precision_policy;
+        self
+    }
+
+    /// Builds the configured [`DataBackendSession`].
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error:
+    /// - If the chunk size is zero.
+    /// - If the number of decode threads is zero.
+    /// - If a precision of the [`PrecisionPolicy`] exceeds [`FIXED_PRECISION`].
+    pub fn build(self) -> Result<DataBackendSession, PersistenceError> {
+        let chunk_size = match self.chunk_mode {
+            ChunkMode::RowCount(0) => return Err(PersistenceError::InvalidChunkSize),
+            ChunkMode::RowCount(n) => n,
+            ChunkMode::RowGroup => DEFAULT_CHUNK_SIZE,
+        };
+        if self.decode_threads == Some(0) {
+            return Err(PersistenceError::InvalidDecodeThreads);
+        }
+        if let PrecisionPolicy::Expect {
+            price_precision,
+            size_precision,
+        }
+        | PrecisionPolicy::Override {
+            price_precision,
+            size_precision,
+        } = self.precision_policy
+        {
+            let precision = price_precision.max(size_precision);
+            if precision > FIXED_PRECISION {
+                return Err(PersistenceError::InvalidPrecision(precision));
+            }
+        }
+
+        Ok(DataBackendSession {
+            session_ctx: new_session_context(self.use_mmap, None, None),
+            queries: Vec::default(),
+            instrument_queries: Vec::default(),
+            tables: Vec::default(),
+            row_groups: HashMap::new(),
+            chunk_size,
+            chunk_mode: self.chunk_mode,
+            runtime: Arc::new(new_runtime(self.decode_threads)),
+            decode_threads: self.decode_threads,
+            sort_key: self.sort_key,
+            use_mmap: self.use_mmap,
+            target_partitions: None,
+            reader_batch_size: None,
+            #[cfg(feature = "object_store")]
+            retry_policy: None,
+            dedup: self.dedup,
+            allow_missing_optional: self.allow_missing_optional,
+            reject_extra_columns: self.reject_extra_columns,
+            sort_on_read: self.sort_on_read,
+            null_policy: self.null_policy,
+            precision_policy: self.precision_policy,
+            errors: DecodeErrors::default(),
+            metrics: SharedMetrics::default(),
+            cache: None,
+        })
+    }
+}
+
+/// Provides a DataFusion session and registers DataFusion queries.
+///
+/// The session is used to register data sources and make queries on them. A
+/// query returns a Chunk of Arrow records. It is decoded and converted into
+/// a Vec of data by types that implement [`DecodeDataFromRecordBatch`].
+#[cfg_attr(
+    feature = "python",
+    pyo3::pyclass(module = "nautilus_trader.core.nautilus_pyo3.persistence")
+)]
+pub struct DataBackendSession {
+    pub chunk_size: usize,
+    pub runtime: Arc<tokio::runtime::Runtime>,
+    session_ctx: SessionContext,
+    queries: Vec<PendingQuery>,
+    instrument_queries: Vec<InstrumentQuery>,
+    tables: Vec<TableInfo>,
+    row_groups: HashMap<String, Vec<usize>>,
+    chunk_mode: ChunkMode,
+    decode_threads: Option<usize>,
+    sort_key: SortKey,
+    use_mmap: bool,
+    target_partitions: Option<usize>,
+    reader_batch_size: Option<usize>,
+    #[cfg(feature = "object_store")]
+    retry_policy: Option<RetryPolicy>,
+    dedup: bool,
+    allow_missing_optional: bool,
+    reject_extra_columns: bool,
+    sort_on_read: bool,
+    null_policy: NullPolicy,
+    precision_policy: PrecisionPolicy,
+    errors: DecodeErrors,
+    metrics: SharedMetrics,
+    cache: Option<Arc<Mutex<QueryCache>>>,
+}
+
+impl DataBackendSession {
+    /// Creates a new [`DataBackendSession`] instance.
+    ///
+    /// This is equivalent to `DataBackendSession::builder().chunk_size(chunk_size).build()`.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if `chunk_size` is zero.
+    pub fn new(chunk_size: usize) -> Result<Self, PersistenceError> {
+        Self::builder().chunk_size(chunk_size).build()
+    }
+
+    /// Returns a [`DataBackendSessionBuilder`] to configure a new session.
+    #[must_use]
+    pub fn builder() -> DataBackendSessionBuilder {
+        DataBackendSessionBuilder::default()
+    }
+
+    /// Returns the [`SortKey`] by which the queries of the session are ordered.
+    #[must_use]
+    pub const fn sort_key(&self) -> SortKey {
+        self.sort_key
+    }
+
+    /// Returns the [`ChunkMode`] by which the merged result is split into chunks.
+    #[must_use]
+    pub const fn chunk_mode(&self) -> ChunkMode {
+        self.chunk_mode
+    }
+
+    /// Returns the number of worker threads used to decode the queries of the
+    /// session, or `None` for the number of CPU cores.
+    #[must_use]
+    pub const fn decode_threads(&self) -> Option<usize> {
+        self.decode_threads
+    }
+
+    /// Returns whether local files are read through memory maps.
+    #[must_use]
+    pub const fn uses_mmap(&self) -> bool {
+        self.use_mmap
+    }
+
+    /// Sets the number of worker threads used to decode the queries of the
+    /// session.
+    ///
+    /// Each registered query is decoded by its own task, so up to `n` queries
+    /// are decoded in parallel while the results are still merged in `ts_init`
+    /// order. This applies to queries registered after the call, and defaults
+    /// to the number of CPU cores.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if `n` is zero.
+    pub fn set_decode_threads(&mut self, n: usize) -> Result<(), PersistenceError> {
+        if n == 0 {
+            return Err(PersistenceError::InvalidDecodeThreads);
+        }
+
+        self.decode_threads = Some(n);
+        self.runtime = Arc::new(new_runtime(self.decode_threads));
+        Ok(())
+    }
+
+    /// Sets the [`RetryPolicy`] for the reads of the object stores registered for
+    /// `s3://` and `gs://` URIs after the call, which wraps each store in a
+    /// [`RetryingObjectStore`].
+    #[cfg(feature = "object_store")]
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.retry_policy = Some(policy);
+    }
+
+    /// Sets the number of DataFusion target partitions, into which the operators
+    /// of a query plan such as sorts and aggregations are split to run in
+    /// parallel. This applies to queries registered after the call, and defaults
+    /// to the number of CPU cores.
+    ///
+    /// File scans are not repartitioned, so a single file is still read by one
+    /// partition. The partitions run as tasks on the worker threads set by
+    /// [`DataBackendSession::set_decode_threads`], so partitions beyond the
+    /// number of decode threads add no parallelism.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if `n` is zero.
+    pub fn set_target_partitions(&mut self, n: usize) -> Result<(), PersistenceError> {
+        if n == 0 {
+            return Err(PersistenceError::InvalidTargetPartitions);
+        }
+
+        self.target_partitions = Some(n);
+        self.session_ctx
+            .state_ref()
+            .write()
+            .config_mut()
+            .options_mut()
+            .execution
+            .target_partitions = n;
+        Ok(())
+    }
+
+    /// Sets the number of rows of the record batches read by the DataFusion
+    /// scans, which applies to queries registered after the call and defaults
+    /// to 8192.
+    ///
+    /// This is the granularity of reading and decompressing the files, and of
+    /// each call to decode a batch into data, while the session `chunk_size` is
+    /// the number of data items in each chunk of the result handed to the
+    /// caller, such as each [`CVec`] returned over FFI. A query decodes its
+    /// batches in slices of at most `chunk_size` rows, so a batch size below the
+    /// chunk size decodes smaller slices without changing the chunks of the
+    /// result.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if `n` is zero.
+    pub fn set_reader_batch_size(&mut self, n: usize) -> Result<(), PersistenceError> {
+        if n == 0 {
+            return Err(PersistenceError::InvalidReaderBatchSize);
+        }
+
+        self.reader_batch_size = Some(n);
+        self.session_ctx
+            .state_ref()
+            .write()
+            .config_mut()
+            .options_mut()
+            .execution
+            .batch_size = n;
+        Ok(())
+    }
+
+    /// Enables a cache of decoded query results, holding up to an estimated
+    /// `capacity_bytes` of data and evicting the least recently used results.
+    ///
+    /// A query is cached once its result has been fully consumed without decode
+    /// errors, and a later query with the same table name, SQL and data type is
+    /// then served from the cache without being scanned or decoded. The results
+    /// of a table stay cached until it is deregistered or the session cleared, so
+    /// a table name should not be reused for a different file in the meantime.
+    ///
+    /// Enabling the cache again replaces it with an empty cache.
+    pub fn enable_cache(&mut self, capacity_bytes: usize) {
+        self.cache = Some(Arc::new(Mutex::new(QueryCache::new(capacity_bytes))));
+    }
+
+    /// Returns the statistics of the query cache, or `None` if it is disabled.
+    #[must_use]
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache
+            .as_ref()
+            .map(|cache| cache.lock().unwrap().stats())
+    }
+
+    pub fn write_data<T: EncodeToRecordBatch>(
+        data: &[T],
+        metadata: &HashMap<String, String>,
+        stream: &mut dyn WriteStream,
+    ) -> Result<(), PersistenceError> {
+        let record_batch = T::encode_batch(metadata, data)?;
+        stream.write(&record_batch)?;
+        Ok(())
+    }
+
+    /// Query a file for its records. the caller must specify `T` to indicate
+    /// the kind of data expected from this query.
+    ///
+    /// `table_name`: Logical `table_name` assigned to this file. Queries to this file should address the
+    /// file by its table name.
+    /// `file_path`: Path to file, or an `s3://` or `gs://` URI with the `object_store` feature
+    /// `sql_query`: A custom sql query to retrieve records from file. If no query is provided a default
+    /// query "SELECT * FROM <`table_name`>" is run.
+    ///
+    /// The same file can be added under several table names, such as to split
+    /// it with a different query for each. Each table is registered separately
+    /// and keeps its own query, statistics and cache entries, and all of their
+    /// queries are merged into the result. A table name which is already
+    /// registered returns an error, until it is deregistered.
+    ///
+    /// The columns projected by the query are validated against the Arrow schema
+    /// for `T` before any data is decoded, and a [`PersistenceError::SchemaMismatch`]
+    /// naming the first mismatching column is returned if a required column is
+    /// missing or has the wrong data type. Additional columns are ignored.
+    /// Timestamps such as `ts_init` may also be stored as Arrow timestamps of any
+    /// unit, which are converted to nanoseconds when decoded.
+    ///
+    /// # Safety
+    ///
+    /// The file data must be ordered by the `ts_init` in ascending order for this
+    /// to work correctly.
+    pub fn add_file<T>(
+        &mut self,
+        table_name: &str,
+        file_path: &str,
+        sql_query: Option<&str>,
+    ) -> Result<(), PersistenceError>
+    where
+        T: DecodeDataFromRecordBatch + NautilusDataTypeProvider + Into<Data>,
+    {
+        self.add_file_with_chunk_size::<T>(table_name, file_path, sql_query, None)
+    }
+
+    /// Query a file for all its records and collect them, for the common case of
+    /// reading a single file at once. The caller must specify `T` to indicate
+    /// the kind of data expected from this query.
+    ///
+    /// Equivalent to [`DataBackendSession::add_file`] with the default query,
+    /// followed by collecting [`QueryResult::flatten_results`], so any other
+    /// registered queries are consumed and merged into the result as well. Use
+    /// the lower level methods to stream large files rather than hold them in
+    /// memory.
+    ///
+    /// # Safety
+    ///
+    /// The file data must be ordered by the `ts_init` in ascending order for this
+    /// to work correctly.
+    pub fn collect<T>(
+        &mut self,
+        table_name: &str,
+        file_path: &str,
+    ) -> Result<Vec<Data>, PersistenceError>
+    where
+        T: DecodeDataFromRecordBatch + NautilusDataTypeProvider + Into<Data>,
+    {
+        self.add_file::<T>(table_name, file_path, None)?;
+        self.get_query_result().flatten_results().collect()
+    }
+
+    /// Query a file for all its records and collect them into a `Vec` of `T`,
+    /// decoding directly into the concrete type rather than wrapping each record
+    /// in a [`Data`].
+    ///
+    /// Unlike [`DataBackendSession::collect`], only the records of the file are
+    /// returned, ordered by the session [`SortKey`], and any other registered
+    /// queries are left pending. A file of another data type returns a
+    /// [`PersistenceError::SchemaMismatch`] error. The columns of the file are
+    /// checked as by [`DataBackendSession::add_file`], and the table is listed by
+    /// [`DataBackendSession::registered_tables`] once collected.
+    ///
+    /// # Safety
+    ///
+    /// The file data must be ordered by the `ts_init` in ascending order for this
+    /// to work correctly.
+    pub fn collect_typed<T>(
+        &mut self,
+        table_name: &str,
+        file_path: &str,
+    ) -> Result<Vec<T>, PersistenceError>
+    where
+        T: DecodeFromRecordBatch + NautilusDataTypeProvider,
+    {
+        self.register_parquet_file(table_name, file_path)?;
+        self.with_registered_table(table_name, |session| {
+            let stats = file_stats(file_path)?;
+            let sql_query = match session.sort_key {
+                SortKey::TsInit => format!("SELECT * FROM {table_name}"),
+                sort_key => format!("SELECT * FROM {table_name} ORDER BY {sort_key}"),
+            };
+
+            let (query, _) = session.run_query::<T>(sql_query)?;
+            let projection = session.projection::<T>(query.schema().as_arrow())?;
+            let overrides = session.precision_overrides(query.schema().as_arrow(), None)?;
+            let batches = session.runtime.block_on(query.collect())?;
+
+            let mut data = Vec::new();
+            for batch in batches {
+                let batch = batch.project(&projection).map_err(EncodingError::from)?;
+                let batch = apply_null_policy(normalize_timestamps(batch)?, session.null_policy)?;
+                let mut metadata = batch.schema().metadata().clone();
+                metadata.extend(overrides.clone().unwrap_or_default());
+                data.extend(T::decode_batch(&metadata, batch)?);
+            }
+            session
+                .tables
+                .push(table_info::<T>(table_name, stats, session.chunk_size));
+            Ok(data)
+        })
+    }
+
+    /// Query a file of instrument definitions, as written by
+    /// [`crate::parquet::write_instruments_to_parquet`], to be collected with any
+    /// other registered instrument queries by [`DataBackendSession::get_instruments`].
+    ///
+    /// Instruments are not market data, so they are not merged into the query
+    /// results of the session. The table is listed by
+    /// [`DataBackendSession::registered_tables`] with the data type
+    /// [`NautilusDataType::InstrumentAny`]. A file of another data type returns a
+    /// [`PersistenceError::SchemaMismatch`] error.
+    ///
+    /// `sql_query`: A custom SQL query to retrieve instruments from the file,
+    /// defaulting to `SELECT * FROM <table_name>`.
+    pub fn add_instrument_file(
+        &mut self,
+        table_name: &str,
+        file_path: &str,
+        sql_query: Option<&str>,
+    ) -> Result<(), PersistenceError> {
+        self.register_parquet_file(table_name, file_path)?;
+        self.with_registered_table(table_name, |session| {
+            let stats = file_stats(file_path)?;
+            let default_query = format!("SELECT * FROM {table_name}");
+            let sql_query = sql_query.unwrap_or(&default_query);
+            let query = session
+                .runtime
+                .block_on(session.session_ctx.sql(sql_query))?;
+            session.projection::<InstrumentAny>(query.schema().as_arrow())?;
+
+            session.tables.push(table_info::<InstrumentAny>(
+                table_name,
+                stats,
+                session.chunk_size,
+            ));
+            session.instrument_queries.push(InstrumentQuery {
+                table_name: table_name.to_string(),
+                sql_query: sql_query.to_string(),
+            });
+            Ok(())
+        })
+    }
+
+    /// Consumes the registered instrument queries and collects their instruments,
+    /// in order of registration, with those of each query ordered by `ts_init`
+    /// and then by instrument ID.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if a query fails, or its instruments fail
+    /// to decode.
+    pub fn get_instruments(&mut self) -> Result<Vec<InstrumentAny>, PersistenceError> {
+        let mut instruments = Vec::new();
+        for query in std::mem::take(&mut self.instrument_queries) {
+            let sql_query = format!(
+                "SELECT * FROM ({}) ORDER BY ts_init, instrument_id",
+                query.sql_query
+            );
+            let query = self.runtime.block_on(self.session_ctx.sql(&sql_query))?;
+            let projection = decode_projection::<InstrumentAny>(query.schema().as_arrow())?;
+            for batch in self.runtime.block_on(query.collect())? {
+                let batch = batch.project(&projection).map_err(EncodingError::from)?;
+                instruments.extend(decode_instruments(&batch)?);
+            }
+        }
+        Ok(instruments)
+    }
+
+    /// Query a file of instrument definitions and collect them ordered by
+    /// `ts_init` and then by instrument ID.
+    ///
+    /// This is equivalent to [`DataBackendSession::add_instrument_file`] followed
+    /// by [`DataBackendSession::get_instruments`], so the instruments of any other
+    /// registered instrument queries are collected first.
+    pub fn collect_instruments(
+        &mut self,
+        table_name: &str,
+        file_path: &str,
+    ) -> Result<Vec<InstrumentAny>, PersistenceError> {
+        self.add_instrument_file(table_name, file_path, None)?;
+        self.get_instruments()
+    }
+
+    /// Query a file for its records, decoding at most `chunk_size` rows of the
+    /// file at a time. The caller must specify `T` to indicate the kind of data
+    /// expected from this query.
+    ///
+    /// `chunk_size`: Overrides the session chunk size for this table only, falling
+    /// back to the session value if `None`. This bounds the data buffered for the
+    /// table, while the merged result is still chunked by the session chunk size.
+    ///
+    /// See [`DataBackendSession::add_file`] for the remaining arguments.
+    ///
+    /// # Safety
+    ///
+    /// The file data must be ordered by the `ts_init` in ascending order for this
+    /// to work correctly.
+    pub fn add_file_with_chunk_size<T>(
+        &mut self,
+        table_name: &str,
+        file_path: &str,
+        sql_query: Option<&str>,
+        chunk_size: Option<usize>,
+    ) -> Result<(), PersistenceError>
+    where
+        T: DecodeDataFromRecordBatch + NautilusDataTypeProvider + Into<Data>,
+    {
+        self.register_parquet_file(table_name, file_path)?;
+        self.with_registered_table(table_name, |session| {
+            let default_query = format!("SELECT * FROM {}", &table_name);
+            let sql_query = sql_query.unwrap_or(&default_query);
+            session.add_query::<T>(
+                table_name,
+                file_stats(file_path)?,
+                sql_query,
+                chunk_size,
+                None,
+            )
+        })
+    }
+
+    /// Query a file for the records of a single instrument. The caller must
+    /// specify `T` to indicate the kind of data expected from this query.
+    ///
+    /// `instrument_id`: The instrument to retrieve records for.
+    ///
+    /// If the file has an `instrument_id` column, the filter is pushed into the
+    /// DataFusion scan so only the records of the instrument are decoded.
+    /// Otherwise the instrument of the file is read from its schema metadata, and
+    /// no records are returned if it differs. A missing instrument results in an
+    /// empty result rather than an error.
+    ///
+    /// See [`DataBackendSession::add_file`] for the remaining arguments.
+    ///
+    /// # Safety
+    ///
+    /// The file data must be ordered by the `ts_init` in ascending order for this
+    /// to work correctly.
+    pub fn add_file_for_instrument<T>(
+        &mut self,
+        table_name: &str,
+        file_path: &str,
+        instrument_id: &InstrumentId,
+    ) -> Result<(), PersistenceError>
+    where
+        T: DecodeDataFromRecordBatch + NautilusDataTypeProvider + Into<Data>,
+    {
+        self.register_parquet_file(table_name, file_path)?;
+        self.with_registered_table(table_name, |session| {
+            let stats = file_stats(file_path)?;
+
+            let table = session
+                .runtime
+                .block_on(session.session_ctx.table(table_name))?;
+            let schema = table.schema().as_arrow();
+            let instrument_id = instrument_id.to_string();
+
+            if schema.index_of(KEY_INSTRUMENT_ID).is_ok() {
+                let sql_query = format!(
+                    "SELECT * FROM {table_name} WHERE {KEY_INSTRUMENT_ID} = '{}'",
+                    instrument_id.replace('\'', "''")
+                );
+                // Rows of a multi-instrument file are decoded with the filtered instrument
+                let metadata = HashMap::from([(KEY_INSTRUMENT_ID.to_string(), instrument_id)]);
+                return session.add_query::<T>(table_name, stats, &sql_query, None, Some(metadata));
+            }
+
+            let file_instrument_id = match schema.metadata().get(KEY_INSTRUMENT_ID) {
+                Some(file_instrument_id) => Some(file_instrument_id.clone()),
+                None => schema
+                    .metadata()
+                    .get(KEY_BAR_TYPE)
+                    .and_then(|bar_type| BarType::from_str(bar_type).ok())
+                    .map(|bar_type| bar_type.instrument_id().to_string()),
+            };
+            let sql_query = if file_instrument_id.as_ref() == Some(&instrument_id) {
+                format!("SELECT * FROM {table_name}")
+            } else {
+                format!("SELECT * FROM {table_name} WHERE false")
+            };
+            session.add_query::<T>(table_name, stats, &sql_query, None, None)
+        })
+    }
+
+    /// Query a file for the records of a set of instruments. The caller must
+    /// specify `T` to indicate the kind of data expected from this query.
+    ///
+    /// `instrument_ids`: The instruments to retrieve records for.
+    ///
+    /// If the file has an `instrument_id` column, a filtered scan is added per
+    /// instrument, so the records of each are decoded with their own instrument
+    /// and merged by `ts_init` with the other queries. Otherwise the instrument
+    /// of the file is read from its schema metadata, and all of its records are
+    /// returned if it is one of `instrument_ids`. Instruments missing from the
+    /// file, or an empty `instrument_ids`, result in an empty result rather than
+    /// an error.
+    ///
+    /// See [`DataBackendSession::add_file`] for the remaining arguments.
+    ///
+    /// # Safety
+    ///
+    /// The file data must be ordered by the `ts_init` in ascending order for this
+    /// to work correctly.
+    pub fn add_file_for_instruments<T>(
+        &mut self,
+        table_name: &str,
+        file_path: &str,
+        instrument_ids: &[InstrumentId],
+    ) -> Result<(), PersistenceError>
+    where
+        T: DecodeDataFromRecordBatch + NautilusDataTypeProvider + Into<Data>,
+    {
+        self.register_parquet_file(table_name, file_path)?;
+        self.with_registered_table(table_name, |session| {
+            let stats = file_stats(file_path)?;
+
+            let table = session
+                .runtime
+                .block_on(session.session_ctx.table(table_name))?;
+            let schema = table.schema().as_arrow();
+            let mut instrument_ids: Vec<String> =
+                instrument_ids.iter().map(ToString::to_string).collect();
+            instrument_ids.sort();
+            instrument_ids.dedup();
+
+            if schema.index_of(KEY_INSTRUMENT_ID).is_ok() && !instrument_ids.is_empty() {
+                for instrument_id in instrument_ids {
+                    let sql_query = format!(
+                        "SELECT * FROM {table_name} WHERE {KEY_INSTRUMENT_ID} = '{}'",
+                        instrument_id.replace('\'', "''")
+                    );
+                    let metadata = HashMap::from([(KEY_INSTRUMENT_ID.to_string(), instrument_id)]);
+                    session.add_query::<T>(table_name, stats, &sql_query, None, Some(metadata))?;
+                }
+                return Ok(());
+            }
+
+            let file_instrument_id = match schema.metadata().get(KEY_INSTRUMENT_ID) {
+                Some(file_instrument_id) => Some(file_instrument_id.clone()),
+                None => schema
+                    .metadata()
+                    .get(KEY_BAR_TYPE)
+                    .and_then(|bar_type| BarType::from_str(bar_type).ok())
+                    .map(|bar_type| bar_type.instrument_id().to_string()),
+            };
+            let sql_query = match file_instrument_id {
+                Some(file_instrument_id) if instrument_ids.contains(&file_instrument_id) => {
+                    format!("SELECT * FROM {table_name}")
+                }
+                _ => format!("SELECT * FROM {table_name} WHERE false"),
+            };
+            session.add_query::<T>(table_name, stats, &sql_query, None, None)
+        })
+    }
+
+    /// Query a file for its records, scanning and decoding only the `columns` of
+    /// `T`. The caller must specify `T` to indicate the kind of data expected
+    /// from this query.
+    ///
+    /// `columns`: The fields of `T` to read, such as `["bid_price", "ask_price", "ts_init"]`.
+    ///
+    /// Only integer fields, such as prices, sizes, `ts_event` and sequence
+    /// numbers, may be projected away, and are decoded as zero. The returned
+    /// data is otherwise fully populated, and the zeroed fields should not be
+    /// relied upon. Returns a [`PersistenceError::InvalidProjection`] if a column
+    /// is not a field of `T`, or if a required column is projected away: the
+    /// `ts_init`, any enum or string field, and an `instrument_id` or `bar_type`
+    /// column of the file.
+    ///
+    /// See [`DataBackendSession::add_file`] for the remaining arguments.
+    ///
+    /// # Safety
+    ///
+    /// The file data must be ordered by the `ts_init` in ascending order for this
+    /// to work correctly.
+    pub fn add_file_projected<T>(
+        &mut self,
+        table_name: &str,
+        file_path: &str,
+        columns: &[&str],
+    ) -> Result<(), PersistenceError>
+    where
+        T: DecodeDataFromRecordBatch + NautilusDataTypeProvider + Into<Data>,
+    {
+        let schema = T::get_schema(None);
+        let keys = [KEY_INSTRUMENT_ID, KEY_BAR_TYPE];
+        if let Some(column) = columns
+            .iter()
+            .find(|column| schema.index_of(column).is_err() && !keys.contains(column))
+        {
+            return Err(PersistenceError::InvalidProjection {
+                column: (*column).to_string(),
+                reason: "is not a field of the data type",
+            });
+        }
+
+        self.register_parquet_file(table_name, file_path)?;
+        self.with_registered_table(table_name, |session| {
+            let table = session
+                .runtime
+                .block_on(session.session_ctx.table(table_name))?;
+            let file_schema = table.schema().as_arrow().clone();
+            let select_list = projected_select_list::<T>(&file_schema, columns, &keys)?;
+
+            let sql_query = format!("SELECT {} FROM {table_name}", select_list.join(", "));
+            session.add_query::<T>(table_name, file_stats(file_path)?, &sql_query, None, None)
+        })
+    }
+
+    /// Query a file with non-standard column names for records of `T`, aliasing
+    /// its columns to the names of the schema of `T`. The caller must specify `T`
+    /// to indicate the kind of data expected from this query.
+    ///
+    /// `column_map`: The names of the file columns mapped to the field names they
+    /// hold, such as `timestamp` to `ts_init`. Columns already named as a field
+    /// need no mapping.
+    ///
+    /// Every field of `T` must be found by name after the mapping, rather than
+    /// by position. Returns a [`PersistenceError::MissingColumns`] error listing
+    /// the fields which are not, or a [`PersistenceError::InvalidProjection`]
+    /// error if a mapped column is not in the file. See
+    /// [`DataBackendSession::add_file`] for the remaining arguments.
+    ///
+    /// # Safety
+    ///
+    /// The file data must be ordered by the `ts_init` in ascending order for this
+    /// to work correctly.
+    pub fn add_file_with_column_map<T>(
+        &mut self,
+        table_name: &str,
+        file_path: &str,
+        column_map: &HashMap<String, String>,
+    ) -> Result<(), PersistenceError>
+    where
+        T: DecodeDataFromRecordBatch + NautilusDataTypeProvider + Into<Data>,
+    {
+        self.register_parquet_file(table_name, file_path)?;
+        self.with_registered_table(table_name, |session| {
+            let table = session
+                .runtime
+                .block_on(session.session_ctx.table(table_name))?;
+            let file_schema = table.schema().as_arrow().clone();
+            let select_list = column_map_select_list::<T>(&file_schema, column_map)?;
+
+            let sql_query = format!("SELECT {} FROM {table_name}", select_list.join(", "));
+            session.add_query::<T>(table_name, file_stats(file_path)?, &sql_query, None, None)
+        })
+    }
+
+    /// Query a file for the records matching a structured `filter`. The caller
+    /// must specify `T` to indicate the kind of data expected from this query.
+    ///
+    /// `filter`: The predicate on the columns of the file, such as
+    /// `Filter::col("price").gt(lit(1.05))`.
+    ///
+    /// The filter is translated into the `WHERE` clause of the query, and is
+    /// pushed into the DataFusion scan. See [`DataBackendSession::add_file`] for
+    /// the remaining arguments.
+    ///
+    /// # Safety
+    ///
+    /// The file data must be ordered by the `ts_init` in ascending order for this
+    /// to work correctly.
+    pub fn add_file_filtered<T>(
+        &mut self,
+        table_name: &str,
+        file_path: &str,
+        filter: &Filter,
+    ) -> Result<(), PersistenceError>
+    where
+        T: DecodeDataFromRecordBatch + NautilusDataTypeProvider + Into<Data>,
+    {
+        let sql_query = format!("SELECT * FROM {table_name} WHERE {}", filter.to_sql()?);
+        self.add_file::<T>(table_name, file_path, Some(&sql_query))
+    }
+
+    /// Query a file of bars for the records of a single bar type.
+    ///
+    /// `bar_type`: The bar type to retrieve bars for, such as
+    /// `ADABTC.BINANCE-1-MINUTE-LAST-EXTERNAL`.
+    ///
+    /// The bar type is parsed before the file is registered, and an error is
+    /// returned if it is malformed. If the file has a `bar_type` column, the
+    /// filter is pushed into the DataFusion scan so only the matching bars are
+    /// decoded. Otherwise the bar type of the file is read from its schema
+    /// metadata, and no records are returned if it differs.
+    ///
+    /// See [`DataBackendSession::add_file`] for the remaining arguments.
+    ///
+    /// # Safety
+    ///
+    /// The file data must be ordered by the `ts_init` in ascending order for this
+    /// to work correctly.
+    pub fn add_file_for_bar_type(
+        &mut self,
+        table_name: &str,
+        file_path: &str,
+        bar_type: &str,
+    ) -> Result<(), PersistenceError> {
+        let bar_type = BarType::from_str(bar_type)?.to_string();
+        self.register_parquet_file(table_name, file_path)?;
+        self.with_registered_table(table_name, |session| {
+            let stats = file_stats(file_path)?;
+
+            let table = session
+                .runtime
+                .block_on(session.session_ctx.table(table_name))?;
+            let schema = table.schema().as_arrow();
+
+            if schema.index_of(KEY_BAR_TYPE).is_ok() {
+                let sql_query = format!(
+                    "SELECT * FROM {table_name} WHERE {KEY_BAR_TYPE} = '{}'",
+                    bar_type.replace('\'', "''")
+                );
+                // Rows of a multi-type file are decoded with the filtered bar type
+                let metadata = HashMap::from([(KEY_BAR_TYPE.to_string(), bar_type)]);
+                return session.add_query::<Bar>(
+                    table_name,
+                    stats,
+                    &sql_query,
+                    None,
+                    Some(metadata),
+                );
+            }
+
+            let file_bar_type = schema
+                .metadata()
+                .get(KEY_BAR_TYPE)
+                .and_then(|file_bar_type| BarType::from_str(file_bar_type).ok())
+                .map(|file_bar_type| file_bar_type.to_string());
+            let sql_query = if file_bar_type.as_ref() == Some(&bar_type) {
+                format!("SELECT * FROM {table_name}")
+            } else {
+                format!("SELECT * FROM {table_name} WHERE false")
+            };
+            session.add_query::<Bar>(table_name, stats, &sql_query, None, None)
+        })
+    }
+
+    /// Query a CSV file for its records, with its columns mapped to the fields of
+    /// `T` by `csv_schema`.
+    ///
+    /// The file must have a header row. Its compression is detected from the
+    /// extension, as `.gz`, `.bz2`, `.xz` or `.zst`, and it is otherwise read as
+    /// uncompressed. The records are sorted by `ts_init` in DataFusion, so the
+    /// file need not be ordered, and are then decoded and merged identically to
+    /// a parquet file.
+    ///
+    /// See [`DataBackendSession::add_file`] for the remaining arguments.
+    pub fn add_csv_file<T>(
+        &mut self,
+        table_name: &str,
+        file_path: &str,
+        csv_schema: &CsvSchema,
+    ) -> Result<(), PersistenceError>
+    where
+        T: DecodeDataFromRecordBatch + NautilusDataTypeProvider + Into<Data>,
+    {
+        let (compression, extension) = csv_compression(file_path);
+        let options = CsvReadOptions::new()
+            .has_header(true)
+            .file_extension(extension)
+            .file_compression_type(compression);
+        self.runtime.block_on(
+            self.session_ctx
+                .register_csv(table_name, file_path, options),
+        )?;
+        self.with_registered_table(table_name, |session| {
+            let sql_query = format!(
+                "SELECT {} FROM {table_name} ORDER BY ts_init",
+                csv_schema.select_list(&T::get_schema(None))
+            );
+            let metadata = Some(csv_schema.metadata.clone());
+            session.add_query::<T>(table_name, (None, None, None), &sql_query, None, metadata)
+        })
+    }
+
+    /// Query an Arrow IPC file, such as a `.feather` file, for its records. The
+    /// caller must specify `T` to indicate the kind of data expected from this
+    /// query.
+    ///
+    /// The file must hold the Arrow schema and metadata for `T`, and is validated,
+    /// decoded and merged identically to a parquet file. An IPC file has no
+    /// statistics, so the `ts_init` range of the table is unknown.
+    ///
+    /// See [`DataBackendSession::add_file`] for the remaining arguments.
+    ///
+    /// # Safety
+    ///
+    /// The file data must be ordered by the `ts_init` in ascending order for this
+    /// to work correctly.
+    pub fn add_ipc_file<T>(
+        &mut self,
+        table_name: &str,
+        file_path: &str,
+    ) -> Result<(), PersistenceError>
+    where
+        T: DecodeDataFromRecordBatch + NautilusDataTypeProvider + Into<Data>,
+    {
+        // Only files with the extension given are read, so match the file itself
+        let extension = Path::new(file_path)
+            .extension()
+            .map(|extension| format!(".{}", extension.to_string_lossy()))
+            .unwrap_or_default();
+        let options = ArrowReadOptions {
+            file_extension: &extension,
+            ..Default::default()
+        };
+        self.runtime.block_on(
+            self.session_ctx
+                .register_arrow(table_name, file_path, options),
+        )?;
+        self.with_registered_table(table_name, |session| {
+            let sql_query = format!("SELECT * FROM {table_name}");
+            session.add_query::<T>(table_name, (None, None, None), &sql_query, None, None)
+        })
+    }
+
+    /// Query an in-memory record batch for its records. The caller must specify
+    /// `T` to indicate the kind of data expected from this query.
+    ///
+    /// `table_name`: Logical `table_name` assigned to this batch. Queries to this batch should
+    /// address the batch by its table name.
+    /// `batch`: The record batch, with the Arrow schema and metadata for `T`
+    ///
+    /// The batch is registered as a DataFusion table, and is queried and merged
+    /// identically to a file.
+    ///
+    /// # Safety
+    ///
+    /// The batch data must be ordered by the `ts_init` in ascending order for this
+    /// to work correctly.
+    pub fn add_batch<T>(
+        &mut self,
+        table_name: &str,
+        batch: RecordBatch,
+    ) -> Result<(), PersistenceError>
+    where
+        T: DecodeDataFromRecordBatch + NautilusDataTypeProvider + Into<Data>,
+    {
+        let stats = batch_stats(&batch);
+        self.session_ctx.register_batch(table_name, batch)?;
+        self.with_registered_table(table_name, |session| {
+            let sql_query = format!("SELECT * FROM {table_name}");
+            session.add_query::<T>(table_name, stats, &sql_query, None, None)
+        })
+    }
+
+    /// Query a table already registered with the session for its records again,
+    /// such as to rerun a query after consuming its result. The caller must
+    /// specify `T` to indicate the kind of data expected from this query.
+    ///
+    /// `sql_query`: A custom SQL query on the table, or `SELECT * FROM {table_name}`
+    /// if `None`.
+    ///
+    /// The statistics of the table are those from its first registration. With
+    /// the cache enabled by [`DataBackendSession::enable_cache`], a repeated query
+    /// is served from the cache.
+    pub fn add_table_query<T>(
+        &mut self,
+        table_name: &str,
+        sql_query: Option<&str>,
+    ) -> Result<(), PersistenceError>
+    where
+        T: DecodeDataFromRecordBatch + NautilusDataTypeProvider + Into<Data>,
+    {
+        let stats = self
+            .tables
+            .iter()
+            .find(|table| table.table_name == table_name)
+            .map_or((None, None, None), |table| {
+                (table.row_count, table.min_ts_init, table.max_ts_init)
+            });
+        let default_query = format!("SELECT * FROM {table_name}");
+        let sql_query = sql_query.unwrap_or(&default_query);
+        self.add_query::<T>(table_name, stats, sql_query, None, None)
+    }
+
+    /// Query a file of time bars for its records resampled to a coarser interval
+    /// of `step` `aggregation`, e.g. 5 minute bars from 1 minute bars.
+    ///
+    /// The bars are grouped by DataFusion into windows aligned to the interval
+    /// boundaries, with the open of the first bar, the highest high, the lowest
+    /// low, the close of the last bar and the total volume of each window. The
+    /// `ts_event` and `ts_init` of a resampled bar are the end of its window,
+    /// including for a final window which is only partially covered by the file.
+    ///
+    /// Returns an error if `aggregation` is not time based, or if the resampled
+    /// interval is not a multiple of the interval of the bars in the file.
+    ///
+    /// # Safety
+    ///
+    /// The file data must be ordered by the `ts_init` in ascending order for this
+    /// to work correctly.
+    pub fn add_file_resampled_bars(
+        &mut self,
+        table_name: &str,
+        file_path: &str,
+        step: usize,
+        aggregation: BarAggregation,
+    ) -> Result<(), PersistenceError> {
+        if !is_time_aggregation(aggregation) {
+            return Err(PersistenceError::UnsupportedAggregation(aggregation));
+        }
+
+        self.register_parquet_file(table_name, file_path)?;
+        self.with_registered_table(table_name, |session| {
+            let stats = file_stats(file_path)?;
+
+            let table = session
+                .runtime
+                .block_on(session.session_ctx.table(table_name))?;
+            let mut metadata = table.schema().as_arrow().metadata().clone();
+            let bar_type = metadata
+                .get(KEY_BAR_TYPE)
+                .and_then(|bar_type| BarType::from_str(bar_type).ok())
+                .ok_or(EncodingError::MissingMetadata(KEY_BAR_TYPE))?;
+            let spec = bar_type.spec();
+            if !is_time_aggregation(spec.aggregation) {
+                return Err(PersistenceError::UnsupportedAggregation(spec.aggregation));
+            }
+
+            let resampled_bar_type = BarType::new(
+                bar_type.instrument_id(),
+                BarSpecification {
+                    step,
+                    aggregation,
+                    price_type: spec.price_type,
+                },
+                bar_type.aggregation_source(),
+            );
+            let interval_ns = get_bar_interval_ns(&bar_type).as_u64();
+            let target_ns = get_bar_interval_ns(&resampled_bar_type).as_u64();
+            if interval_ns == 0
+                || target_ns < interval_ns
+                || target_ns / interval_ns * interval_ns != target_ns
+            {
+                return Err(PersistenceError::InvalidBarInterval {
+                    interval_ns,
+                    target_ns,
+                });
+            }
+
+            // Bars are stamped at their close, so a window contains bars closing
+            // after its start up to and including its end
+            let sql_query = format!(
+                "SELECT \
+                    FIRST_VALUE(open ORDER BY ts_init) AS open, \
+                    MAX(high) AS high, \
+                    MIN(low) AS low, \
+                    LAST_VALUE(close ORDER BY ts_init) AS close, \
+                    SUM(volume) AS volume, \
+                    window_end AS ts_event, \
+                    window_end AS ts_init \
+                FROM ( \
+                    SELECT *, \
+                        (ts_event + arrow_cast({}, 'UInt64')) / arrow_cast({target_ns}, 'UInt64') \
+                            * arrow_cast({target_ns}, 'UInt64') AS window_end \
+                    FROM {table_name} \
+                ) \
+                GROUP BY window_end \
+                ORDER BY window_end",
+                target_ns - 1,
+            );
+            metadata.insert(KEY_BAR_TYPE.to_string(), resampled_bar_type.to_string());
+            session.add_query::<Bar>(table_name, stats, &sql_query, None, Some(metadata))
+        })
+    }
+
+    /// Query a file of trades for time bars aggregated from them, with a bar for
+    /// every `step` units of the `aggregation` holding trades.
+    ///
+    /// The open and close of a bar are the prices of its first and last trades by
+    /// `ts_init`, its high and low the extreme trade prices, and its volume the sum
+    /// of the trade sizes. A bar is stamped at the close of its interval with both
+    /// `ts_event` and `ts_init`, and holds the trades with a `ts_event` after its
+    /// start up to and including its close. Intervals without trades are skipped
+    /// rather than filled, so the bars are not necessarily contiguous.
+    ///
+    /// The bars are `LAST` priced and `INTERNAL` sourced, for the instrument read
+    /// from the schema metadata of the file.
+    ///
+    /// Returns an error if the aggregation is not time based, or `step` is zero.
+    ///
+    /// # Safety
+    ///
+    /// The file data must be ordered by the `ts_init` in ascending order for this
+    /// to work correctly.
+    pub fn trades_to_bars(
+        &mut self,
+        table_name: &str,
+        file_path: &str,
+        step: usize,
+        aggregation: BarAggregation,
+    ) -> Result<(), PersistenceError> {
+        if !is_time_aggregation(aggregation) {
+            return Err(PersistenceError::UnsupportedAggregation(aggregation));
+        }
+        if step == 0 {
+            return Err(PersistenceError::InvalidBarStep);
+        }
+
+        self.register_parquet_file(table_name, file_path)?;
+        self.with_registered_table(table_name, |session| {
+            let stats = file_stats(file_path)?;
+
+            let table = session.runtime.block_on(session.session_ctx.table(table_name))?;
+            let mut metadata = table.schema().as_arrow().metadata().clone();
+            let instrument_id = metadata
+                .get(KEY_INSTRUMENT_ID)
+                .and_then(|instrument_id| InstrumentId::from_str(instrument_id).ok())
+                .ok_or(EncodingError::MissingMetadata(KEY_INSTRUMENT_ID))?;
+
+            let bar_type = BarType::new(
+                instrument_id,
+                BarSpecification {
+                    step,
+                    aggregation,
+                    price_type: PriceType::Last,
+                },
+                AggregationSource::Internal,
+            );
+            let interval_ns = get_bar_interval_ns(&bar_type).as_u64();
+
+            let sql_query = format!(
+                "SELECT \
+                    FIRST_VALUE(price ORDER BY ts_init) AS open, \
+                    MAX(price) AS high, \
+                    MIN(price) AS low, \
+                    LAST_VALUE(price ORDER BY ts_init) AS close, \
+                    SUM(size) AS volume, \
+                    window_end AS ts_event, \
+                    window_end AS ts_init \
+                FROM ( \
+                    SELECT *, \
+                        (ts_event + arrow_cast({}, 'UInt64')) / arrow_cast({interval_ns}, 'UInt64') \
+                            * arrow_cast({interval_ns}, 'UInt64') AS window_end \
+                    FROM {table_name} \
+                ) \
+                GROUP BY window_end \
+                ORDER BY window_end",
+                interval_ns - 1,
+            );
+            metadata.insert(KEY_BAR_TYPE.to_string(), bar_type.to_string());
+            session.add_query::<Bar>(table_name, stats, &sql_query, None, Some(metadata))
+        })
+    }
+
+    /// Query a file of order book deltas for periodic snapshots of the book,
+    /// replayed from the deltas at every `interval_ns` nanoseconds.
+    ///
+    /// `depth`: The number of levels of each side of the book in a snapshot, with
+    /// the remaining levels of the [`OrderBookDepth10`] left empty.
+    ///
+    /// The deltas are ordered by `ts_init` and `sequence` before being replayed
+    /// into a single book, so the file should hold a single instrument. A snapshot
+    /// is emitted at each interval boundary passed by the deltas, as described on
+    /// [`SnapshotSampler`], and a clear action empties the book.
+    ///
+    /// Returns an error if `interval_ns` is zero, or `depth` is not between 1
+    /// and 10.
+    pub fn deltas_to_snapshots(
+        &mut self,
+        table_name: &str,
+        file_path: &str,
+        interval_ns: u64,
+        depth: usize,
+    ) -> Result<(), PersistenceError> {
+        let mut sampler = SnapshotSampler::new(interval_ns, depth)?;
+        self.add_replayed_deltas(table_name, file_path, move |chunk| sampler.process(chunk))
+    }
+
+    /// Query a file of order book deltas for the top `decode_depth` levels of the
+    /// book after each delta, decoded into an [`OrderBookDepth10`] rather than
+    /// emitting the deltas themselves.
+    ///
+    /// Levels beyond `decode_depth` are dropped and left empty. The deltas are
+    /// replayed in the order of `ts_init` and `sequence` into a single book, as
+    /// for [`Self::deltas_to_snapshots`].
+    ///
+    /// Returns an error if `decode_depth` is not between 1 and 10.
+    pub fn add_file_book_depth(
+        &mut self,
+        table_name: &str,
+        file_path: &str,
+        decode_depth: usize,
+    ) -> Result<(), PersistenceError> {
+        if decode_depth == 0 || decode_depth > DEPTH10_LEN {
+            return Err(PersistenceError::InvalidSnapshotDepth(decode_depth));
+        }
+
+        let mut decoder = DepthDecoder::new(decode_depth);
+        self.add_replayed_deltas(table_name, file_path, move |chunk| decoder.process(chunk))
+    }
+
+    /// Registers a query for the deltas of the file at `file_path`, in the order
+    /// of `ts_init` and `sequence`, mapping each decoded chunk with `replay` to
+    /// the order book depth it gives.
+    fn add_replayed_deltas(
+        &mut self,
+        table_name: &str,
+        file_path: &str,
+        mut replay: impl FnMut(IntoIter<Data>) -> Vec<Data> + Send + 'static,
+    ) -> Result<(), PersistenceError> {
+        self.register_parquet_file(table_name, file_path)?;
+        self.with_registered_table(table_name, |session| {
+            let sql_query = format!("SELECT * FROM {table_name} ORDER BY ts_init, sequence");
+            let query = session
+                .runtime
+                .block_on(session.session_ctx.sql(&sql_query))?;
+            let projection = decode_projection::<OrderBookDelta>(query.schema().as_arrow())?;
+            let plan = session.runtime.block_on(query.create_physical_plan())?;
+            let batch_stream = {
+                let _guard = session.runtime.enter();
+                execute_stream(plan, session.session_ctx.task_ctx())?
+            };
+
+            let deltas = session.decode_stream::<OrderBookDelta>(
+                batch_stream,
+                projection,
+                session.chunk_size,
+                None,
+            );
+            let snapshots = deltas.map(move |chunk| replay(chunk).into_iter());
+
+            session.tables.push(TableInfo {
+                table_name: table_name.to_string(),
+                data_type: NautilusDataType::OrderBookDepth10,
+                chunk_size: session.chunk_size,
+                row_count: None,
+                min_ts_init: None,
+                max_ts_init: None,
+            });
+            session.queries.push(PendingQuery {
+                table_name: table_name.to_string(),
+                sql_query,
+                stream: EagerStream::from_stream_with_runtime(snapshots, session.runtime.clone()),
+                size_hint: None,
+                time_range: None,
+                row_groups: Vec::new(),
+                bounds_probe: None,
+            });
+            Ok(())
+        })
+    }
+
+    /// Registers the parquet file at `file_path` with the session as `table_name`.
+    ///
+    /// Under [`ChunkMode::RowGroup`] the row counts of the row groups of a local
+    /// file are read from its footer, to chunk the queries of the table.
+    fn register_parquet_file(
+        &mut self,
+        table_name: &str,
+        file_path: &str,
+    ) -> Result<(), PersistenceError> {
+        #[cfg(feature = "object_store")]
+        self.register_object_store(file_path)?;
+        #[cfg(feature = "http")]
+        self.register_http_store(file_path)?;
+
+        self.runtime.block_on(self.session_ctx.register_parquet(
+            table_name,
+            file_path,
+            parquet_read_options(),
+        ))?;
+
+        if self.chunk_mode == ChunkMode::RowGroup && object_store_uri(file_path).is_none() {
+            self.with_registered_table(table_name, |session| {
+                let metadata = parse_metadata(&File::open(file_path)?)?;
+                let row_groups = metadata
+                    .row_groups()
+                    .iter()
+                    .map(|row_group| row_group.num_rows() as usize)
+                    .filter(|&num_rows| num_rows > 0)
+                    .collect();
+                session
+                    .row_groups
+                    .insert(table_name.to_string(), row_groups);
+                Ok(())
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Runs `f` on the table `table_name` just registered with the session, and
+    /// deregisters the table if `f` fails so the name can be registered again.
+    fn with_registered_table<R>(
+        &mut self,
+        table_name: &str,
+        f: impl FnOnce(&mut Self) -> Result<R, PersistenceError>,
+    ) -> Result<R, PersistenceError> {
+        let result = f(self);
+        if result.is_err() {
+            self.deregister(table_name);
+        }
+        result
+    }
+
+    /// Runs `sql_query` on the registered table `table_name`, and adds the
+    /// decoded result to the session with the `stats` of the table.
+    ///
+    /// The result is ordered by the session [`SortKey`] if it is not `ts_init`,
+    /// the order in which files are stored.
+    ///
+    /// The `metadata` entries, if any, override the schema metadata of the
+    /// query result when decoding.
+    fn add_query<T>(
+        &mut self,
+        table_name: &str,
+        stats: TableStats,
+        sql_query: &str,
+        chunk_size: Option<usize>,
+        metadata: Option<HashMap<String, String>>,
+    ) -> Result<(), PersistenceError>
+    where
+        T: DecodeDataFromRecordBatch + NautilusDataTypeProvider + Into<Data>,
+    {
+        let sql_query = match self.sort_key {
+            SortKey::TsInit => sql_query.to_string(),
+            sort_key => format!("SELECT * FROM ({sql_query}) ORDER BY {sort_key}"),
+        };
+        let chunk_size = chunk_size.unwrap_or(self.chunk_size);
+        let table_info = table_info::<T>(table_name, stats, chunk_size);
+        let time_range = table_info
+            .min_ts_init
+            .zip(table_info.max_ts_init)
+            .map(|(min, max)| (min.as_u64(), max.as_u64()));
+
+        let (query, sql_query) = self.run_query::<T>(sql_query)?;
+        let metadata = self.precision_overrides(query.schema().as_arrow(), metadata)?;
+        let bounds_probe = Some(BoundsProbe::new::<T>(
+            &sql_query,
+            self.sort_key,
+            metadata.clone(),
+            self.null_policy,
+        ));
+        let row_groups = self.row_groups.get(table_name).cloned().unwrap_or_default();
+        let cache_key = QueryCacheKey::new(table_name, &sql_query, T::DATA_TYPE, metadata.as_ref());
+        let cached = self
+            .cache
+            .as_ref()
+            .and_then(|cache| cache.lock().unwrap().get(&cache_key));
+        if let Some(cached) = cached {
+            let chunks = cached.chunks;
+            let stream =
+                futures::stream::iter(0..chunks.len()).map(move |i| chunks[i].clone().into_iter());
+            self.tables.push(table_info);
+            self.queries.push(PendingQuery {
+                table_name: table_name.to_string(),
+                sql_query,
+                stream: EagerStream::from_stream_with_runtime(stream, self.runtime.clone()),
+                size_hint: cached.size_hint,
+                time_range,
+                row_groups,
+                bounds_probe,
+            });
+            return Ok(());
+        }
+
+        let projection = self.projection::<T>(query.schema().as_arrow())?;
+        let plan = self.runtime.block_on(query.create_physical_plan())?;
+        let size_hint = match plan.statistics()?.num_rows {
+            Precision::Exact(num_rows) => Some(num_rows as u64),
+            _ => None,
+        };
+        let batch_stream = {
+            let _guard = self.runtime.enter();
+            execute_stream(plan, self.session_ctx.task_ctx())?
+        };
+
+        self.tables.push(table_info);
+        let stream = match &self.cache {
+            Some(cache) => {
+                let results = decode_results::<T>(
+                    batch_stream,
+                    projection,
+                    chunk_size,
+                    metadata,
+                    self.null_policy,
+                    self.metrics.clone(),
+                );
+                let stream = caching_stream(
+                    results,
+                    cache.clone(),
+                    cache_key,
+                    size_hint,
+                    self.errors.clone(),
+                );
+                EagerStream::from_stream_with_runtime(stream, self.runtime.clone())
+            }
+            None => self.decode_batch_stream::<T>(batch_stream, projection, chunk_size, metadata),
+        };
+        self.queries.push(PendingQuery {
+            table_name: table_name.to_string(),
+            sql_query,
+            stream,
+            size_hint,
+            time_range,
+            row_groups,
+            bounds_probe,
+        });
+        Ok(())
+    }
+
+    /// Runs `sql_query` on the registered tables, returning the query and its
+    /// final SQL.
+    ///
+    /// The optional columns of `T` missing from the result are selected as zero
+    /// if the session allows missing optional columns.
+    fn run_query<T: ArrowSchemaProvider>(
+        &self,
+        mut sql_query: String,
+    ) -> Result<(DataFrame, String), PersistenceError> {
+        let mut query = self.runtime.block_on(self.session_ctx.sql(&sql_query))?;
+        if self.allow_missing_optional {
+            let defaults: Vec<String> = missing_optional_fields::<T>(query.schema().as_arrow())
+                .iter()
+                .map(|field| format!("arrow_cast(0, '{}') AS {}", field.data_type(), field.name()))
+                .collect();
+            if !defaults.is_empty() {
+                sql_query = format!("SELECT *, {} FROM ({sql_query})", defaults.join(", "));
+                query = self.runtime.block_on(self.session_ctx.sql(&sql_query))?;
+            }
+        }
+        Ok((query, sql_query))
+    }
+
+    /// Returns the projection of the columns of `schema` decoded as `T`,
+    /// returning an error for any other column if the session rejects extra
+    /// columns.
+    fn projection<T: ArrowSchemaProvider>(
+        &self,
+        schema: &Schema,
+    ) -> Result<Vec<usize>, PersistenceError> {
+        let projection = decode_projection::<T>(schema)?;
+        if self.reject_extra_columns {
+            check_extra_columns(schema, &projection)?;
+        }
+        Ok(projection)
+    }
+
+    /// Returns the `metadata` overrides of a query with the given `schema` under
+    /// the session [`PrecisionPolicy`], checking the precisions of the file
+    /// against those expected.
+    ///
+    /// A precision missing from the file is not checked, and fails to decode.
+    fn precision_overrides(
+        &self,
+        schema: &Schema,
+        metadata: Option<HashMap<String, String>>,
+    ) -> Result<Option<HashMap<String, String>>, PersistenceError> {
+        match self.precision_policy {
+            PrecisionPolicy::FromFile => Ok(metadata),
+            PrecisionPolicy::Expect {
+                price_precision,
+                size_precision,
+            } => {
+                for (key, expected) in [
+                    (KEY_PRICE_PRECISION, price_precision),
+                    (KEY_SIZE_PRECISION, size_precision),
+                ] {
+                    let found = metadata
+                        .as_ref()
+                        .and_then(|metadata| metadata.get(key))
+                        .or_else(|| schema.metadata().get(key));
+                    let Some(found) = found else {
+                        continue;
+                    };
+                    let found = found
+                        .parse::<u8>()
+                        .map_err(|e| EncodingError::ParseError(key, e.to_string()))?;
+                    if found != expected {
+                        return Err(PersistenceError::PrecisionMismatch {
+                            key,
+                            expected,
+                            found,
+                        });
+                    }
+                }
+                Ok(metadata)
+            }
+            PrecisionPolicy::Override {
+                price_precision,
+                size_precision,
+            } => {
+                let mut metadata = metadata.unwrap_or_default();
+                metadata.insert(KEY_PRICE_PRECISION.to_string(), price_precision.to_string());
+                metadata.insert(KEY_SIZE_PRECISION.to_string(), size_precision.to_string());
+                Ok(Some(metadata))
+            }
+        }
+    }
+
+    /// Query a file for its records, first validating that the file is ordered by
+    /// the session [`SortKey`] in ascending order. The caller must specify `T` to
+    /// indicate the kind of data expected from this query.
+    ///
+    /// Returns a [`PersistenceError::NotMonotonic`] error pointing at the first
+    /// out of order row, in which case the file is not registered. Only the
+    /// column of the key is scanned, and row groups with a single value are
+    /// checked from the parquet statistics alone.
+    ///
+    /// See [`DataBackendSession::add_file`] for the arguments.
+    pub fn add_file_checked<T>(
+        &mut self,
+        table_name: &str,
+        file_path: &str,
+        sql_query: Option<&str>,
+    ) -> Result<(), PersistenceError>
+    where
+        T: DecodeDataFromRecordBatch + NautilusDataTypeProvider + Into<Data>,
+    {
+        check_monotonic(file_path, self.sort_key)?;
+        self.add_file::<T>(table_name, file_path, sql_query)
+    }
+
+    /// Query a file for its records within the given `ts_init` range. The
+    /// caller must specify `T` to indicate the kind of data expected from this query.
+    ///
+    /// `start`: Inclusive lower bound on `ts_init`, unbounded if `None`.
+    /// `end`: Inclusive upper bound on `ts_init`, unbounded if `None`.
+    ///
+    /// The range predicate is pushed into the DataFusion scan so that row groups
+    /// outside of the window are pruned using the parquet statistics.
+    ///
+    /// # Safety
+    ///
+    /// The file data must be ordered by the `ts_init` in ascending order for this
+    /// to work correctly.
+    pub fn add_file_range_query<T>(
+        &mut self,
+        table_name: &str,
+        file_path: &str,
+        start: Option<UnixNanos>,
+        end: Option<UnixNanos>,
+    ) -> Result<(), PersistenceError>
+    where
+        T: DecodeDataFromRecordBatch + NautilusDataTypeProvider + Into<Data>,
+    {
+        let sql_query = build_range_query(table_name, start, end);
+        self.add_file::<T>(table_name, file_path, Some(&sql_query))
+    }
+
+    /// Query a file of order book deltas for the records with a `sequence`
+    /// between `start_seq` and `end_seq` inclusive.
+    ///
+    /// The filter is on the values of the sequence column alone, so a file whose
+    /// sequences are out of order still gives every delta in the window, in the
+    /// `ts_init` order of the file. The predicate is pushed into the DataFusion
+    /// scan as with [`DataBackendSession::add_file_filtered`].
+    ///
+    /// # Safety
+    ///
+    /// The file data must be ordered by the `ts_init` in ascending order for this
+    /// to work correctly.
+    pub fn add_file_sequence_range(
+        &mut self,
+        table_name: &str,
+        file_path: &str,
+        start_seq: u64,
+        end_seq: u64,
+    ) -> Result<(), PersistenceError> {
+        let filter = Filter::col("sequence")
+            .gt_eq(lit(start_seq))
+            .and(Filter::col("sequence").lt_eq(lit(end_seq)));
+        self.add_file_filtered::<OrderBookDelta>(table_name, file_path, &filter)
+    }
+
+    /// Query a file for a page of `limit` records, after skipping the first
+    /// `offset` records in `ts_init` order. The caller must specify `T` to
+    /// indicate the kind of data expected from this query.
+    ///
+    /// The limit is pushed into the DataFusion scan, so only the rows up to the
+    /// end of the page are read, and only the page is decoded. An `offset` beyond
+    /// the end of the file gives an empty result.
+    ///
+    /// See [`DataBackendSession::add_file`] for the remaining arguments.
+    ///
+    /// # Safety
+    ///
+    /// The file data must be ordered by the `ts_init` in ascending order for this
+    /// to work correctly.
+    pub fn add_file_paged_query<T>(
+        &mut self,
+        table_name: &str,
+        file_path: &str,
+        limit: usize,
+        offset: usize,
+    ) -> Result<(), PersistenceError>
+    where
+        T: DecodeDataFromRecordBatch + NautilusDataTypeProvider + Into<Data>,
+    {
+        let sql_query =
+            format!("SELECT * FROM {table_name} ORDER BY ts_init LIMIT {limit} OFFSET {offset}");
+        self.add_file::<T>(table_name, file_path, Some(&sql_query))
+    }
+
+    /// Query a file for its last `n` records, those with the largest `ts_init`,
+    /// such as to warm up before the start of a backtest. The caller must specify
+    /// `T` to indicate the kind of data expected from this query.
+    ///
+    /// The records are still delivered in ascending `ts_init` order. An `n`
+    /// larger than the file gives all of its records.
+    ///
+    /// See [`DataBackendSession::add_file`] for the remaining arguments.
+    pub fn add_file_tail_query<T>(
+        &mut self,
+        table_name: &str,
+        file_path: &str,
+        n: usize,
+    ) -> Result<(), PersistenceError>
+    where
+        T: DecodeDataFromRecordBatch + NautilusDataTypeProvider + Into<Data>,
+    {
+        let sql_query = format!(
+            "SELECT * FROM (SELECT * FROM {table_name} ORDER BY ts_init DESC LIMIT {n}) ORDER BY ts_init"
+        );
+        self.add_file::<T>(table_name, file_path, Some(&sql_query))
+    }
+
+    /// Query a range of the row groups of a local parquet file for their records,
+    /// such as to inspect a region of a large file listed by
+    /// [`DataBackendSession::file_metadata`]. The caller must specify `T` to
+    /// indicate the kind of data expected from this query.
+    ///
+    /// `start_row_group`: The index of the first row group to read.
+    /// `end_row_group`: The exclusive index of the row group to stop before.
+    ///
+    /// Only the given row groups are read from the file, and are registered as an
+    /// in-memory table with [`DataBackendSession::add_batch`]. Returns a
+    /// [`PersistenceError::InvalidRowGroupRange`] error with the number of row
+    /// groups of the file if the range is empty or out of bounds.
+    ///
+    /// # Safety
+    ///
+    /// The file data must be ordered by the `ts_init` in ascending order for this
+    /// to work correctly.
+    pub fn add_file_row_groups<T>(
+        &mut self,
+        table_name: &str,
+        file_path: &str,
+        start_row_group: usize,
+        end_row_group: usize,
+    ) -> Result<(), PersistenceError>
+    where
+        T: DecodeDataFromRecordBatch + NautilusDataTypeProvider + Into<Data>,
+    {
+        let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(file_path)?)?;
+        let num_row_groups = builder.metadata().num_row_groups();
+        if start_row_group >= end_row_group || end_row_group > num_row_groups {
+            return Err(PersistenceError::InvalidRowGroupRange {
+                start: start_row_group,
+                end: end_row_group,
+                num_row_groups,
+            });
+        }
+
+        let schema = builder.schema().clone();
+        let reader = builder
+            .with_row_groups((start_row_group..end_row_group).collect())
+            .build()?;
+        let batches = reader.collect::<Result<Vec<_>, _>>()?;
+        let batch = concat_batches(&schema, &batches)?;
+        self.add_batch::<T>(table_name, batch)
+    }
+
+    /// Query all the parquet files in a local directory as a single table. The
+    /// caller must specify `T` to indicate the kind of data expected from this
+    /// query.
+    ///
+    /// `dir_path`: The directory of the files, which are matched by the `.parquet`
+    /// extension. Hidden files and subdirectories are skipped.
+    ///
+    /// The files are registered as one DataFusion table named `table_name`, and the
+    /// records of all the files are returned ordered by `ts_init`. The schema of
+    /// the table, including its metadata, is read from the first file by name.
+    ///
+    /// # Safety
+    ///
+    /// The data of each file must be ordered by the `ts_init` in ascending order
+    /// for this to work correctly.
+    pub fn add_directory<T>(
+        &mut self,
+        table_name: &str,
+        dir_path: &str,
+    ) -> Result<(), PersistenceError>
+    where
+        T: DecodeDataFromRecordBatch + NautilusDataTypeProvider + Into<Data>,
+    {
+        let file_paths = list_parquet_files(dir_path)?;
+        self.register_parquet_files(table_name, &file_paths)?;
+        self.with_registered_table(table_name, |session| {
+            let file_stats = file_paths
+                .iter()
+                .map(|file_path| file_stats(file_path))
+                .collect::<Result<Vec<_>>>()?;
+
+            let sql_query = format!("SELECT * FROM {table_name} ORDER BY ts_init");
+            session.add_query::<T>(table_name, merge_stats(&file_stats), &sql_query, None, None)
+        })
+    }
+
+    /// Query the parquet files in a local directory for their records within the
+    /// given `ts_init` range, as a single table. The caller must specify `T` to
+    /// indicate the kind of data expected from this query.
+    ///
+    /// `start`: Inclusive lower bound on `ts_init`, unbounded if `None`.
+    /// `end`: Inclusive upper bound on `ts_init`, unbounded if `None`.
+    ///
+    /// The `ts_init` bounds of each file are read from its footer, and only the
+    /// files which overlap the range, or have no statistics, are registered and
+    /// scanned. The records of these files are merged and trimmed to the range
+    /// as for [`DataBackendSession::add_file_range_query`]. If no file overlaps
+    /// the range the result is empty.
+    ///
+    /// See [`DataBackendSession::add_directory`] for the remaining arguments.
+    ///
+    /// # Safety
+    ///
+    /// The data of each file must be ordered by the `ts_init` in ascending order
+    /// for this to work correctly.
+    pub fn add_directory_range_query<T>(
+        &mut self,
+        table_name: &str,
+        dir_path: &str,
+        start: Option<UnixNanos>,
+        end: Option<UnixNanos>,
+    ) -> Result<(), PersistenceError>
+    where
+        T: DecodeDataFromRecordBatch + NautilusDataTypeProvider + Into<Data>,
+    {
+        let mut file_paths = Vec::new();
+        let mut overlapping_stats = Vec::new();
+        for file_path in list_parquet_files(dir_path)? {
+            let stats = file_stats(&file_path)?;
+            let (_, min_ts_init, max_ts_init) = stats;
+            let before_start = matches!(start.zip(max_ts_init), Some((start, max)) if max < start);
+            let after_end = matches!(end.zip(min_ts_init), Some((end, min)) if min > end);
+            if !before_start && !after_end {
+                file_paths.push(file_path);
+                overlapping_stats.push(stats);
+            }
+        }
+
+        if file_paths.is_empty() {
+            let batch = RecordBatch::new_empty(Arc::new(T::get_schema(None)));
+            return self.add_batch::<T>(table_name, batch);
+        }
+
+        self.register_parquet_files(table_name, &file_paths)?;
+        self.with_registered_table(table_name, |session| {
+            let sql_query = build_range_query(table_name, start, end);
+            session.add_query::<T>(
+                table_name,
+                merge_stats(&overlapping_stats),
+                &sql_query,
+                None,
+                None,
+            )
+        })
+    }
+
+    /// Registers the local parquet files at `file_paths` with the session as one
+    /// table `table_name`, with the schema of the first file.
+    fn register_parquet_files(
+        &self,
+        table_name: &str,
+        file_paths: &[String],
+    ) -> Result<(), PersistenceError> {
+        let table_paths = file_paths
+            .iter()
+            .map(ListingTableUrl::parse)
+            .collect::<Result<Vec<_>>>()?;
+
+        let listing_options = parquet_read_options().to_listing_options(
+            &self.session_ctx.copied_config(),
+            self.session_ctx.copied_table_options(),
+        );
+        let config = self.runtime.block_on(
+            ListingTableConfig::new_with_multi_paths(table_paths)
+                .with_listing_options(listing_options)
+                .infer_schema(&self.session_ctx.state()),
+        )?;
+        self.session_ctx
+            .register_table(table_name, Arc::new(ListingTable::try_new(config)?))?;
+        Ok(())
+    }
+
+    /// Query a parquet file served over HTTP(S) for all its records. The caller
+    /// must specify `T` to indicate the kind of data expected from this query.
+    ///
+    /// The file is read with range requests, so only its footer and the row
+    /// groups needed by the query are fetched. The server must support `HEAD`
+    /// requests and respond to ranged `GET` requests with a partial content.
+    ///
+    /// # Errors
+    ///
+    /// This function returns a [`PersistenceError::InvalidHttpUrl`] error if
+    /// `url` does not have an `http` or `https` scheme.
+    ///
+    /// # Safety
+    ///
+    /// The file data must be ordered by the `ts_init` in ascending order for this
+    /// to work correctly.
+    #[cfg(feature = "http")]
+    pub fn add_http_file<T>(&mut self, table_name: &str, url: &str) -> Result<(), PersistenceError>
+    where
+        T: DecodeDataFromRecordBatch + NautilusDataTypeProvider + Into<Data>,
+    {
+        if !matches!(object_store_uri(url), Some(("http" | "https", _))) {
+            return Err(PersistenceError::InvalidHttpUrl(url.to_string()));
+        }
+        self.add_file::<T>(table_name, url, None)
+    }
+
+    /// Query a hive-style partitioned dataset, such as
+    /// `root/date=2023-01-01/part.parquet`, as a single table. The caller must
+    /// specify `T` to indicate the kind of data expected from this query.
+    ///
+    /// `root_path`: The root directory of the dataset, or an `s3://` or `gs://` URI
+    /// with the `object_store` feature.
+    /// `partition_cols`: The names of the partition directories, from the
+    /// outermost, which are exposed as string columns of the table.
+    /// `sql_query`: A custom sql query to retrieve records from the table. If no
+    /// query is provided a default query "SELECT * FROM <`table_name`>" is run.
+    ///
+    /// A filter on the partition columns, such as `WHERE date = '2023-01-01'`,
+    /// prunes the partitions scanned. The records of all the partitions read are
+    /// returned ordered by `ts_init`.
+    ///
+    /// # Safety
+    ///
+    /// The data of each file must be ordered by the `ts_init` in ascending order
+    /// for this to work correctly.
+    pub fn add_partitioned_dataset<T>(
+        &mut self,
+        table_name: &str,
+        root_path: &str,
+        partition_cols: &[&str],
+        sql_query: Option<&str>,
+    ) -> Result<(), PersistenceError>
+    where
+        T: DecodeDataFromRecordBatch + NautilusDataTypeProvider + Into<Data>,
+    {
+        #[cfg(feature = "object_store")]
+        self.register_object_store(root_path)?;
+
+        let options = parquet_read_options().table_partition_cols(
+            partition_cols
+                .iter()
+                .map(|col| ((*col).to_string(), DataType::Utf8))
+                .collect(),
+        );
+        self.runtime.block_on(
+            self.session_ctx
+                .register_parquet(table_name, root_path, options),
+        )?;
+        self.with_registered_table(table_name, |session| {
+            let default_query = format!("SELECT * FROM {table_name}");
+            let sql_query = sql_query.unwrap_or(&default_query);
+            let sql_query = format!("SELECT * FROM ({sql_query}) ORDER BY ts_init");
+            session.add_query::<T>(table_name, (None, None, None), &sql_query, None, None)
+        })
+    }
+
+    /// Registers the object store for the bucket of `file_path` with the session,
+    /// if it is an `s3://` or `gs://` URI.
+    ///
+    /// Credentials are read from the environment by the standard providers. A
+    /// store already registered for the bucket, such as a custom one, is kept.
+    #[cfg(feature = "object_store")]
+    fn register_object_store(&self, file_path: &str) -> Result<(), PersistenceError> {
+        use datafusion::{error::DataFusionError, execution::object_store::ObjectStoreUrl};
+        use object_store::{aws::AmazonS3Builder, gcp::GoogleCloudStorageBuilder, ObjectStore};
+
+        let Some((scheme @ ("s3" | "gs"), bucket)) = object_store_uri(file_path) else {
+            return Ok(());
+        };
+        let url = ObjectStoreUrl::parse(format!("{scheme}://{bucket}"))?;
+        if self.session_ctx.runtime_env().object_store(&url).is_ok() {
+            return Ok(());
+        }
+
+        let store: Arc<dyn ObjectStore> = match scheme {
+            "s3" => Arc::new(
+                AmazonS3Builder::from_env()
+                    .with_bucket_name(bucket)
+                    .build()
+                    .map_err(DataFusionError::ObjectStore)?,
+            ),
+            "gs" => Arc::new(
+                GoogleCloudStorageBuilder::from_env()
+                    .with_bucket_name(bucket)
+                    .build()
+                    .map_err(DataFusionError::ObjectStore)?,
+            ),
+            _ => return Ok(()),
+        };
+        let store: Arc<dyn ObjectStore> = match self.retry_policy {
+            Some(policy) => Arc::new(RetryingObjectStore::new(store, policy)),
+            None => store,
+        };
+
+        self.session_ctx.register_object_store(url.as_ref(), store);
+        Ok(())
+    }
+
+    /// Registers the HTTP store for the host of `file_path` with the session, if
+    /// it is an `http://` or `https://` URL.
+    #[cfg(feature = "http")]
+    fn register_http_store(&self, file_path: &str) -> Result<(), PersistenceError> {
+        use datafusion::{error::DataFusionError, execution::object_store::ObjectStoreUrl};
+        use object_store::{http::HttpBuilder, ClientOptions};
+
+        let Some((scheme @ ("http" | "https"), host)) = object_store_uri(file_path) else {
+            return Ok(());
+        };
+
+        let url = format!("{scheme}://{host}");
+        let store = HttpBuilder::new()
+            .with_url(&url)
+            .with_client_options(ClientOptions::new().with_allow_http(scheme == "http"))
+            .build()
+            .map_err(DataFusionError::ObjectStore)?;
+        self.session_ctx
+            .register_object_store(ObjectStoreUrl::parse(url)?.as_ref(), Arc::new(store));
+        Ok(())
+    }
+
+    fn decode_batch_stream<T>(
+        &self,
+        stream: SendableRecordBatchStream,
+        projection: Vec<usize>,
+        chunk_size: usize,
+        metadata: Option<HashMap<String, String>>,
+    ) -> EagerStream<IntoIter<Data>>
+    where
+        T: DecodeDataFromRecordBatch + Into<Data>,
+    {
+        let transform = self.decode_stream::<T>(stream, projection, chunk_size, metadata);
+        EagerStream::from_stream_with_runtime(transform, self.runtime.clone())
+    }
+
+    /// Returns a stream decoding the record batches of `stream` into chunks of
+    /// at most `chunk_size` data items.
+    fn decode_stream<T>(
+        &self,
+        stream: SendableRecordBatchStream,
+        projection: Vec<usize>,
+        chunk_size: usize,
+        metadata: Option<HashMap<String, String>>,
+    ) -> impl Stream<Item = IntoIter<Data>> + Send + 'static
+    where
+        T: DecodeDataFromRecordBatch + Into<Data>,
+    {
+        let errors = self.errors.clone();
+        let null_policy = self.null_policy;
+        let metrics = self.metrics.clone();
+        decode_results::<T>(
+            stream,
+            projection,
+            chunk_size,
+            metadata,
+            null_policy,
+            metrics,
+        )
+        .map(move |result| {
+            result.map_or_else(
+                |e| {
+                    errors.lock().unwrap().push_back(e);
+                    Vec::new().into_iter()
+                },
+                Vec::into_iter,
+            )
+        })
+    }
+
+    // Consumes the registered queries and returns a [`QueryResult].
+    // Passes the output of the query though the a KMerge which sorts the
+    // queries in ascending order of `ts_init`.
+    // QueryResult is an iterator that return Vec<Data>.
+    pub fn get_query_result(&mut self) -> QueryResult {
+        let queries = std::mem::take(&mut self.queries);
+        let errors = std::mem::take(&mut self.errors);
+        let metrics = std::mem::take(&mut self.metrics);
+        self.query_result(queries, errors, metrics)
+    }
+
+    /// Consumes the registered queries on `table_name` only, and returns their
+    /// merged [`QueryResult`], leaving the queries on other tables pending.
+    ///
+    /// The decode errors and metrics of the session are shared by all of its
+    /// pending queries, so the result also reports those of the other tables
+    /// decoded so far.
+    ///
+    /// # Errors
+    ///
+    /// This function returns a [`PersistenceError::QueryNotFound`] error if no
+    /// query is pending on `table_name`.
+    pub fn get_table_query_result(
+        &mut self,
+        table_name: &str,
+    ) -> Result<QueryResult, PersistenceError> {
+        let (queries, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.queries)
+            .into_iter()
+            .partition(|query| query.table_name == table_name);
+        self.queries = pending;
+        if queries.is_empty() {
+            return Err(PersistenceError::QueryNotFound(table_name.to_string()));
+        }
+        Ok(self.query_result(queries, self.errors.clone(), self.metrics.clone()))
+    }
+
+    fn query_result(
+        &self,
+        queries: Vec<PendingQuery>,
+        errors: DecodeErrors,
+        metrics: SharedMetrics,
+    ) -> QueryResult {
+        let mut kmerge: KMerge<_, _, _> = KMerge::new(SortKeyComparator(self.sort_key));
+
+        let mut size_hint_total = Some(0);
+        let mut time_range = None;
+        let mut time_range_known = true;
+        let mut row_groups = VecDeque::new();
+        let mut bounds_probes = Vec::new();
+        for query in queries {
+            kmerge.push_iter(query.stream);
+            bounds_probes.push(query.bounds_probe);
+            row_groups.extend(query.row_groups);
+            size_hint_total = size_hint_total.zip(query.size_hint).map(|(a, b)| a + b);
+            match (time_range, query.time_range) {
+                (_, None) => time_range_known = false,
+                (None, Some(range)) => time_range = Some(range),
+                (Some((start, end)), Some((min, max))) => {
+                    time_range = Some((start.min(min), end.max(max)));
+                }
+            }
+        }
+
+        QueryResult {
+            merge: kmerge,
+            size_hint_total,
+            time_range: time_range.filter(|_| time_range_known),
+            chunk_size: self.chunk_size,
+            row_groups,
+            dedup: self.dedup,
+            last: None,
+            sort_on_read: self.sort_on_read,
+            sorted: None,
+            progress: None,
+            cancellation_token: None,
+            errors,
+            metrics,
+            bounds_probes,
+            session_ctx: self.session_ctx.clone(),
+            runtime: self.runtime.clone(),
+            sort_key: self.sort_key,
+        }
+    }
+
+    /// Deregisters the table `table_name` from the session, dropping any of its
+    /// queries not yet consumed by a query result.
+    ///
+    /// Returns `true` if the table was registered.
+    pub fn deregister(&mut self, table_name: &str) -> bool {
+        if let Some(cache) = &self.cache {
+            cache.lock().unwrap().invalidate_table(table_name);
+        }
+        self.queries.retain(|query| query.table_name != table_name);
+        self.instrument_queries
+            .retain(|query| query.table_name != table_name);
+        self.tables.retain(|table| table.table_name != table_name);
+        self.row_groups.remove(table_name);
+        matches!(self.session_ctx.deregister_table(table_name), Ok(Some(_)))
+    }
+
+    /// Deregisters all tables from the session, dropping any queries not yet
+    /// consumed by a query result.
+    pub fn clear(&mut self) {
+        self.queries.clear();
+        self.instrument_queries.clear();
+        self.tables.clear();
+        self.row_groups.clear();
+        self.errors = DecodeErrors::default();
+        self.metrics = SharedMetrics::default();
+        self.session_ctx = new_session_context(
+            self.use_mmap,
+            self.target_partitions,
+            self.reader_batch_size,
+        );
+        if let Some(cache) = &self.cache {
+            cache.lock().unwrap().clear();
+        }
+    }
+
+    /// Returns the underlying DataFusion session context, with the tables
+    /// registered with the session.
+    ///
+    /// The context can be used to register UDFs or to run SQL beyond the typed
+    /// queries, such as joins across tables. Asynchronous methods of the context
+    /// may be run with the session [`runtime`](Self::runtime).
+    #[must_use]
+    pub const fn context(&self) -> &SessionContext {
+        &self.session_ctx
+    }
+
+    /// Runs `sql_query` on the tables registered with the session, returning the
+    /// resulting Arrow record batches.
+    ///
+    /// The batches are returned as is, and are not decoded into [`Data`] nor
+    /// added to the query result of the session.
+    pub fn sql(&self, sql_query: &str) -> Result<Vec<RecordBatch>, PersistenceError> {
+        let query = self.runtime.block_on(self.session_ctx.sql(sql_query))?;
+        Ok(self.runtime.block_on(query.collect())?)
+    }
+
+    /// Returns the DataFusion physical plan of the queries registered on
+    /// `table_name`, as shown by `EXPLAIN`, without executing them.
+    ///
+    /// The plan shows whether predicates and limits are pushed into the parquet
+    /// scan, and how the rows are ordered. The plans of several queries on the
+    /// table, such as those of [`DataBackendSession::add_file_for_instruments`],
+    /// are separated by a blank line. Queries are only explained until collected
+    /// with [`DataBackendSession::get_query_result`], and otherwise a
+    /// [`PersistenceError::QueryNotFound`] error is returned.
+    pub fn explain(&self, table_name: &str) -> Result<String, PersistenceError> {
+        let plans = self
+            .queries
+            .iter()
+            .filter(|query| query.table_name == table_name)
+            .map(|query| {
+                let query = self
+                    .runtime
+                    .block_on(self.session_ctx.sql(&query.sql_query))?;
+                let plan = self.runtime.block_on(query.create_physical_plan())?;
+                let plan = displayable(plan.as_ref()).indent(true).to_string();
+                Ok(plan)
+            })
+            .collect::<Result<Vec<_>, PersistenceError>>()?;
+        if plans.is_empty() {
+            return Err(PersistenceError::QueryNotFound(table_name.to_string()));
+        }
+        Ok(plans.join("\n"))
+    }
+
+    /// Returns the number of rows in the registered table `table_name`.
+    ///
+    /// The count is computed by DataFusion without decoding any data.
+    pub fn count(&self, table_name: &str) -> Result<u64, PersistenceError> {
+        self.count_range(table_name, None, None)
+    }
+
+    /// Returns the number of rows in the registered table `table_name` with
+    /// `ts_init` within the inclusive `start` and `end` bounds.
+    ///
+    /// The count is computed by DataFusion without decoding any data.
+    pub fn count_range(
+        &self,
+        table_name: &str,
+        start: Option<UnixNanos>,
+        end: Option<UnixNanos>,
+    ) -> Result<u64, PersistenceError> {
+        let sql_query = format!(
+            "SELECT COUNT(*) FROM {table_name}{}",
+            build_range_filter(start, end)
+        );
+        let batches = self.sql(&sql_query)?;
+
+        let count = batches
+            .first()
+            .and_then(|batch| batch.column(0).as_any().downcast_ref::<Int64Array>())
+            .map_or(0, |counts| counts.value(0));
+        Ok(count as u64)
+    }
+
+    /// Returns information on the tables registered with the session, in
+    /// order of registration.
+    #[must_use]
+    pub fn registered_tables(&self) -> Vec<TableInfo> {
+        self.tables.clone()
+    }
 
-use compare::Compare;
-use datafusion::{
-    error::Result, logical_expr::expr::Sort, physical_plan::SendableRecordBatchStream, prelude::*,
-};
-use futures::StreamExt;
-use nautilus_core::ffi::cvec::CVec;
-use nautilus_model::data::{Data, GetTsInit};
+    /// Reads the structure of the parquet file at `file_path` from its footer,
+    /// without scanning or decoding any data.
+    ///
+    /// Returns the bounds of `ts_init` for each row group from the column
+    /// statistics, which are `None` if the statistics or the column are missing.
+    pub fn file_metadata(file_path: &str) -> Result<FileMetadata, PersistenceError> {
+        let file = File::open(file_path)?;
+        let metadata = parse_metadata(&file)?;
+        let column_names: Vec<String> = metadata
+            .file_metadata()
+            .schema_descr()
+            .columns()
+            .iter()
+            .map(|column| column.name().to_string())
+            .collect();
+        let ts_init_index = column_names.iter().position(|name| name == "ts_init");
 
-use super::kmerge_batch::{EagerStream, ElementBatchIter, KMerge};
-use crate::arrow::{
-    DataStreamingError, DecodeDataFromRecordBatch, EncodeToRecordBatch, WriteStream,
-};
+        let row_groups = metadata
+            .row_groups()
+            .iter()
+            .map(|row_group| {
+                let bounds = ts_init_index.and_then(|index| row_group_bounds(row_group, index));
+                RowGroupInfo {
+                    num_rows: row_group.num_rows() as u64,
+                    min_ts_init: bounds.map(|(min, _)| min.into()),
+                    max_ts_init: bounds.map(|(_, max)| max.into()),
+                }
+            })
+            .collect();
 
-#[derive(Debug, Default)]
-pub struct TsInitComparator;
+        Ok(FileMetadata {
+            num_rows: metadata.file_metadata().num_rows() as u64,
+            column_names,
+            row_groups,
+        })
+    }
+
+    /// Consumes the registered queries and returns a [`DataQueryResult`] which
+    /// yields the merged data in chunks of at most `chunk_size` elements, or a
+    /// chunk per row group under [`ChunkMode::RowGroup`].
+    ///
+    /// Only a single chunk is held in memory at a time, regardless of the size
+    /// of the underlying files.
+    pub fn get_chunked_query_result(&mut self) -> DataQueryResult {
+        DataQueryResult::new(self.get_query_result(), self.chunk_size)
+    }
+
+    /// Consumes the registered queries and returns a [`DataQueryStream`] which
+    /// asynchronously yields the merged data in chunks of at most `chunk_size`
+    /// elements.
+    ///
+    /// The next chunk is only decoded once the previous one has been polled, so
+    /// a slow consumer applies backpressure to the underlying queries.
+    pub fn get_query_stream(&mut self) -> DataQueryStream {
+        DataQueryStream::new(self.get_query_result(), self.chunk_size)
+    }
+}
+
+// Note: Intended to be used on a single Python thread
+unsafe impl Send for DataBackendSession {}
+
+/// Returns the SQL select list which reads the `columns` of `T` from a file with
+/// `file_schema`, defaulting the other integer fields to zero, and keeping the
+/// `keys` columns of the file.
+///
+/// File columns are matched to the fields of `T` as for decoding, which supports
+/// files written with legacy column names.
+fn projected_select_list<T: ArrowSchemaProvider>(
+    file_schema: &Schema,
+    columns: &[&str],
+    keys: &[&str],
+) -> Result<Vec<String>, PersistenceError> {
+    let schema = T::get_schema(None);
+    let file_indices = decode_projection::<T>(file_schema)?;
+    let mut select_list = schema
+        .fields()
+        .iter()
+        .zip(file_indices)
+        .map(|(field, index)| {
+            let name = field.name();
+            if columns.contains(&name.as_str()) {
+                let file_name = file_schema.field(index).name().replace('"', "\"\"");
+                Ok(format!("\"{file_name}\" AS {name}"))
+            } else if is_optional_field(field) {
+                Ok(format!("arrow_cast(0, '{}') AS {name}", field.data_type()))
+            } else {
+                Err(PersistenceError::InvalidProjection {
+                    column: name.clone(),
+                    reason: "is required",
+                })
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for key in keys {
+        if file_schema.index_of(key).is_err() {
+            continue;
+        }
+        if !columns.contains(key) {
+            return Err(PersistenceError::InvalidProjection {
+                column: (*key).to_string(),
+                reason: "is required",
+            });
+        }
+        select_list.push((*key).to_string());
+    }
+    Ok(select_list)
+}
+
+/// Returns the select list aliasing the columns of `file_schema` to the fields
+/// of `T` through `column_map`, with the `instrument_id` and `bar_type` columns
+/// kept if the file has them.
+fn column_map_select_list<T: ArrowSchemaProvider>(
+    file_schema: &Schema,
+    column_map: &HashMap<String, String>,
+) -> Result<Vec<String>, PersistenceError> {
+    if let Some(column) = column_map
+        .keys()
+        .find(|column| file_schema.index_of(column).is_err())
+    {
+        return Err(PersistenceError::InvalidProjection {
+            column: column.clone(),
+            reason: "is not a column of the file",
+        });
+    }
+
+    // A column mapped to another name no longer holds the field of its own name
+    let source = |name: &str| {
+        column_map
+            .iter()
+            .find(|(_, field)| *field == name)
+            .map(|(column, _)| column.clone())
+            .or_else(|| {
+                (file_schema.index_of(name).is_ok() && !column_map.contains_key(name))
+                    .then(|| name.to_string())
+            })
+    };
+    let alias = |column: &str, name: &str| format!("\"{}\" AS {name}", column.replace('"', "\"\""));
+
+    let schema = T::get_schema(None);
+    let mut select_list = Vec::new();
+    let mut missing = Vec::new();
+    for field in schema.fields() {
+        match source(field.name()) {
+            Some(column) => select_list.push(alias(&column, field.name())),
+            None => missing.push(field.name().as_str()),
+        }
+    }
+    if !missing.is_empty() {
+        return Err(PersistenceError::MissingColumns(missing.join(", ")));
+    }
+
+    for key in [KEY_INSTRUMENT_ID, KEY_BAR_TYPE] {
+        if let Some(column) = source(key) {
+            select_list.push(alias(&column, key));
+        }
+    }
+    Ok(select_list)
+}
 
-impl<I> Compare<ElementBatchIter<I, Data>> for TsInitComparator
+/// Decodes the record batches of `stream` into chunks of at most `chunk_size`
+/// elements of `T`, yielding an error in place of a batch or chunk which fails.
+///
+/// The scan and decode of each batch are added to `metrics`.
+fn decode_results<T>(
+    stream: SendableRecordBatchStream,
+    projection: Vec<usize>,
+    chunk_size: usize,
+    metadata: Option<HashMap<String, String>>,
+    null_policy: NullPolicy,
+    metrics: SharedMetrics,
+) -> impl Stream<Item = Result<Vec<Data>, PersistenceError>> + Send + 'static
 where
-    I: Iterator<Item = IntoIter<Data>>,
+    T: DecodeDataFromRecordBatch + Into<Data>,
 {
-    fn compare(
-        &self,
-        l: &ElementBatchIter<I, Data>,
-        r: &ElementBatchIter<I, Data>,
-    ) -> std::cmp::Ordering {
-        // Max heap ordering must be reversed
-        l.item.ts_init().cmp(&r.item.ts_init()).reverse()
+    let chunk_size = chunk_size.max(1);
+    let scan_metrics = metrics.clone();
+    let stream = futures::stream::unfold(stream, move |mut stream| {
+        let metrics = scan_metrics.clone();
+        async move {
+            let start = Instant::now();
+            let result = stream.next().await?;
+            let mut metrics = metrics.lock().unwrap();
+            metrics.scan_time += start.elapsed();
+            if let Ok(batch) = &result {
+                metrics.bytes += batch.get_array_memory_size() as u64;
+            }
+            Some((result, stream))
+        }
+    });
+    stream.flat_map(move |result| {
+        let batch = result.map_err(PersistenceError::from).and_then(|batch| {
+            let batch = batch.project(&projection).map_err(EncodingError::from)?;
+            apply_null_policy(normalize_timestamps(batch)?, null_policy)
+        });
+        let (chunks, batch_metadata) = match batch {
+            Ok(batch) => {
+                // Slicing is zero-copy, so only `chunk_size` rows are decoded at a time
+                let chunks: Vec<_> = (0..batch.num_rows())
+                    .step_by(chunk_size)
+                    .map(|offset| {
+                        let len = chunk_size.min(batch.num_rows() - offset);
+                        Ok(batch.slice(offset, len))
+                    })
+                    .collect();
+                let mut batch_metadata = batch.schema().metadata().clone();
+                if let Some(metadata) = &metadata {
+                    batch_metadata.extend(metadata.clone());
+                }
+                (chunks, batch_metadata)
+            }
+            Err(e) => (vec![Err(e)], HashMap::new()),
+        };
+        let metrics = metrics.clone();
+        futures::stream::iter(chunks.into_iter().map(move |chunk| {
+            let start = Instant::now();
+            let data = T::decode_data_batch(&batch_metadata, chunk?)?;
+            let mut metrics = metrics.lock().unwrap();
+            metrics.decode_time += start.elapsed();
+            metrics.chunks += 1;
+            metrics.rows += data.len() as u64;
+            Ok(data)
+        }))
+    })
+}
+
+/// Passes the decoded chunks of `results` through, and inserts them into `cache`
+/// for `key` once the stream completes without errors.
+///
+/// Errors are pushed to `errors` and the failed chunks skipped, so a result is
+/// never cached with missing data.
+fn caching_stream(
+    results: impl Stream<Item = Result<Vec<Data>, PersistenceError>> + Send + 'static,
+    cache: Arc<Mutex<QueryCache>>,
+    key: QueryCacheKey,
+    size_hint: Option<u64>,
+    errors: DecodeErrors,
+) -> impl Stream<Item = IntoIter<Data>> + Send + 'static {
+    let mut chunks = Some(Vec::new());
+    results
+        .map(Some)
+        .chain(futures::stream::once(async { None }))
+        .filter_map(move |result| {
+            let item = match result {
+                Some(Ok(chunk)) => {
+                    if let Some(chunks) = &mut chunks {
+                        chunks.push(chunk.clone());
+                    }
+                    Some(chunk.into_iter())
+                }
+                Some(Err(e)) => {
+                    chunks = None;
+                    errors.lock().unwrap().push_back(e);
+                    None
+                }
+                None => {
+                    if let Some(chunks) = chunks.take() {
+                        cache.lock().unwrap().insert(key.clone(), chunks, size_hint);
+                    }
+                    None
+                }
+            };
+            futures::future::ready(item)
+        })
+}
+
+/// Returns the compression of the CSV file at `file_path` detected from its
+/// extension, and the extension to match the file by.
+fn csv_compression(file_path: &str) -> (FileCompressionType, &str) {
+    let file_name = file_path.rsplit('/').next().unwrap_or(file_path);
+    let extension = file_name.find('.').map_or("", |i| &file_name[i..]);
+    let compression = match extension.rsplit('.').next() {
+        Some("gz") => FileCompressionType::GZIP,
+        Some("bz2") => FileCompressionType::BZIP2,
+        Some("xz") => FileCompressionType::XZ,
+        Some("zst") => FileCompressionType::ZSTD,
+        _ => FileCompressionType::UNCOMPRESSED,
+    };
+    (compression, extension)
+}
+
+/// Creates a new multi-threaded runtime with `worker_threads` threads, or the
+/// number of CPU cores if `None`.
+fn new_runtime(worker_threads: Option<usize>) -> tokio::runtime::Runtime {
+    let mut builder = tokio::runtime::Builder::new_multi_thread();
+    if let Some(n) = worker_threads {
+        builder.worker_threads(n);
     }
+    builder.enable_all().build().unwrap()
 }
 
-pub type QueryResult = KMerge<EagerStream<std::vec::IntoIter<Data>>, Data, TsInitComparator>;
+/// Creates the DataFusion session context used to register and query tables.
+fn new_session_context(
+    use_mmap: bool,
+    target_partitions: Option<usize>,
+    reader_batch_size: Option<usize>,
+) -> SessionContext {
+    let mut session_cfg = SessionConfig::new()
+        .set_str("datafusion.optimizer.repartition_file_scans", "false")
+        .with_collect_statistics(true);
+    if let Some(n) = target_partitions {
+        session_cfg = session_cfg.with_target_partitions(n);
+    }
+    if let Some(n) = reader_batch_size {
+        session_cfg = session_cfg.with_batch_size(n);
+    }
+    let session_ctx = SessionContext::new_with_config(session_cfg);
+    if use_mmap {
+        // Replaces the default store for local paths, other schemes are unaffected
+        #[cfg(feature = "mmap")]
+        session_ctx.register_object_store(
+            ObjectStoreUrl::local_filesystem().as_ref(),
+            Arc::new(MmapFileSystem::new()),
+        );
+    }
+    session_ctx
+}
 
-/// Provides a DataFusion session and registers DataFusion queries.
-///
-/// The session is used to register data sources and make queries on them. A
-/// query returns a Chunk of Arrow records. It is decoded and converted into
-/// a Vec of data by types that implement [`DecodeDataFromRecordBatch`].
-#[cfg_attr(
-    feature = "python",
-    pyo3::pyclass(module = "nautilus_trader.core.nautilus_pyo3.persistence")
-)]
-pub struct DataBackendSession {
-    pub chunk_size: usize,
-    pub runtime: Arc<tokio::runtime::Runtime>,
-    session_ctx: SessionContext,
-    batch_streams: Vec<EagerStream<IntoIter<Data>>>,
+/// Returns the options for reading parquet files ordered by `ts_init`.
+fn parquet_read_options() -> ParquetReadOptions<'static> {
+    ParquetReadOptions {
+        skip_metadata: Some(false),
+        file_sort_order: vec![vec![Expr::Sort(Sort {
+            expr: Box::new(col("ts_init")),
+            asc: true,
+            nulls_first: true,
+        })]],
+        ..Default::default()
+    }
 }
 
-impl DataBackendSession {
-    /// Creates a new [`DataBackendSession`] instance.
-    #[must_use]
-    pub fn new(chunk_size: usize) -> Self {
-        let runtime = tokio::runtime::Builder::new_multi_thread()
-            .enable_all()
-            .build()
-            .unwrap();
-        let session_cfg =
-            SessionConfig::new().set_str("datafusion.optimizer.repartition_file_scans", "false");
-        let session_ctx = SessionContext::new_with_config(session_cfg);
-        Self {
-            session_ctx,
-            batch_streams: Vec::default(),
-            chunk_size,
-            runtime: Arc::new(runtime),
+/// Returns the paths of the parquet files in the directory at `dir_path`, sorted
+/// by name. Hidden files, subdirectories and other files are skipped.
+fn list_parquet_files(dir_path: &str) -> Result<Vec<String>, PersistenceError> {
+    let mut file_paths = Vec::new();
+    for entry in fs::read_dir(dir_path)? {
+        let path = entry?.path();
+        let is_visible = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| !name.starts_with('.'));
+        let is_parquet = path.extension().is_some_and(|ext| ext == "parquet");
+        if path.is_file() && is_parquet && is_visible {
+            file_paths.push(path.to_string_lossy().into_owned());
         }
     }
+    file_paths.sort();
+    Ok(file_paths)
+}
 
-    pub fn write_data<T: EncodeToRecordBatch>(
-        data: &[T],
-        metadata: &HashMap<String, String>,
-        stream: &mut dyn WriteStream,
-    ) -> Result<(), DataStreamingError> {
-        let record_batch = T::encode_batch(metadata, data)?;
-        stream.write(&record_batch)?;
-        Ok(())
+/// The row count and the `ts_init` bounds of a registered table.
+type TableStats = (Option<u64>, Option<UnixNanos>, Option<UnixNanos>);
+
+/// Returns the [`TableInfo`] of the table `table_name` of data type `T`, with the
+/// given `stats` and `chunk_size`.
+fn table_info<T: NautilusDataTypeProvider>(
+    table_name: &str,
+    stats: TableStats,
+    chunk_size: usize,
+) -> TableInfo {
+    let (row_count, min_ts_init, max_ts_init) = stats;
+    TableInfo {
+        table_name: table_name.to_string(),
+        data_type: T::DATA_TYPE,
+        chunk_size,
+        row_count,
+        min_ts_init,
+        max_ts_init,
     }
+}
 
-    /// Query a file for its records. the caller must specify `T` to indicate
-    /// the kind of data expected from this query.
-    ///
-    /// `table_name`: Logical `table_name` assigned to this file. Queries to this file should address the
-    /// file by its table name.
-    /// `file_path`: Path to file
-    /// `sql_query`: A custom sql query to retrieve records from file. If no query is provided a default
-    /// query "SELECT * FROM <`table_name`>" is run.
-    ///
-    /// # Safety
-    ///
-    /// The file data must be ordered by the `ts_init` in ascending order for this
-    /// to work correctly.
-    pub fn add_file<T>(
-        &mut self,
-        table_name: &str,
-        file_path: &str,
-        sql_query: Option<&str>,
-    ) -> Result<()>
-    where
-        T: DecodeDataFromRecordBatch + Into<Data>,
-    {
-        let parquet_options = ParquetReadOptions::<'_> {
-            skip_metadata: Some(false),
-            file_sort_order: vec![vec![Expr::Sort(Sort {
-                expr: Box::new(col("ts_init")),
-                asc: true,
-                nulls_first: true,
-            })]],
-            ..Default::default()
+/// Returns the statistics of the parquet file at `file_path`.
+///
+/// The footer of a remote file is not read, so its statistics are unavailable.
+fn file_stats(file_path: &str) -> Result<TableStats> {
+    if object_store_uri(file_path).is_some() {
+        return Ok((None, None, None));
+    }
+
+    let (row_count, min_ts_init, max_ts_init) = read_ts_init_stats(file_path)?;
+    Ok((Some(row_count), min_ts_init, max_ts_init))
+}
+
+/// Returns the statistics of a table of files with the given `file_stats`.
+fn merge_stats(file_stats: &[TableStats]) -> TableStats {
+    let Some((first, rest)) = file_stats.split_first() else {
+        return (Some(0), None, None);
+    };
+    rest.iter()
+        .fold(*first, |stats, (row_count, min_ts_init, max_ts_init)| {
+            (
+                stats.0.zip(*row_count).map(|(a, b)| a + b),
+                stats.1.zip(*min_ts_init).map(|(a, b)| a.min(b)),
+                stats.2.zip(*max_ts_init).map(|(a, b)| a.max(b)),
+            )
+        })
+}
+
+/// Returns the statistics of the in-memory record `batch`.
+fn batch_stats(batch: &RecordBatch) -> TableStats {
+    let ts_init = batch
+        .column_by_name("ts_init")
+        .and_then(|column| column.as_any().downcast_ref::<UInt64Array>());
+    let min_ts_init = ts_init.and_then(min).map(UnixNanos::from);
+    let max_ts_init = ts_init.and_then(max).map(UnixNanos::from);
+    (Some(batch.num_rows() as u64), min_ts_init, max_ts_init)
+}
+
+/// Reads the row count and the `ts_init` bounds of the parquet file at `file_path`
+/// from its footer, without scanning any data.
+///
+/// The bounds are `None` if any row group is missing `ts_init` statistics.
+fn read_ts_init_stats(file_path: &str) -> Result<(u64, Option<UnixNanos>, Option<UnixNanos>)> {
+    let file = File::open(file_path)?;
+    let metadata = parse_metadata(&file)?;
+    let row_count = metadata.file_metadata().num_rows() as u64;
+
+    let ts_init_index = metadata
+        .file_metadata()
+        .schema_descr()
+        .columns()
+        .iter()
+        .position(|column| column.name() == "ts_init");
+
+    let Some(ts_init_index) = ts_init_index else {
+        return Ok((row_count, None, None));
+    };
+
+    let mut bounds: Option<(u64, u64)> = None;
+    for row_group in metadata.row_groups() {
+        let Some((min, max)) = row_group_bounds(row_group, ts_init_index) else {
+            return Ok((row_count, None, None));
         };
-        self.runtime.block_on(self.session_ctx.register_parquet(
-            table_name,
-            file_path,
-            parquet_options,
-        ))?;
+        bounds = Some(match bounds {
+            Some((acc_min, acc_max)) => (acc_min.min(min), acc_max.max(max)),
+            None => (min, max),
+        });
+    }
 
-        let default_query = format!("SELECT * FROM {}", &table_name);
-        let sql_query = sql_query.unwrap_or(&default_query);
-        let query = self.runtime.block_on(self.session_ctx.sql(sql_query))?;
+    Ok((
+        row_count,
+        bounds.map(|(min, _)| min.into()),
+        bounds.map(|(_, max)| max.into()),
+    ))
+}
 
-        let batch_stream = self.runtime.block_on(query.execute_stream())?;
+/// Returns the bounds of a timestamp column of a row group from the statistics
+/// of the column at `index` in nanoseconds, or `None` if the statistics are
+/// missing.
+fn row_group_bounds(row_group: &RowGroupMetaData, index: usize) -> Option<(u64, u64)> {
+    let column = row_group.column(index);
+    let scale = nanos_per_unit(column.column_descr());
+    match column.statistics() {
+        // Unsigned integers are stored with the INT64 physical type
+        Some(Statistics::Int64(stats)) if stats.has_min_max_set() => {
+            Some((*stats.min() as u64 * scale, *stats.max() as u64 * scale))
+        }
+        _ => None,
+    }
+}
 
-        self.add_batch_stream::<T>(batch_stream);
-        Ok(())
+/// Returns the number of nanoseconds per unit of the parquet timestamp `column`,
+/// which is one for a column of plain integer nanoseconds.
+fn nanos_per_unit(column: &ColumnDescriptor) -> u64 {
+    match (column.logical_type(), column.converted_type()) {
+        (Some(LogicalType::Timestamp { unit, .. }), _) => match unit {
+            ParquetTimeUnit::MILLIS(_) => 1_000_000,
+            ParquetTimeUnit::MICROS(_) => 1_000,
+            ParquetTimeUnit::NANOS(_) => 1,
+        },
+        (_, ConvertedType::TIMESTAMP_MILLIS) => 1_000_000,
+        (_, ConvertedType::TIMESTAMP_MICROS) => 1_000,
+        _ => 1,
     }
+}
 
-    fn add_batch_stream<T>(&mut self, stream: SendableRecordBatchStream)
-    where
-        T: DecodeDataFromRecordBatch + Into<Data>,
-    {
-        let transform = stream.map(|result| match result {
-            Ok(batch) => T::decode_data_batch(batch.schema().metadata(), batch)
-                .unwrap()
-                .into_iter(),
-            Err(e) => panic!("Error getting next batch from RecordBatchStream: {e}"),
+/// Converts an Arrow timestamp `column` of any unit into `UInt64` nanoseconds,
+/// returning any other column unchanged.
+fn timestamp_to_nanos(column: &ArrayRef) -> Result<ArrayRef, ArrowError> {
+    let DataType::Timestamp(_, tz) = column.data_type() else {
+        return Ok(column.clone());
+    };
+    let nanos = cast(
+        column,
+        &DataType::Timestamp(TimeUnit::Nanosecond, tz.clone()),
+    )?;
+    cast(&cast(&nanos, &DataType::Int64)?, &DataType::UInt64)
+}
+
+/// Converts the Arrow timestamp columns of `batch` into `UInt64` nanoseconds, as
+/// written by producers which store timestamps such as `ts_init` with a unit.
+fn normalize_timestamps(batch: RecordBatch) -> Result<RecordBatch, ArrowError> {
+    let schema = batch.schema();
+    let is_timestamp = |field: &Field| matches!(field.data_type(), DataType::Timestamp(..));
+    if !schema.fields().iter().any(|field| is_timestamp(field)) {
+        return Ok(batch);
+    }
+
+    let fields: Vec<_> = schema
+        .fields()
+        .iter()
+        .map(|field| {
+            if is_timestamp(field) {
+                Arc::new(field.as_ref().clone().with_data_type(DataType::UInt64))
+            } else {
+                field.clone()
+            }
+        })
+        .collect();
+    let columns = batch
+        .columns()
+        .iter()
+        .map(timestamp_to_nanos)
+        .collect::<Result<Vec<_>, _>>()?;
+    let schema = Schema::new_with_metadata(fields, schema.metadata().clone());
+    RecordBatch::try_new(Arc::new(schema), columns)
+}
+
+/// Applies `null_policy` to the nulls in the price and size columns of `batch`.
+fn apply_null_policy(
+    batch: RecordBatch,
+    null_policy: NullPolicy,
+) -> Result<RecordBatch, PersistenceError> {
+    let schema = batch.schema();
+    let nullable: Vec<usize> = (0..batch.num_columns())
+        .filter(|&i| {
+            FIXED_POINT_FIELDS.contains(&schema.field(i).name().as_str())
+                && batch.column(i).null_count() > 0
+        })
+        .collect();
+    let Some(&first) = nullable.first() else {
+        return Ok(batch);
+    };
+
+    match null_policy {
+        NullPolicy::Error => Err(PersistenceError::NullValue(
+            schema.field(first).name().clone(),
+        )),
+        NullPolicy::Skip => {
+            let mut mask = is_not_null(batch.column(first))?;
+            for &i in &nullable[1..] {
+                mask = and(&mask, &is_not_null(batch.column(i))?)?;
+            }
+            Ok(filter_record_batch(&batch, &mask)?)
+        }
+        NullPolicy::Zero => {
+            let mut columns = batch.columns().to_vec();
+            for &i in &nullable {
+                let column = &columns[i];
+                let zero = cast(&Int64Array::from(vec![0]), column.data_type())?;
+                columns[i] = zip(&is_null(column)?, &Scalar::new(zero), column)?;
+            }
+            Ok(RecordBatch::try_new(schema, columns)?)
+        }
+    }
+}
+
+/// Returns whether a column of `data_type` can be decoded as `field`, which
+/// allows Arrow timestamps of any unit for the `UInt64` nanosecond timestamps.
+fn is_decodable_as(data_type: &DataType, field: &Field) -> bool {
+    data_type == field.data_type()
+        || (field.data_type() == &DataType::UInt64
+            && field.name().starts_with("ts_")
+            && matches!(data_type, DataType::Timestamp(..)))
+}
+
+/// Checks that the column of `sort_key` in the parquet file at `file_path` is
+/// monotonically increasing, returning an error for the first out of order row.
+///
+/// A row group whose statistics show a single value is checked against the
+/// previous row without being read.
+fn check_monotonic(file_path: &str, sort_key: SortKey) -> Result<(), PersistenceError> {
+    let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(file_path)?)?;
+    let metadata = builder.metadata().clone();
+    let schema_descr = metadata.file_metadata().schema_descr();
+    let Some(ts_index) = schema_descr
+        .columns()
+        .iter()
+        .position(|column| column.name() == sort_key.column())
+    else {
+        return Err(PersistenceError::SchemaMismatch {
+            expected: format!("`{sort_key}` (UInt64)"),
+            found: "no column".to_string(),
         });
+    };
+    let mask = ProjectionMask::leaves(schema_descr, [ts_index]);
 
-        self.batch_streams
-            .push(EagerStream::from_stream_with_runtime(
-                transform,
-                self.runtime.clone(),
-            ));
+    let mut prev_ts: Option<u64> = None;
+    let mut offset = 0u64;
+    for (i, row_group) in metadata.row_groups().iter().enumerate() {
+        let constant_ts = row_group_bounds(row_group, ts_index)
+            .filter(|(min, max)| min == max)
+            .map(|(min, _)| min);
+
+        let ts_values: Vec<u64> = match constant_ts {
+            Some(ts) => vec![ts],
+            None => {
+                let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(file_path)?)?
+                    .with_projection(mask.clone())
+                    .with_row_groups(vec![i])
+                    .build()?;
+                let mut values = Vec::with_capacity(row_group.num_rows() as usize);
+                for batch in reader {
+                    let batch = batch.map_err(ParquetError::from)?;
+                    let column = timestamp_to_nanos(batch.column(0))?;
+                    let column =
+                        column
+                            .as_any()
+                            .downcast_ref::<UInt64Array>()
+                            .ok_or_else(|| PersistenceError::SchemaMismatch {
+                                expected: format!("`{sort_key}` (UInt64)"),
+                                found: format!("`{sort_key}` ({})", batch.column(0).data_type()),
+                            })?;
+                    values.extend(column.values().iter());
+                }
+                values
+            }
+        };
+
+        for (j, ts) in ts_values.into_iter().enumerate() {
+            if let Some(prev) = prev_ts {
+                if ts < prev {
+                    return Err(PersistenceError::NotMonotonic {
+                        sort_key,
+                        index: offset + j as u64,
+                        prev_ts: prev.into(),
+                        ts: ts.into(),
+                    });
+                }
+            }
+            prev_ts = Some(ts);
+        }
+        offset += row_group.num_rows() as u64;
     }
 
-    // Consumes the registered queries and returns a [`QueryResult].
-    // Passes the output of the query though the a KMerge which sorts the
-    // queries in ascending order of `ts_init`.
-    // QueryResult is an iterator that return Vec<Data>.
-    pub fn get_query_result(&mut self) -> QueryResult {
-        let mut kmerge: KMerge<_, _, _> = KMerge::new(TsInitComparator);
+    Ok(())
+}
+
+/// Validates that each of the parquet files at `paths` is monotonically
+/// increasing by `ts_init`, returning a report per file in the order of `paths`.
+///
+/// The files are checked concurrently, with up to one thread per available core.
+/// Only the `ts_init` column is scanned, and row groups whose statistics show a
+/// single value are checked without being read, as for
+/// [`DataBackendSession::add_file_checked`].
+#[must_use]
+pub fn validate_catalog(paths: &[String]) -> Vec<ValidationReport> {
+    let num_threads = thread::available_parallelism()
+        .map_or(1, usize::from)
+        .min(paths.len());
+    let next_path = AtomicUsize::new(0);
+
+    let mut reports: Vec<(usize, ValidationReport)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..num_threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut reports = Vec::new();
+                    loop {
+                        let i = next_path.fetch_add(1, Ordering::Relaxed);
+                        let Some(file_path) = paths.get(i) else {
+                            break reports;
+                        };
+                        reports.push((
+                            i,
+                            ValidationReport {
+                                file_path: file_path.clone(),
+                                result: check_monotonic(file_path, SortKey::TsInit),
+                            },
+                        ));
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("validation thread panicked"))
+            .collect()
+    });
+
+    reports.sort_by_key(|(i, _)| *i);
+    reports.into_iter().map(|(_, report)| report).collect()
+}
 
-        self.batch_streams
-            .drain(..)
-            .for_each(|eager_stream| kmerge.push_iter(eager_stream));
+/// Compares the data of type `T` of the parquet files at `path_a` and `path_b`
+/// element by element, as for migrating a catalog.
+///
+/// The decoded [`Data`] is compared rather than the files themselves, so files
+/// holding the same data with a different row group layout or compression are
+/// equal. Both files are streamed in chunks, and the comparison stops at the
+/// first difference.
+///
+/// # Errors
+///
+/// This function returns an error if either file cannot be read as `T`, or a
+/// record batch fails to decode.
+pub fn compare_catalogs<T>(
+    path_a: &str,
+    path_b: &str,
+) -> Result<CatalogComparison, PersistenceError>
+where
+    T: DecodeDataFromRecordBatch + NautilusDataTypeProvider + Into<Data>,
+{
+    let read = |file_path: &str| -> Result<_, PersistenceError> {
+        let mut session = DataBackendSession::new(10_000)?;
+        session.add_file::<T>("catalog", file_path, None)?;
+        Ok(session.get_query_result().flatten_results())
+    };
+    let mut data_a = read(path_a)?;
+    let mut data_b = read(path_b)?;
 
-        kmerge
+    let mut index = 0;
+    loop {
+        match (data_a.next().transpose()?, data_b.next().transpose()?) {
+            (None, None) => {
+                return Ok(CatalogComparison {
+                    first_difference: None,
+                })
+            }
+            (Some(a), Some(b)) if a == b => index += 1,
+            _ => {
+                return Ok(CatalogComparison {
+                    first_difference: Some(index),
+                })
+            }
+        }
     }
 }
 
-// Note: Intended to be used on a single Python thread
-unsafe impl Send for DataBackendSession {}
+/// Groups `data` by instrument, keeping the order of the elements of each
+/// instrument, so a time-ordered input gives time-ordered groups.
+///
+/// The groups hold a copy of each element, so the whole input is buffered a
+/// second time. To process the instruments of a large result in turn, query
+/// each with [`DataBackendSession::add_file_for_instrument`] instead.
+#[must_use]
+pub fn group_by_instrument(data: &[Data]) -> HashMap<InstrumentId, Vec<Data>> {
+    let mut groups: HashMap<InstrumentId, Vec<Data>> = HashMap::new();
+    for item in data {
+        groups
+            .entry(item.instrument_id())
+            .or_default()
+            .push(item.clone());
+    }
+    groups
+}
+
+/// Returns the gaps in `data` longer than `max_gap_ns`, as the pairs of the
+/// `ts_init` of consecutive elements more than `max_gap_ns` apart.
+///
+/// The `data` is expected to be ordered by `ts_init`, as yielded by a
+/// [`QueryResult`], such as to find the missing windows of a series of bars.
+#[must_use]
+pub fn find_gaps(data: &[Data], max_gap_ns: u64) -> Vec<(u64, u64)> {
+    data.windows(2)
+        .map(|window| (window[0].ts_init().as_u64(), window[1].ts_init().as_u64()))
+        .filter(|(prev, next)| next.saturating_sub(*prev) > max_gap_ns)
+        .collect()
+}
+
+/// Splits `data` at `ts`, into the elements with a `ts_init` before `ts` and
+/// those at or after it, so data exactly at `ts` goes to the second half.
+///
+/// The `data` must be ordered by `ts_init`, as yielded by a [`QueryResult`], as
+/// the split point is found by a binary search. The second half is moved into a
+/// new allocation, while the first keeps the allocation of `data`.
+#[must_use]
+pub fn split_at_ts(mut data: Vec<Data>, ts: UnixNanos) -> (Vec<Data>, Vec<Data>) {
+    let index = data.partition_point(|item| item.ts_init() < ts);
+    let after = data.split_off(index);
+    (data, after)
+}
+
+/// Returns whether `field` may be missing from a query, in which case it is
+/// decoded as zero: an integer field other than `ts_init`.
+fn is_optional_field(field: &Field) -> bool {
+    matches!(field.data_type(), DataType::Int64 | DataType::UInt64) && field.name() != "ts_init"
+}
+
+/// Returns the optional fields of `T` which cannot be matched to a column of
+/// `schema` as for [`decode_projection`].
+fn missing_optional_fields<T: ArrowSchemaProvider>(schema: &Schema) -> Vec<Field> {
+    let expected = T::get_schema(None);
+    expected
+        .fields()
+        .iter()
+        .enumerate()
+        .filter(|(i, field)| {
+            let matches_position = schema.fields().get(*i).is_some_and(|found| {
+                is_decodable_as(found.data_type(), field)
+                    && expected.index_of(found.name()).is_err()
+            });
+            is_optional_field(field) && schema.index_of(field.name()).is_err() && !matches_position
+        })
+        .map(|(_, field)| field.as_ref().clone())
+        .collect()
+}
+
+/// Returns the indices of the columns in `schema` required to decode `T`, in
+/// the order expected by its Arrow schema.
+///
+/// Columns are matched by name, falling back to the column at the same position
+/// when it has the expected data type and a name unknown to `T` (as for files
+/// written with legacy column names). Returns an error naming the first required
+/// column which cannot be matched.
+fn decode_projection<T: ArrowSchemaProvider>(
+    schema: &Schema,
+) -> Result<Vec<usize>, PersistenceError> {
+    let expected = T::get_schema(None);
+    expected
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let mismatch = |found: String| PersistenceError::SchemaMismatch {
+                expected: format!("`{}` ({})", field.name(), field.data_type()),
+                found,
+            };
+
+            if let Ok(index) = schema.index_of(field.name()) {
+                let found = schema.field(index);
+                if !is_decodable_as(found.data_type(), field) {
+                    return Err(mismatch(format!(
+                        "`{}` ({})",
+                        found.name(),
+                        found.data_type()
+                    )));
+                }
+                return Ok(index);
+            }
+            match schema.fields().get(i) {
+                Some(found)
+                    if is_decodable_as(found.data_type(), field)
+                        && expected.index_of(found.name()).is_err() =>
+                {
+                    Ok(i)
+                }
+                Some(found) => Err(mismatch(format!(
+                    "`{}` ({})",
+                    found.name(),
+                    found.data_type()
+                ))),
+                None => Err(mismatch("no column".to_string())),
+            }
+        })
+        .collect()
+}
+
+/// Checks that every column of `schema` is in the decode `projection`, other
+/// than the `instrument_id` and `bar_type` columns, returning an error naming
+/// the columns which are not.
+fn check_extra_columns(schema: &Schema, projection: &[usize]) -> Result<(), PersistenceError> {
+    let extra: Vec<&str> = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .filter(|(i, field)| {
+            !projection.contains(i)
+                && field.name() != KEY_INSTRUMENT_ID
+                && field.name() != KEY_BAR_TYPE
+        })
+        .map(|(_, field)| field.name().as_str())
+        .collect();
+    if extra.is_empty() {
+        Ok(())
+    } else {
+        Err(PersistenceError::UnexpectedColumns(extra.join(", ")))
+    }
+}
+
+/// Returns whether bars of the given `aggregation` are sampled by time.
+const fn is_time_aggregation(aggregation: BarAggregation) -> bool {
+    matches!(
+        aggregation,
+        BarAggregation::Millisecond
+            | BarAggregation::Second
+            | BarAggregation::Minute
+            | BarAggregation::Hour
+            | BarAggregation::Day
+    )
+}
+
+/// Returns the scheme and bucket of `file_path` if it is an object storage URI,
+/// where the bucket of an HTTP(S) URL is its host.
+fn object_store_uri(file_path: &str) -> Option<(&str, &str)> {
+    let (scheme, path) = file_path.split_once("://")?;
+    match scheme {
+        "s3" | "gs" | "http" | "https" => path.split('/').next().map(|bucket| (scheme, bucket)),
+        _ => None,
+    }
+}
+
+/// Builds a query selecting all rows of `table_name` with `ts_init` within the
+/// inclusive `start` and `end` bounds, ordered by `ts_init`.
+fn build_range_query(table_name: &str, start: Option<UnixNanos>, end: Option<UnixNanos>) -> String {
+    format!(
+        "SELECT * FROM {table_name}{} ORDER BY ts_init",
+        build_range_filter(start, end)
+    )
+}
+
+/// Builds a `WHERE` clause filtering `ts_init` within the inclusive `start` and
+/// `end` bounds, which is empty if both are unbounded.
+fn build_range_filter(start: Option<UnixNanos>, end: Option<UnixNanos>) -> String {
+    let mut conditions = Vec::new();
+    if let Some(start) = start {
+        conditions.push(format!("ts_init >= {start}"));
+    }
+    if let Some(end) = end {
+        conditions.push(format!("ts_init <= {end}"));
+    }
+
+    if conditions.is_empty() {
+        String::new()
+    } else {
+        format!(" WHERE {}", conditions.join(" AND "))
+    }
+}
 
+/// Yields the merged data of a [`QueryResult`] in chunks of at most `size`
+/// elements.
+///
+/// A chunk returned as a `CVec` by [`DataQueryResult::next_chunk`] is owned by
+/// the reader, and is freed when the next chunk is requested, on
+/// [`DataQueryResult::clear`], or when the reader itself is dropped. The chunks
+/// returned to Python are instead owned by their `PyCapsule`, and are freed when
+/// the capsule is garbage collected.
 #[cfg_attr(
     feature = "python",
     pyo3::pyclass(module = "nautilus_trader.core.nautilus_pyo3.persistence")
@@ -184,6 +4214,9 @@ pub struct DataQueryResult {
     pub result: QueryResult,
     pub acc: Vec<Data>,
     pub size: usize,
+    /// If chunks are yielded to Python as raw `CVec` capsules, rather than as
+    /// lists of Python objects.
+    pub raw: bool,
 }
 
 impl DataQueryResult {
@@ -195,6 +4228,7 @@ impl DataQueryResult {
             result,
             acc: Vec::new(),
             size,
+            raw: true,
         }
     }
 
@@ -209,6 +4243,15 @@ impl DataQueryResult {
         chunk
     }
 
+    /// Returns the next chunk of data as a `CVec`, or `None` once the result
+    /// is exhausted.
+    ///
+    /// The chunk is owned by the reader and is dropped when the next chunk is
+    /// requested or the reader itself is dropped.
+    pub fn next_chunk(&mut self) -> Option<CVec> {
+        self.next().map(|data| self.set_chunk(data))
+    }
+
     /// Chunks generated by iteration must be dropped after use, otherwise
     /// it will leak memory. Current chunk is held by the reader,
     /// drop if exists and reset the field.
@@ -219,19 +4262,32 @@ impl DataQueryResult {
             drop(data);
         }
     }
+
+    /// Drops the current chunk and clears the pending queries, after which no
+    /// more chunks are returned.
+    pub fn clear(&mut self) {
+        self.drop_chunk();
+        self.acc.clear();
+        self.result.clear();
+    }
 }
 
 impl Iterator for DataQueryResult {
     type Item = Vec<Data>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        for _ in 0..self.size {
+        let size = self.result.row_groups.pop_front().unwrap_or(self.size);
+        for _ in 0..size {
             match self.result.next() {
                 Some(item) => self.acc.push(item),
                 None => break,
             }
         }
 
+        if self.acc.is_empty() {
+            return None;
+        }
+
         // TODO: consider using drain here if perf is unchanged
         // Some(self.acc.drain(0..).collect())
         let mut acc: Vec<Data> = Vec::new();
@@ -242,8 +4298,55 @@ impl Iterator for DataQueryResult {
 
 impl Drop for DataQueryResult {
     fn drop(&mut self) {
-        self.drop_chunk();
-        self.result.clear();
+        self.clear();
+    }
+}
+
+/// Provides the chunks of a [`QueryResult`] as an asynchronous [`Stream`].
+///
+/// The merge runs on a dedicated thread, as decoding blocks on the session
+/// runtime, and sends each chunk through a channel with capacity for a single
+/// chunk. Dropping the stream stops the merge.
+pub struct DataQueryStream {
+    rx: Receiver<Vec<Data>>,
+    cancellation_token: Option<CancellationToken>,
+}
+
+impl DataQueryStream {
+    /// Creates a new [`DataQueryStream`] instance.
+    #[must_use]
+    pub fn new(result: QueryResult, size: usize) -> Self {
+        let cancellation_token = result.cancellation_token.clone();
+        let (tx, rx) = mpsc::channel(1);
+        thread::spawn(move || {
+            for chunk in DataQueryResult::new(result, size) {
+                if tx.blocking_send(chunk).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            rx,
+            cancellation_token,
+        }
+    }
+}
+
+impl Stream for DataQueryStream {
+    type Item = Vec<Data>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        // A chunk decoded ahead of the consumer is discarded once cancelled
+        if self
+            .cancellation_token
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+        {
+            self.rx.close();
+            return Poll::Ready(None);
+        }
+        self.rx.poll_recv(cx)
     }
 }
 
@@ -0,0 +1,311 @@
+// -------------------------------------------------------------------------------------------------
+//  Copyright (C) 2015-2024 Nautech Systems Pty Ltd. All rights reserved.
+//  https://nautechsystems.io
+//
+//  Licensed under the GNU Lesser General Public License Version 3.0 (the "License");
+//  You may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at https://www.gnu.org/licenses/lgpl-3.0.en.html
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+// -------------------------------------------------------------------------------------------------
+
+//! Provides the sampling of order book snapshots from a stream of deltas.
+
+use nautilus_model::{
+    data::{
+        delta::OrderBookDelta,
+        depth::{OrderBookDepth10, DEPTH10_LEN},
+        order::{BookOrder, NULL_ORDER},
+        Data,
+    },
+    enums::{BookType, OrderSide, RecordFlag},
+    orderbook::{book::OrderBook, level::Level},
+    types::quantity::Quantity,
+};
+
+use crate::error::PersistenceError;
+
+/// Replays order book deltas into a book, and samples a snapshot of the book at
+/// each interval boundary.
+///
+/// The snapshot at a boundary includes every delta with a `ts_init` up to and
+/// including the boundary, and has its `ts_event` and `ts_init` set to the
+/// boundary. The book state is carried across calls to [`SnapshotSampler::process`],
+/// so deltas may arrive in any number of batches.
+#[derive(Debug)]
+pub struct SnapshotSampler {
+    interval_ns: u64,
+    depth: usize,
+    book: Option<OrderBook>,
+    next_boundary: u64,
+}
+
+impl SnapshotSampler {
+    /// Creates a new [`SnapshotSampler`] instance.
+    ///
+    /// `interval_ns`: The interval between snapshots.
+    /// `depth`: The number of levels of each side in a snapshot.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if `interval_ns` is zero, or `depth` is
+    /// not between 1 and 10.
+    pub fn new(interval_ns: u64, depth: usize) -> Result<Self, PersistenceError> {
+        if interval_ns == 0 {
+            return Err(PersistenceError::InvalidSnapshotInterval);
+        }
+        if depth == 0 || depth > DEPTH10_LEN {
+            return Err(PersistenceError::InvalidSnapshotDepth(depth));
+        }
+
+        Ok(Self {
+            interval_ns,
+            depth,
+            book: None,
+            next_boundary: 0,
+        })
+    }
+
+    /// Applies the deltas of `data` in order, returning the snapshots for the
+    /// interval boundaries which were passed. Data other than deltas is ignored.
+    ///
+    /// The book is for the instrument of the first delta.
+    pub fn process(&mut self, data: impl IntoIterator<Item = Data>) -> Vec<Data> {
+        let mut snapshots = Vec::new();
+        for data in data {
+            let Data::Delta(delta) = data else {
+                continue;
+            };
+            snapshots.extend(self.apply(&delta).into_iter().map(Data::Depth10));
+        }
+        snapshots
+    }
+
+    fn apply(&mut self, delta: &OrderBookDelta) -> Vec<OrderBookDepth10> {
+        let ts_init = delta.ts_init.as_u64();
+        let mut snapshots = Vec::new();
+        match &self.book {
+            Some(book) => {
+                while self.next_boundary < ts_init {
+                    snapshots.push(snapshot(
+                        book,
+                        self.depth,
+                        self.next_boundary,
+                        self.next_boundary,
+                    ));
+                    self.next_boundary += self.interval_ns;
+                }
+            }
+            None => {
+                self.book = Some(OrderBook::new(delta.instrument_id, BookType::L2_MBP));
+                self.next_boundary = (ts_init / self.interval_ns + 1) * self.interval_ns;
+            }
+        }
+
+        // A clear action resets the book, so later snapshots only hold later orders
+        if let Some(book) = &mut self.book {
+            book.apply_delta(delta);
+        }
+        snapshots
+    }
+}
+
+/// Replays order book deltas into a book, and decodes the top levels of the book
+/// after each delta into an [`OrderBookDepth10`].
+///
+/// Each snapshot has the `ts_event` and `ts_init` of the delta it follows, and
+/// levels beyond the depth are dropped. As with [`SnapshotSampler`], the book
+/// state is carried across calls to [`DepthDecoder::process`].
+#[derive(Debug)]
+pub struct DepthDecoder {
+    depth: usize,
+    book: Option<OrderBook>,
+}
+
+impl DepthDecoder {
+    /// Creates a new [`DepthDecoder`] instance.
+    ///
+    /// `depth`: The number of levels of each side in a snapshot, up to 10.
+    #[must_use]
+    pub fn new(depth: usize) -> Self {
+        Self {
+            depth: depth.min(DEPTH10_LEN),
+            book: None,
+        }
+    }
+
+    /// Applies the deltas of `data` in order, returning a snapshot after each
+    /// delta. Data other than deltas is ignored.
+    ///
+    /// The book is for the instrument of the first delta.
+    pub fn process(&mut self, data: impl IntoIterator<Item = Data>) -> Vec<Data> {
+        let mut snapshots = Vec::new();
+        for data in data {
+            let Data::Delta(delta) = data else {
+                continue;
+            };
+            let book = self
+                .book
+                .get_or_insert_with(|| OrderBook::new(delta.instrument_id, BookType::L2_MBP));
+            book.apply_delta(&delta);
+            snapshots.push(Data::Depth10(snapshot(
+                book,
+                self.depth,
+                delta.ts_event.as_u64(),
+                delta.ts_init.as_u64(),
+            )));
+        }
+        snapshots
+    }
+}
+
+/// Returns a snapshot of the top `depth` levels of each side of `book`.
+fn snapshot(book: &OrderBook, depth: usize, ts_event: u64, ts_init: u64) -> OrderBookDepth10 {
+    let mut bids = [NULL_ORDER; DEPTH10_LEN];
+    let mut asks = [NULL_ORDER; DEPTH10_LEN];
+    let mut bid_counts = [0; DEPTH10_LEN];
+    let mut ask_counts = [0; DEPTH10_LEN];
+    for (i, level) in book.bids().take(depth).enumerate() {
+        (bids[i], bid_counts[i]) = level_order(level, OrderSide::Buy);
+    }
+    for (i, level) in book.asks().take(depth).enumerate() {
+        (asks[i], ask_counts[i]) = level_order(level, OrderSide::Sell);
+    }
+
+    OrderBookDepth10::new(
+        book.instrument_id,
+        bids,
+        asks,
+        bid_counts,
+        ask_counts,
+        RecordFlag::F_SNAPSHOT as u8,
+        book.sequence,
+        ts_event.into(),
+        ts_init.into(),
+    )
+}
+
+/// Returns the aggregated order of a price `level`, and its count of orders.
+fn level_order(level: &Level, side: OrderSide) -> (BookOrder, u32) {
+    let precision = level.first().map_or(0, |order| order.size.precision);
+    let size = Quantity::from_raw(level.size_raw(), precision);
+    (
+        BookOrder::new(side, level.price.value, size, 0),
+        level.len() as u32,
+    )
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod tests {
+    use nautilus_model::{
+        enums::BookAction,
+        identifiers::InstrumentId,
+        types::{price::Price, quantity::Quantity},
+    };
+    use rstest::rstest;
+
+    use super::*;
+
+    fn add(side: OrderSide, price: &str, size: &str, ts: u64) -> Data {
+        let order = BookOrder::new(side, Price::from(price), Quantity::from(size), 0);
+        Data::Delta(OrderBookDelta::new(
+            InstrumentId::from("AUD/USD.SIM"),
+            BookAction::Add,
+            order,
+            0,
+            ts,
+            ts.into(),
+            ts.into(),
+        ))
+    }
+
+    fn clear(ts: u64) -> Data {
+        Data::Delta(OrderBookDelta::clear(
+            InstrumentId::from("AUD/USD.SIM"),
+            ts,
+            ts.into(),
+            ts.into(),
+        ))
+    }
+
+    #[rstest]
+    fn test_snapshots_carry_state_across_batches() {
+        let mut sampler = SnapshotSampler::new(10, 2).unwrap();
+        let first = sampler.process(vec![
+            add(OrderSide::Buy, "1.00", "100", 1),
+            add(OrderSide::Buy, "0.99", "200", 2),
+            add(OrderSide::Buy, "0.98", "300", 3),
+            add(OrderSide::Sell, "1.01", "100", 10),
+        ]);
+        let second = sampler.process(vec![add(OrderSide::Sell, "1.02", "50", 25)]);
+
+        assert!(first.is_empty());
+        assert_eq!(second.len(), 2);
+        let snapshot = second[0].as_depth10().unwrap();
+        assert_eq!(snapshot.ts_init.as_u64(), 10);
+        assert_eq!(snapshot.bids[0].price, Price::from("1.00"));
+        assert_eq!(snapshot.bids[1].price, Price::from("0.99"));
+        assert_eq!(snapshot.bids[2], NULL_ORDER);
+        assert_eq!(snapshot.asks[0].size, Quantity::from("100"));
+        assert_eq!(snapshot.bid_counts[..3], [1, 1, 0]);
+        // The boundary with no new deltas repeats the book
+        assert_eq!(second[1].as_depth10().unwrap().ts_init.as_u64(), 20);
+        assert_eq!(second[1].as_depth10().unwrap().bids, snapshot.bids);
+    }
+
+    #[rstest]
+    fn test_snapshots_after_clear() {
+        let mut sampler = SnapshotSampler::new(10, 10).unwrap();
+        let snapshots = sampler.process(vec![
+            add(OrderSide::Buy, "1.00", "100", 1),
+            clear(5),
+            add(OrderSide::Sell, "1.01", "100", 6),
+            add(OrderSide::Sell, "1.02", "100", 11),
+        ]);
+
+        assert_eq!(snapshots.len(), 1);
+        let snapshot = snapshots[0].as_depth10().unwrap();
+        assert_eq!(snapshot.bids[0], NULL_ORDER);
+        assert_eq!(snapshot.asks[0].price, Price::from("1.01"));
+        assert_eq!(snapshot.ask_counts[..2], [1, 0]);
+    }
+
+    #[rstest]
+    fn test_depth_decoder_drops_levels_beyond_depth() {
+        let mut decoder = DepthDecoder::new(2);
+        let snapshots = decoder.process(vec![
+            add(OrderSide::Buy, "1.00", "100", 1),
+            add(OrderSide::Buy, "0.99", "200", 2),
+            add(OrderSide::Buy, "1.01", "300", 3),
+        ]);
+
+        assert_eq!(snapshots.len(), 3);
+        let last = snapshots[2].as_depth10().unwrap();
+        assert_eq!(last.ts_init.as_u64(), 3);
+        assert_eq!(last.bids[0].price, Price::from("1.01"));
+        assert_eq!(last.bids[1].price, Price::from("1.00"));
+        assert_eq!(last.bids[2], NULL_ORDER);
+        assert_eq!(last.bid_counts[..3], [1, 1, 0]);
+    }
+
+    #[rstest]
+    #[case(0, 10)]
+    #[case(10, 0)]
+    #[case(10, 11)]
+    fn test_invalid_sampler_errors(#[case] interval_ns: u64, #[case] depth: usize) {
+        let result = SnapshotSampler::new(interval_ns, depth);
+
+        assert!(matches!(
+            result,
+            Err(PersistenceError::InvalidSnapshotInterval
+                | PersistenceError::InvalidSnapshotDepth(_))
+        ));
+    }
+}
@@ -0,0 +1,234 @@
+// -------------------------------------------------------------------------------------------------
+//  Copyright (C) 2015-2024 Nautech Systems Pty Ltd. All rights reserved.
+//  https://nautechsystems.io
+//
+//  Licensed under the GNU Lesser General Public License Version 3.0 (the "License");
+//  You may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at https://www.gnu.org/licenses/lgpl-3.0.en.html
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+// -------------------------------------------------------------------------------------------------
+
+//! Provides the collection of query results larger than memory into temporary
+//! parquet files.
+
+use std::{
+    fs::{self, File},
+    mem::size_of,
+    path::{Path, PathBuf},
+    vec::IntoIter,
+};
+
+use datafusion::{
+    arrow::{datatypes::SchemaRef, record_batch::RecordBatch},
+    parquet::arrow::arrow_reader::{ParquetRecordBatchReader, ParquetRecordBatchReaderBuilder},
+};
+use nautilus_model::data::{
+    bar::Bar, delta::OrderBookDelta, depth::OrderBookDepth10, quote::QuoteTick, trade::TradeTick,
+    Data,
+};
+
+use super::session::{data_type, NautilusDataType};
+use crate::{
+    arrow::{decode_batch, encode_batch, EncodingError},
+    error::PersistenceError,
+    parquet::{write_batch_to_parquet, ParquetCompression},
+};
+
+/// The collected data of a query result, of which the data beyond a memory
+/// threshold is held in temporary parquet files.
+///
+/// Created by [`super::session::QueryResult::collect_to_spill`]. The data is
+/// read back lazily, a record batch at a time, and can be iterated any number
+/// of times. The temporary files are removed when the result is dropped.
+#[derive(Debug)]
+pub struct SpilledResult {
+    dir: PathBuf,
+    spill_dir: Option<PathBuf>,
+    files: Vec<(PathBuf, NautilusDataType)>,
+    memory: Vec<Data>,
+    len: usize,
+}
+
+impl SpilledResult {
+    /// Returns the number of data items of the result.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the result holds no data.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of temporary files the result was spilled to.
+    #[must_use]
+    pub fn num_spilled_files(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Returns an iterator over the data of the result, in collected order,
+    /// which reads the spilled files back one record batch at a time.
+    ///
+    /// The iterator yields an error if a spilled file fails to read or decode.
+    pub fn iter(&self) -> impl Iterator<Item = Result<Data, PersistenceError>> + '_ {
+        let mut files = self.files.iter();
+        let mut reader: Option<(ParquetRecordBatchReader, SchemaRef, NautilusDataType)> = None;
+        let mut chunk: IntoIter<Data> = Vec::new().into_iter();
+        let mut memory = self.memory.iter();
+        std::iter::from_fn(move || loop {
+            if let Some(data) = chunk.next() {
+                return Some(Ok(data));
+            }
+            if let Some((batches, schema, data_type)) = &mut reader {
+                match batches.next() {
+                    Some(Ok(batch)) => match decode_spilled(*data_type, batch, schema) {
+                        Ok(data) => chunk = data.into_iter(),
+                        Err(e) => return Some(Err(e)),
+                    },
+                    Some(Err(e)) => return Some(Err(EncodingError::from(e).into())),
+                    None => reader = None,
+                }
+                continue;
+            }
+            match files.next() {
+                Some((file_path, data_type)) => match open_spilled(file_path) {
+                    Ok((batches, schema)) => reader = Some((batches, schema, *data_type)),
+                    Err(e) => return Some(Err(e)),
+                },
+                None => return memory.next().cloned().map(Ok),
+            }
+        })
+    }
+
+    /// Writes the buffered `data` to temporary files, with a file for each run
+    /// of data sharing a data type, instrument and precisions.
+    fn spill(&mut self, data: &mut Vec<Data>) -> Result<(), PersistenceError> {
+        let spill_dir = match &self.spill_dir {
+            Some(spill_dir) => spill_dir.clone(),
+            None => {
+                let spill_dir = self
+                    .dir
+                    .join(format!("nautilus-spill-{:016x}", rand::random::<u64>()));
+                fs::create_dir(&spill_dir)?;
+                self.spill_dir = Some(spill_dir.clone());
+                spill_dir
+            }
+        };
+
+        for run in data.chunk_by(|a, b| encoding_key(a) == encoding_key(b)) {
+            let batch = encode_batch(run)?;
+            let file_path = spill_dir.join(format!("{:06}.parquet", self.files.len()));
+            write_batch_to_parquet(
+                &file_path.to_string_lossy(),
+                &batch,
+                ParquetCompression::None,
+            )?;
+            self.files.push((file_path, data_type(&run[0])));
+        }
+        data.clear();
+        Ok(())
+    }
+}
+
+impl Drop for SpilledResult {
+    fn drop(&mut self) {
+        if let Some(spill_dir) = &self.spill_dir {
+            if let Err(e) = fs::remove_dir_all(spill_dir) {
+                log::error!("Failed to remove spill directory {spill_dir:?}: {e}");
+            }
+        }
+    }
+}
+
+/// Collects `data` into a [`SpilledResult`], writing the buffered data to a
+/// temporary directory under `dir` whenever its size exceeds `threshold_bytes`.
+pub(crate) fn collect_to_spill(
+    data: impl Iterator<Item = Result<Data, PersistenceError>>,
+    dir: &Path,
+    threshold_bytes: usize,
+) -> Result<SpilledResult, PersistenceError> {
+    let mut result = SpilledResult {
+        dir: dir.to_path_buf(),
+        spill_dir: None,
+        files: Vec::new(),
+        memory: Vec::new(),
+        len: 0,
+    };
+    let mut buffer = Vec::new();
+    for item in data {
+        buffer.push(item?);
+        result.len += 1;
+        if buffer.len() * size_of::<Data>() > threshold_bytes {
+            result.spill(&mut buffer)?;
+        }
+    }
+    result.memory = buffer;
+    Ok(result)
+}
+
+/// Returns the key identifying the schema metadata `data` is encoded with, so
+/// each spilled file decodes back to the same data.
+fn encoding_key(data: &Data) -> (NautilusDataType, String, u8, u8) {
+    let (id, price_precision, size_precision) = match data {
+        Data::Delta(delta) => (
+            delta.instrument_id.to_string(),
+            delta.order.price.precision,
+            delta.order.size.precision,
+        ),
+        Data::Deltas(deltas) => (deltas.instrument_id.to_string(), 0, 0),
+        Data::Depth10(depth) => (
+            depth.instrument_id.to_string(),
+            depth.bids[0].price.precision,
+            depth.bids[0].size.precision,
+        ),
+        Data::Quote(quote) => (
+            quote.instrument_id.to_string(),
+            quote.bid_price.precision,
+            quote.bid_size.precision,
+        ),
+        Data::Trade(trade) => (
+            trade.instrument_id.to_string(),
+            trade.price.precision,
+            trade.size.precision,
+        ),
+        Data::Bar(bar) => (
+            bar.bar_type.to_string(),
+            bar.open.precision,
+            bar.volume.precision,
+        ),
+    };
+    (data_type(data), id, price_precision, size_precision)
+}
+
+/// Opens the spilled file at `file_path`, returning a reader of its record
+/// batches and its schema, which holds the metadata the batches lack.
+fn open_spilled(
+    file_path: &Path,
+) -> Result<(ParquetRecordBatchReader, SchemaRef), PersistenceError> {
+    let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(file_path)?)?;
+    let schema = builder.schema().clone();
+    Ok((builder.build()?, schema))
+}
+
+fn decode_spilled(
+    data_type: NautilusDataType,
+    batch: RecordBatch,
+    schema: &SchemaRef,
+) -> Result<Vec<Data>, PersistenceError> {
+    let batch = batch.with_schema(schema.clone())?;
+    match data_type {
+        NautilusDataType::OrderBookDelta => decode_batch::<OrderBookDelta>(&batch),
+        NautilusDataType::OrderBookDepth10 => decode_batch::<OrderBookDepth10>(&batch),
+        NautilusDataType::QuoteTick => decode_batch::<QuoteTick>(&batch),
+        NautilusDataType::TradeTick => decode_batch::<TradeTick>(&batch),
+        NautilusDataType::Bar => decode_batch::<Bar>(&batch),
+        NautilusDataType::InstrumentAny => Err(PersistenceError::UnsupportedType("InstrumentAny")),
+    }
+}
@@ -0,0 +1,135 @@
+// -------------------------------------------------------------------------------------------------
+//  Copyright (C) 2015-2024 Nautech Systems Pty Ltd. All rights reserved.
+//  https://nautechsystems.io
+//
+//  Licensed under the GNU Lesser General Public License Version 3.0 (the "License");
+//  You may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at https://www.gnu.org/licenses/lgpl-3.0.en.html
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+// -------------------------------------------------------------------------------------------------
+
+//! Errors associated with querying persisted data.
+
+use std::io;
+
+use datafusion::{arrow::error::ArrowError, error::DataFusionError, parquet::errors::ParquetError};
+use nautilus_core::nanos::UnixNanos;
+use nautilus_model::{
+    data::bar::BarTypeParseError, enums::BarAggregation, types::fixed::FIXED_PRECISION,
+};
+
+use crate::{
+    arrow::{DataStreamingError, EncodingError},
+    backend::session::SortKey,
+};
+
+/// The error of the operations of the persistence crate.
+///
+/// The public functions of the crate return this error. A [`DataStreamingError`]
+/// raised while writing converts into the matching variant where one exists, so
+/// an I/O error is [`PersistenceError::Io`] whichever function raised it.
+#[derive(thiserror::Error, Debug)]
+pub enum PersistenceError {
+    #[error("DataFusion error: {0}")]
+    DataFusion(#[from] DataFusionError),
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] ParquetError),
+    #[error("Arrow error: {0}")]
+    Arrow(#[from] ArrowError),
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Encoding error: {0}")]
+    Encoding(#[from] EncodingError),
+    #[error("Schema mismatch: expected column {expected}, found {found}")]
+    SchemaMismatch { expected: String, found: String },
+    #[error("Invalid bar type: {0}")]
+    InvalidBarType(#[from] BarTypeParseError),
+    #[error("Bar aggregation {0} is not time based")]
+    UnsupportedAggregation(BarAggregation),
+    #[error(
+        "Bar interval {target_ns}ns is not a multiple of the file bar interval {interval_ns}ns"
+    )]
+    InvalidBarInterval { interval_ns: u64, target_ns: u64 },
+    #[error("Bar step must be positive")]
+    InvalidBarStep,
+    #[error("Invalid projection: column `{column}` {reason}")]
+    InvalidProjection {
+        column: String,
+        reason: &'static str,
+    },
+    #[error("Missing columns for required fields: {0}")]
+    MissingColumns(String),
+    #[error("Unexpected columns not decoded for the data type: {0}")]
+    UnexpectedColumns(String),
+    #[error("Unknown data type: `{0}`")]
+    UnknownDataType(String),
+    #[error("No query registered for table `{0}`")]
+    QueryNotFound(String),
+    #[error("Chunk size must be positive")]
+    InvalidChunkSize,
+    #[error("Number of decode threads must be positive")]
+    InvalidDecodeThreads,
+    #[error("Number of target partitions must be positive")]
+    InvalidTargetPartitions,
+    #[error("Reader batch size must be positive")]
+    InvalidReaderBatchSize,
+    #[error("Snapshot interval must be positive")]
+    InvalidSnapshotInterval,
+    #[error("Snapshot depth {0} is not between 1 and 10")]
+    InvalidSnapshotDepth(usize),
+    #[error("Invalid row group range {start}..{end}: the file has {num_row_groups} row groups")]
+    InvalidRowGroupRange {
+        start: usize,
+        end: usize,
+        num_row_groups: usize,
+    },
+    #[error("Data not monotonically increasing by `{sort_key}` at row {index}: {prev_ts} > {ts}")]
+    NotMonotonic {
+        sort_key: SortKey,
+        index: u64,
+        prev_ts: UnixNanos,
+        ts: UnixNanos,
+    },
+    #[error("Precision {0} exceeds the maximum of {}", FIXED_PRECISION)]
+    InvalidPrecision(u8),
+    #[error("File `{key}` {found} does not match the expected {expected}")]
+    PrecisionMismatch {
+        key: &'static str,
+        expected: u8,
+        found: u8,
+    },
+    #[error("Null value in column `{0}`")]
+    NullValue(String),
+    #[error("Not an HTTP or HTTPS URL: `{0}`")]
+    InvalidHttpUrl(String),
+    #[error("Data contained more than one data type")]
+    MixedDataTypes,
+    #[error("Unsupported data type: `{0}`")]
+    UnsupportedType(&'static str),
+    #[error("Data streaming error: {0}")]
+    DataStreaming(#[source] DataStreamingError),
+    #[cfg(feature = "polars")]
+    #[error("Polars error: {0}")]
+    Polars(#[from] polars::error::PolarsError),
+}
+
+impl From<DataStreamingError> for PersistenceError {
+    fn from(e: DataStreamingError) -> Self {
+        match e {
+            DataStreamingError::ArrowError(e) => Self::Arrow(e),
+            DataStreamingError::IoError(e) => Self::Io(e),
+            DataStreamingError::ParquetError(e) => Self::Parquet(e),
+            DataStreamingError::EncodingError(e) => Self::Encoding(e),
+            DataStreamingError::UnsupportedType(name) => Self::UnsupportedType(name),
+            DataStreamingError::MixedDataTypes => Self::MixedDataTypes,
+            e => Self::DataStreaming(e),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+// -------------------------------------------------------------------------------------------------
+//  Copyright (C) 2015-2024 Nautech Systems Pty Ltd. All rights reserved.
+//  https://nautechsystems.io
+//
+//  Licensed under the GNU Lesser General Public License Version 3.0 (the "License");
+//  You may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at https://www.gnu.org/licenses/lgpl-3.0.en.html
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+// -------------------------------------------------------------------------------------------------
+
+use std::ffi::{c_char, c_void};
+
+use nautilus_core::ffi::{cvec::CVec, string::cstr_to_str};
+
+use crate::backend::session::{DataBackendSession, DataQueryResult};
+
+/// A callback receiving each chunk of a query result as a `CVec` of `Data`,
+/// with the opaque context pointer passed with it.
+pub type ChunkCallback = extern "C" fn(ctx: *mut c_void, chunk: CVec);
+
+/// Runs the registered queries on `table_name_ptr` and passes each chunk of
+/// their merged result to `callback` as it is produced, rather than the caller
+/// pulling the chunks. Returns the number of chunks passed.
+///
+/// The chunks hold at most the session chunk size of data, and are owned by
+/// the session: a chunk is only valid for the duration of the callback, and
+/// the callee must neither free it nor keep a pointer to it, copying any data
+/// it needs. Queries on other tables are left pending.
+///
+/// # Safety
+///
+/// - Assumes `session` is a valid pointer to a [`DataBackendSession`], which
+///   is not accessed elsewhere for the duration of the call.
+/// - Assumes `table_name_ptr` is a valid C string pointer.
+/// - Assumes `ctx` is valid for the uses `callback` makes of it.
+///
+/// # Panics
+///
+/// This function panics:
+/// - If `session` or `table_name_ptr` is null.
+/// - If no query is registered on the table.
+#[no_mangle]
+pub unsafe extern "C" fn query_with_callback(
+    session: *mut DataBackendSession,
+    table_name_ptr: *const c_char,
+    callback: ChunkCallback,
+    ctx: *mut c_void,
+) -> u64 {
+    assert!(!session.is_null(), "`session` was NULL");
+    let session = &mut *session;
+    let table_name = cstr_to_str(table_name_ptr);
+    let result = session
+        .get_table_query_result(table_name)
+        .expect("Failed to get query result");
+
+    let mut reader = DataQueryResult::new(result, session.chunk_size);
+    let mut num_chunks = 0;
+    while let Some(chunk) = reader.next_chunk() {
+        callback(ctx, chunk);
+        num_chunks += 1;
+    }
+    num_chunks
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+
+    use nautilus_core::ffi::cvec::CVecView;
+    use nautilus_model::data::{delta::OrderBookDelta, Data};
+    use rstest::rstest;
+
+    use super::*;
+
+    extern "C" fn call_closure(ctx: *mut c_void, chunk: CVec) {
+        let closure = unsafe { &mut *ctx.cast::<&mut dyn FnMut(CVec)>() };
+        closure(chunk);
+    }
+
+    #[rstest]
+    fn test_query_with_callback_counts_delta_chunks() {
+        let mut session = DataBackendSession::new(100).unwrap();
+        session
+            .add_file::<OrderBookDelta>(
+                "deltas",
+                "../../tests/test_data/nautilus/deltas.parquet",
+                None,
+            )
+            .unwrap();
+        let table_name = CString::new("deltas").unwrap();
+        let mut lengths = Vec::new();
+        let mut closure = |chunk: CVec| {
+            let data = unsafe { CVecView::<Data>::from_cvec(&chunk) };
+            assert!(data.iter().all(|item| matches!(item, Data::Delta(_))));
+            lengths.push(data.len());
+        };
+        let mut closure: &mut dyn FnMut(CVec) = &mut closure;
+
+        let num_chunks = unsafe {
+            query_with_callback(
+                &mut session,
+                table_name.as_ptr(),
+                call_closure,
+                std::ptr::addr_of_mut!(closure).cast(),
+            )
+        };
+
+        assert_eq!(num_chunks, 11);
+        assert_eq!(lengths.len(), 11);
+        assert!(lengths.iter().all(|&len| len <= 100));
+        assert_eq!(lengths.iter().sum::<usize>(), 1077);
+    }
+}
@@ -25,12 +25,19 @@
 //! for the main `nautilus_trader` Python package, or as part of a Rust only build.
 //!
 //! - `ffi`: Enables the C foreign function interface (FFI) from `cbindgen`
+//! - `http`: Enables reading parquet files served over HTTP(S) with range requests
+//! - `mmap`: Enables reading local parquet files through memory maps
+//! - `object_store`: Enables reading parquet files from S3 and GCS object storage URIs
 //! - `python`: Enables Python bindings from `pyo3`
 
 #![allow(deprecated)] // TODO: Temporary for pyo3 upgrade
 
 pub mod arrow;
 pub mod backend;
+pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod parquet;
 
 #[cfg(feature = "python")]
 pub mod python;
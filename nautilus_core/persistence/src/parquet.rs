@@ -0,0 +1,917 @@
+// -------------------------------------------------------------------------------------------------
+//  Copyright (C) 2015-2024 Nautech Systems Pty Ltd. All rights reserved.
+//  https://nautechsystems.io
+//
+//  Licensed under the GNU Lesser General Public License Version 3.0 (the "License");
+//  You may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at https://www.gnu.org/licenses/lgpl-3.0.en.html
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+// -------------------------------------------------------------------------------------------------
+
+//! Provides writing of Nautilus data to Apache Parquet files.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, File},
+    path::{Path, PathBuf},
+};
+
+use datafusion::{
+    arrow::{
+        array::{UInt32Array, UInt64Array},
+        compute::{concat_batches, max, take_record_batch},
+        datatypes::SchemaRef,
+        record_batch::RecordBatch,
+    },
+    parquet::{
+        arrow::{arrow_reader::ParquetRecordBatchReaderBuilder, ArrowWriter},
+        basic::{Compression, ZstdLevel},
+        file::properties::WriterProperties,
+    },
+};
+use nautilus_model::{
+    data::{is_monotonically_increasing_by_init, Data, GetTsInit},
+    instruments::any::InstrumentAny,
+};
+
+use crate::{
+    arrow::{encode_batch, DataStreamingError, EncodeToRecordBatch, EncodingError},
+    error::PersistenceError,
+};
+
+/// The compression codec used when writing parquet files.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ParquetCompression {
+    /// No compression.
+    None,
+    /// Snappy compression.
+    #[default]
+    Snappy,
+    /// Zstandard compression at the given level, which must be within 1 to 22.
+    Zstd(i32),
+    /// LZ4 compression (the raw LZ4 block format).
+    Lz4,
+}
+
+impl TryFrom<ParquetCompression> for Compression {
+    type Error = DataStreamingError;
+
+    fn try_from(value: ParquetCompression) -> Result<Self, Self::Error> {
+        match value {
+            ParquetCompression::None => Ok(Self::UNCOMPRESSED),
+            ParquetCompression::Snappy => Ok(Self::SNAPPY),
+            ParquetCompression::Zstd(level) => {
+                if !(1..=22).contains(&level) {
+                    return Err(DataStreamingError::InvalidCompressionLevel(level));
+                }
+                Ok(Self::ZSTD(ZstdLevel::try_new(level)?))
+            }
+            ParquetCompression::Lz4 => Ok(Self::LZ4_RAW),
+        }
+    }
+}
+
+/// Writes the given `data` to a parquet file at `file_path`.
+///
+/// The file is written with the Arrow schema of the data type, so it can be read
+/// back with a [`crate::backend::session::DataBackendSession`].
+///
+/// # Errors
+///
+/// This function returns an error:
+/// - If `data` is empty.
+/// - If `data` contains more than one data type.
+/// - If `data` is not monotonically increasing by `ts_init`.
+/// - If the `compression` level is invalid.
+/// - If the file cannot be created or written.
+pub fn write_data_to_parquet(
+    file_path: &str,
+    data: &[Data],
+    compression: ParquetCompression,
+) -> Result<(), PersistenceError> {
+    if !is_monotonically_increasing_by_init(data) {
+        return Err(DataStreamingError::NotMonotonic.into());
+    }
+
+    let batch = encode_batch(data)?;
+    write_batch_to_parquet(file_path, &batch, compression)
+}
+
+/// Writes the given instrument definitions to a parquet file at `file_path`.
+///
+/// The file is written with the Arrow schema of [`InstrumentAny`], so it can be
+/// read back with [`crate::backend::session::DataBackendSession::collect_instruments`].
+///
+/// # Errors
+///
+/// This function returns an error:
+/// - If `instruments` is empty.
+/// - If an instrument fails to serialize.
+/// - If the `compression` level is invalid.
+/// - If the file cannot be created or written.
+pub fn write_instruments_to_parquet(
+    file_path: &str,
+    instruments: &[InstrumentAny],
+    compression: ParquetCompression,
+) -> Result<(), PersistenceError> {
+    if instruments.is_empty() {
+        return Err(DataStreamingError::EmptyData.into());
+    }
+
+    let batch = InstrumentAny::encode_batch(&HashMap::new(), instruments)?;
+    write_batch_to_parquet(file_path, &batch, compression)
+}
+
+/// Writes the given record `batch` to a parquet file at `file_path`.
+///
+/// The schema metadata of the batch is stored in the file footer. The batch is
+/// written to a temporary file which then replaces any existing file, so a failed
+/// write leaves the existing file unchanged.
+///
+/// # Errors
+///
+/// This function returns an error:
+/// - If the `compression` level is invalid.
+/// - If the file cannot be created or written.
+pub fn write_batch_to_parquet(
+    file_path: &str,
+    batch: &RecordBatch,
+    compression: ParquetCompression,
+) -> Result<(), PersistenceError> {
+    let props = WriterProperties::builder()
+        .set_compression(compression.try_into()?)
+        .build();
+
+    write_replacing(file_path, |file| {
+        let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(props))?;
+        writer.write(batch)?;
+        writer.close()?;
+        Ok(())
+    })
+}
+
+/// Returns the path of the temporary file written in place of `file_path`.
+fn temp_path(file_path: &str) -> String {
+    format!("{file_path}.tmp")
+}
+
+/// Creates a temporary file with `write`, and then renames it to `file_path`, so
+/// an existing file is replaced rather than truncated and is left unchanged if
+/// the write fails.
+fn write_replacing(
+    file_path: &str,
+    write: impl FnOnce(File) -> Result<(), PersistenceError>,
+) -> Result<(), PersistenceError> {
+    let temp_path = temp_path(file_path);
+    let result = File::create(&temp_path)
+        .map_err(PersistenceError::from)
+        .and_then(write)
+        .and_then(|()| Ok(fs::rename(&temp_path, file_path)?));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Appends the given `data` to the existing parquet file at `file_path`.
+///
+/// Parquet files cannot be extended in place, as the footer follows the row
+/// groups, so the file is rewritten with its existing row groups followed by a
+/// new row group for `data`. The rewrite goes to a temporary file which then
+/// replaces the original, so a failed append leaves the file unchanged.
+///
+/// # Errors
+///
+/// This function returns an error:
+/// - If `data` is empty, contains more than one data type, or is not
+///   monotonically increasing by `ts_init`.
+/// - If the first `ts_init` of `data` is before the max `ts_init` of the file.
+/// - If the schema of `data`, including its metadata, differs from the file.
+/// - If the `compression` level is invalid.
+/// - If the file cannot be read or written.
+pub fn append_parquet(
+    file_path: &str,
+    data: &[Data],
+    compression: ParquetCompression,
+) -> Result<(), PersistenceError> {
+    if !is_monotonically_increasing_by_init(data) {
+        return Err(DataStreamingError::NotMonotonic.into());
+    }
+
+    let batch = encode_batch(data)?;
+    let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(file_path)?)?;
+    if builder.schema().as_ref() != batch.schema().as_ref() {
+        return Err(DataStreamingError::AppendSchemaMismatch.into());
+    }
+
+    // Read one batch per row group, so the existing row groups are kept as is
+    let row_group_sizes: Vec<usize> = builder
+        .metadata()
+        .row_groups()
+        .iter()
+        .map(|row_group| row_group.num_rows() as usize)
+        .collect();
+    let mut existing = Vec::with_capacity(row_group_sizes.len());
+    for (i, num_rows) in row_group_sizes.into_iter().enumerate() {
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(file_path)?)?
+            .with_row_groups(vec![i])
+            .with_batch_size(num_rows.max(1))
+            .build()?;
+        for batch in reader {
+            existing.push(batch?);
+        }
+    }
+
+    let max_ts_init = existing.iter().filter_map(max_ts_init).max();
+    let ts_init = data[0].ts_init().as_u64();
+    if let Some(max_ts_init) = max_ts_init.filter(|max_ts_init| ts_init < *max_ts_init) {
+        return Err(DataStreamingError::AppendNotMonotonic {
+            max_ts_init,
+            ts_init,
+        }
+        .into());
+    }
+
+    let props = WriterProperties::builder()
+        .set_compression(compression.try_into()?)
+        .build();
+    write_replacing(file_path, |file| {
+        let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(props))?;
+        for existing in &existing {
+            writer.write(existing)?;
+            writer.flush()?;
+        }
+        writer.write(&batch)?;
+        writer.close()?;
+        Ok(())
+    })
+}
+
+/// Writes data to a parquet file incrementally, as it arrives.
+///
+/// Each call to [`ParquetStreamWriter::write`] is flushed to the file as its own
+/// row group, so only the current batch is held in memory. The row groups go to
+/// a temporary file created on the first write, with the schema of its data type,
+/// which [`ParquetStreamWriter::finish`] completes with the footer and renames to
+/// the file path. A writer dropped without finishing removes the temporary file
+/// and leaves any existing file unchanged.
+pub struct ParquetStreamWriter {
+    file_path: String,
+    props: WriterProperties,
+    writer: Option<(ArrowWriter<File>, SchemaRef)>,
+    max_ts_init: Option<u64>,
+}
+
+impl ParquetStreamWriter {
+    /// Creates a new [`ParquetStreamWriter`] instance for the file at `file_path`.
+    ///
+    /// An existing file is replaced when the writer is finished.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the `compression` level is invalid.
+    pub fn new(file_path: &str, compression: ParquetCompression) -> Result<Self, PersistenceError> {
+        let props = WriterProperties::builder()
+            .set_compression(compression.try_into()?)
+            .build();
+        Ok(Self {
+            file_path: file_path.to_string(),
+            props,
+            writer: None,
+            max_ts_init: None,
+        })
+    }
+
+    /// Writes the given `data` to the file as a new row group. Empty `data` is
+    /// ignored.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error:
+    /// - If `data` contains more than one data type, or is not monotonically
+    ///   increasing by `ts_init`.
+    /// - If the first `ts_init` of `data` is before the max `ts_init` written.
+    /// - If the schema of `data`, including its metadata, differs from the data
+    ///   written before.
+    /// - If the file cannot be created or written.
+    pub fn write(&mut self, data: &[Data]) -> Result<(), PersistenceError> {
+        let (Some(first), Some(last)) = (data.first(), data.last()) else {
+            return Ok(());
+        };
+        if !is_monotonically_increasing_by_init(data) {
+            return Err(DataStreamingError::NotMonotonic.into());
+        }
+        let ts_init = first.ts_init().as_u64();
+        if let Some(max_ts_init) = self
+            .max_ts_init
+            .filter(|max_ts_init| ts_init < *max_ts_init)
+        {
+            return Err(DataStreamingError::AppendNotMonotonic {
+                max_ts_init,
+                ts_init,
+            }
+            .into());
+        }
+
+        let batch = encode_batch(data)?;
+        let writer = match &mut self.writer {
+            Some((writer, schema)) => {
+                if schema.as_ref() != batch.schema().as_ref() {
+                    return Err(DataStreamingError::AppendSchemaMismatch.into());
+                }
+                writer
+            }
+            None => {
+                let writer = ArrowWriter::try_new(
+                    File::create(temp_path(&self.file_path))?,
+                    batch.schema(),
+                    Some(self.props.clone()),
+                )?;
+                &mut self.writer.insert((writer, batch.schema())).0
+            }
+        };
+        writer.write(&batch)?;
+        writer.flush()?;
+        self.max_ts_init = Some(last.ts_init().as_u64());
+        Ok(())
+    }
+
+    /// Finishes the file by writing its footer, and replaces any existing file
+    /// with it.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error:
+    /// - If no data was written, in which case no file is created.
+    /// - If the file cannot be written.
+    pub fn finish(mut self) -> Result<(), PersistenceError> {
+        let (writer, _) = self.writer.take().ok_or(DataStreamingError::EmptyData)?;
+        let temp_path = temp_path(&self.file_path);
+        let result = writer
+            .close()
+            .map_err(PersistenceError::from)
+            .and_then(|_| Ok(fs::rename(&temp_path, &self.file_path)?));
+
+        if result.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+        result
+    }
+}
+
+impl Drop for ParquetStreamWriter {
+    fn drop(&mut self) {
+        if self.writer.take().is_some() {
+            let _ = fs::remove_file(temp_path(&self.file_path));
+        }
+    }
+}
+
+/// Returns the max `ts_init` of the record `batch`, if it has any rows.
+fn max_ts_init(batch: &RecordBatch) -> Option<u64> {
+    batch
+        .column_by_name("ts_init")
+        .and_then(|column| column.as_any().downcast_ref::<UInt64Array>())
+        .and_then(max)
+}
+
+/// Compacts the parquet files at `input_paths`, which must hold the same data
+/// type, into a single file at `output_path` ordered by `ts_init`.
+///
+/// The records of all the inputs are read into memory and stably sorted by
+/// `ts_init`, so records with equal timestamps keep the order of the inputs. The
+/// inputs are left in place, and `output_path` must not be one of them.
+///
+/// # Errors
+///
+/// This function returns an error:
+/// - If `input_paths` is empty.
+/// - If the inputs hold more than one data type, or differ in their metadata,
+///   such as their instrument.
+/// - If the `compression` level is invalid.
+/// - If a file cannot be read or written.
+pub fn compact_parquet(
+    input_paths: &[String],
+    output_path: &str,
+    compression: ParquetCompression,
+) -> Result<(), PersistenceError> {
+    let mut schema: Option<SchemaRef> = None;
+    let mut batches = Vec::new();
+    for input_path in input_paths {
+        let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(input_path)?)?;
+        match &schema {
+            None => schema = Some(builder.schema().clone()),
+            Some(schema) if schema.fields() != builder.schema().fields() => {
+                return Err(DataStreamingError::MixedDataTypes.into());
+            }
+            Some(schema) if schema.metadata() != builder.schema().metadata() => {
+                return Err(DataStreamingError::MetadataMismatch.into());
+            }
+            Some(_) => {}
+        }
+        for batch in builder.build()? {
+            batches.push(batch?);
+        }
+    }
+    let schema = schema.ok_or(DataStreamingError::EmptyData)?;
+
+    let batch = concat_batches(&schema, &batches)?;
+    let ts_init = batch
+        .column_by_name("ts_init")
+        .and_then(|column| column.as_any().downcast_ref::<UInt64Array>())
+        .ok_or(EncodingError::MissingColumn("ts_init", 0))?;
+    // Sort the row indices stably, as the sort kernels do not guarantee stability
+    let mut indices: Vec<u32> = (0..batch.num_rows() as u32).collect();
+    indices.sort_by_key(|&i| ts_init.value(i as usize));
+    let batch = take_record_batch(&batch, &UInt32Array::from(indices))?;
+    write_batch_to_parquet(output_path, &batch, compression)
+}
+
+/// The key by which [`write_parquet_partitioned`] splits data into files.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum PartitionBy {
+    /// A file per instrument, named after the instrument ID with any `/` removed,
+    /// as in `EURUSD.SIM.parquet`.
+    #[default]
+    InstrumentId,
+}
+
+/// Writes the given `data` to one parquet file per partition under `dir_path`,
+/// creating the directory if needed.
+///
+/// Each partition is stably sorted by `ts_init` before it is written, so `data`
+/// may interleave partitions in any order. Only partitions holding data create a
+/// file, and the paths of the written files are returned in the order of their
+/// partition keys. An existing file for a partition is overwritten.
+///
+/// # Errors
+///
+/// This function returns an error:
+/// - If a partition contains `OrderBookDeltas` or more than one data type.
+/// - If the `compression` level is invalid.
+/// - If the directory or a file cannot be created or written.
+pub fn write_parquet_partitioned(
+    dir_path: &str,
+    data: &[Data],
+    partition_by: PartitionBy,
+    compression: ParquetCompression,
+) -> Result<Vec<PathBuf>, PersistenceError> {
+    let mut partitions: BTreeMap<String, Vec<Data>> = BTreeMap::new();
+    for item in data {
+        let key = match partition_by {
+            PartitionBy::InstrumentId => item.instrument_id().to_string().replace('/', ""),
+        };
+        partitions.entry(key).or_default().push(item.clone());
+    }
+
+    fs::create_dir_all(dir_path)?;
+    let mut file_paths = Vec::with_capacity(partitions.len());
+    for (key, mut partition) in partitions {
+        partition.sort_by_key(GetTsInit::ts_init);
+        let batch = encode_batch(&partition)?;
+        let file_path = Path::new(dir_path).join(format!("{key}.parquet"));
+        write_batch_to_parquet(&file_path.to_string_lossy(), &batch, compression)?;
+        file_paths.push(file_path);
+    }
+    Ok(file_paths)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod tests {
+    use nautilus_model::{
+        data::{bar::Bar, quote::QuoteTick, trade::TradeTick},
+        identifiers::InstrumentId,
+        types::price::Price,
+    };
+    use rstest::rstest;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::{
+        arrow::DecodeDataFromRecordBatch,
+        backend::session::{DataBackendSession, NautilusDataTypeProvider},
+    };
+
+    fn read_file<T>(file_path: &str, table_name: &str) -> Vec<Data>
+    where
+        T: DecodeDataFromRecordBatch + NautilusDataTypeProvider + Into<Data>,
+    {
+        let mut session = DataBackendSession::new(10_000).unwrap();
+        session.add_file::<T>(table_name, file_path, None).unwrap();
+        session.get_query_result().collect()
+    }
+
+    #[rstest]
+    fn test_write_quotes_round_trip() {
+        let quotes = read_file::<QuoteTick>("../../tests/test_data/nautilus/quotes.parquet", "q");
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("quotes.parquet");
+        let file_path = file_path.to_str().unwrap();
+
+        write_data_to_parquet(file_path, &quotes, ParquetCompression::Snappy).unwrap();
+
+        assert_eq!(read_file::<QuoteTick>(file_path, "q"), quotes);
+    }
+
+    #[rstest]
+    fn test_write_trades_round_trip() {
+        let trades = read_file::<TradeTick>("../../tests/test_data/nautilus/trades.parquet", "t");
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("trades.parquet");
+        let file_path = file_path.to_str().unwrap();
+
+        write_data_to_parquet(file_path, &trades, ParquetCompression::None).unwrap();
+
+        assert_eq!(read_file::<TradeTick>(file_path, "t"), trades);
+    }
+
+    #[rstest]
+    fn test_write_bars_round_trip() {
+        let bars = read_file::<Bar>("../../tests/test_data/nautilus/bars.parquet", "b");
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("bars.parquet");
+        let file_path = file_path.to_str().unwrap();
+
+        write_data_to_parquet(file_path, &bars, ParquetCompression::Snappy).unwrap();
+
+        assert_eq!(read_file::<Bar>(file_path, "b"), bars);
+    }
+
+    #[rstest]
+    fn test_write_quotes_snappy_and_zstd() {
+        let quotes = read_file::<QuoteTick>("../../tests/test_data/nautilus/quotes.parquet", "q");
+        let temp_dir = TempDir::new().unwrap();
+        let snappy_path = temp_dir.path().join("quotes_snappy.parquet");
+        let zstd_path = temp_dir.path().join("quotes_zstd.parquet");
+        let snappy_path = snappy_path.to_str().unwrap();
+        let zstd_path = zstd_path.to_str().unwrap();
+
+        write_data_to_parquet(snappy_path, &quotes, ParquetCompression::Snappy).unwrap();
+        write_data_to_parquet(zstd_path, &quotes, ParquetCompression::Zstd(19)).unwrap();
+
+        assert_eq!(read_file::<QuoteTick>(snappy_path, "q"), quotes);
+        assert_eq!(read_file::<QuoteTick>(zstd_path, "q"), quotes);
+        let snappy_len = std::fs::metadata(snappy_path).unwrap().len();
+        let zstd_len = std::fs::metadata(zstd_path).unwrap().len();
+        assert_ne!(snappy_len, zstd_len);
+    }
+
+    #[rstest]
+    fn test_write_lz4_round_trip() {
+        let bars = read_file::<Bar>("../../tests/test_data/nautilus/bars.parquet", "b");
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("bars.parquet");
+        let file_path = file_path.to_str().unwrap();
+
+        write_data_to_parquet(file_path, &bars, ParquetCompression::Lz4).unwrap();
+
+        assert_eq!(read_file::<Bar>(file_path, "b"), bars);
+    }
+
+    #[rstest]
+    #[case(0)]
+    #[case(23)]
+    fn test_write_invalid_zstd_level_errors(#[case] level: i32) {
+        let bars = read_file::<Bar>("../../tests/test_data/nautilus/bars.parquet", "b");
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("bars.parquet");
+
+        let result = write_data_to_parquet(
+            file_path.to_str().unwrap(),
+            &bars,
+            ParquetCompression::Zstd(level),
+        );
+
+        assert!(matches!(
+            result,
+            Err(PersistenceError::DataStreaming(
+                DataStreamingError::InvalidCompressionLevel(l)
+            )) if l == level
+        ));
+    }
+
+    #[rstest]
+    fn test_write_mixed_data_types_errors() {
+        let mut data = read_file::<QuoteTick>("../../tests/test_data/nautilus/quotes.parquet", "q");
+        let trades = read_file::<TradeTick>("../../tests/test_data/nautilus/trades.parquet", "t");
+        data.truncate(1);
+        data.push(trades[0].clone());
+        data.sort_by_key(GetTsInit::ts_init);
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("mixed.parquet");
+
+        let result = write_data_to_parquet(
+            file_path.to_str().unwrap(),
+            &data,
+            ParquetCompression::Snappy,
+        );
+
+        assert!(matches!(result, Err(PersistenceError::MixedDataTypes)));
+    }
+
+    #[rstest]
+    fn test_write_two_instruments_errors() {
+        let mut data = read_file::<QuoteTick>("../../tests/test_data/nautilus/quotes.parquet", "q");
+        data.truncate(2);
+        if let Data::Quote(quote) = &mut data[1] {
+            quote.instrument_id = InstrumentId::from("GBP/USD.SIM");
+        }
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("two_instruments.parquet");
+
+        let result = write_data_to_parquet(
+            file_path.to_str().unwrap(),
+            &data,
+            ParquetCompression::Snappy,
+        );
+
+        assert!(matches!(
+            result,
+            Err(PersistenceError::DataStreaming(
+                DataStreamingError::MixedMetadata("instrument ID")
+            ))
+        ));
+        assert!(!file_path.exists());
+    }
+
+    #[rstest]
+    fn test_write_mixed_precisions_errors() {
+        let mut data = read_file::<QuoteTick>("../../tests/test_data/nautilus/quotes.parquet", "q");
+        data.truncate(2);
+        if let Data::Quote(quote) = &mut data[1] {
+            quote.bid_price = Price::new(quote.bid_price.as_f64(), quote.bid_price.precision + 1);
+        }
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("mixed_precisions.parquet");
+
+        let result = write_data_to_parquet(
+            file_path.to_str().unwrap(),
+            &data,
+            ParquetCompression::Snappy,
+        );
+
+        assert!(matches!(
+            result,
+            Err(PersistenceError::DataStreaming(
+                DataStreamingError::MixedMetadata("precision")
+            ))
+        ));
+    }
+
+    #[rstest]
+    fn test_write_non_monotonic_errors() {
+        let mut quotes =
+            read_file::<QuoteTick>("../../tests/test_data/nautilus/quotes.parquet", "q");
+        quotes.reverse();
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("quotes.parquet");
+
+        let result = write_data_to_parquet(
+            file_path.to_str().unwrap(),
+            &quotes,
+            ParquetCompression::Snappy,
+        );
+
+        assert!(matches!(
+            result,
+            Err(PersistenceError::DataStreaming(
+                DataStreamingError::NotMonotonic
+            ))
+        ));
+        assert!(!file_path.exists());
+    }
+
+    #[rstest]
+    fn test_write_empty_data_errors() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("empty.parquet");
+
+        let result =
+            write_data_to_parquet(file_path.to_str().unwrap(), &[], ParquetCompression::Snappy);
+
+        assert!(matches!(
+            result,
+            Err(PersistenceError::DataStreaming(
+                DataStreamingError::EmptyData
+            ))
+        ));
+    }
+
+    #[rstest]
+    fn test_append_quotes_round_trip() {
+        let quotes = read_file::<QuoteTick>("../../tests/test_data/nautilus/quotes.parquet", "q");
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("quotes.parquet");
+        let file_path = file_path.to_str().unwrap();
+
+        write_data_to_parquet(file_path, &quotes[..50], ParquetCompression::Snappy).unwrap();
+        append_parquet(file_path, &quotes[50..100], ParquetCompression::Snappy).unwrap();
+
+        assert_eq!(read_file::<QuoteTick>(file_path, "q"), quotes[..100]);
+        let metadata = DataBackendSession::file_metadata(file_path).unwrap();
+        assert_eq!(metadata.row_groups.len(), 2);
+        assert_eq!(metadata.row_groups[0].num_rows, 50);
+        assert!(!temp_dir.path().join("quotes.parquet.tmp").exists());
+    }
+
+    #[rstest]
+    fn test_stream_writer_three_batches() {
+        let quotes = read_file::<QuoteTick>("../../tests/test_data/nautilus/quotes.parquet", "q");
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("quotes.parquet");
+        let file_path = file_path.to_str().unwrap();
+
+        let mut writer = ParquetStreamWriter::new(file_path, ParquetCompression::Snappy).unwrap();
+        writer.write(&quotes[..100]).unwrap();
+        writer.write(&[]).unwrap();
+        writer.write(&quotes[100..250]).unwrap();
+        writer.write(&quotes[250..300]).unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!(read_file::<QuoteTick>(file_path, "q"), quotes[..300]);
+        let metadata = DataBackendSession::file_metadata(file_path).unwrap();
+        let num_rows: Vec<_> = metadata.row_groups.iter().map(|rg| rg.num_rows).collect();
+        assert_eq!(num_rows, [100, 150, 50]);
+    }
+
+    #[rstest]
+    fn test_stream_writer_dropped_keeps_existing_file() {
+        let quotes = read_file::<QuoteTick>("../../tests/test_data/nautilus/quotes.parquet", "q");
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("quotes.parquet");
+        let file_path = file_path.to_str().unwrap();
+        write_data_to_parquet(file_path, &quotes[..50], ParquetCompression::Snappy).unwrap();
+
+        let mut writer = ParquetStreamWriter::new(file_path, ParquetCompression::Snappy).unwrap();
+        writer.write(&quotes[50..100]).unwrap();
+        drop(writer);
+
+        assert_eq!(read_file::<QuoteTick>(file_path, "q"), quotes[..50]);
+        assert!(!temp_dir.path().join("quotes.parquet.tmp").exists());
+    }
+
+    #[rstest]
+    fn test_stream_writer_before_max_ts_init_errors() {
+        let quotes = read_file::<QuoteTick>("../../tests/test_data/nautilus/quotes.parquet", "q");
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("quotes.parquet");
+        let file_path = file_path.to_str().unwrap();
+
+        let mut writer = ParquetStreamWriter::new(file_path, ParquetCompression::Snappy).unwrap();
+        writer.write(&quotes[50..100]).unwrap();
+        let result = writer.write(&quotes[..50]);
+        writer.finish().unwrap();
+
+        assert!(matches!(
+            result,
+            Err(PersistenceError::DataStreaming(
+                DataStreamingError::AppendNotMonotonic { max_ts_init, ts_init }
+            )) if max_ts_init == quotes[99].ts_init().as_u64()
+                    && ts_init == quotes[0].ts_init().as_u64()
+        ));
+        assert_eq!(read_file::<QuoteTick>(file_path, "q"), quotes[50..100]);
+    }
+
+    #[rstest]
+    fn test_append_before_max_ts_init_errors() {
+        let quotes = read_file::<QuoteTick>("../../tests/test_data/nautilus/quotes.parquet", "q");
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("quotes.parquet");
+        let file_path = file_path.to_str().unwrap();
+        write_data_to_parquet(file_path, &quotes[50..100], ParquetCompression::Snappy).unwrap();
+
+        let result = append_parquet(file_path, &quotes[..50], ParquetCompression::Snappy);
+
+        assert!(matches!(
+            result,
+            Err(PersistenceError::DataStreaming(
+                DataStreamingError::AppendNotMonotonic { max_ts_init, ts_init }
+            )) if max_ts_init == quotes[99].ts_init().as_u64()
+                    && ts_init == quotes[0].ts_init().as_u64()
+        ));
+        assert_eq!(read_file::<QuoteTick>(file_path, "q"), quotes[50..100]);
+    }
+
+    #[rstest]
+    fn test_append_different_data_type_errors() {
+        let quotes = read_file::<QuoteTick>("../../tests/test_data/nautilus/quotes.parquet", "q");
+        let trades = read_file::<TradeTick>("../../tests/test_data/nautilus/trades.parquet", "t");
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("quotes.parquet");
+        let file_path = file_path.to_str().unwrap();
+        write_data_to_parquet(file_path, &quotes[..1], ParquetCompression::Snappy).unwrap();
+
+        let result = append_parquet(file_path, &trades, ParquetCompression::Snappy);
+
+        assert!(matches!(
+            result,
+            Err(PersistenceError::DataStreaming(
+                DataStreamingError::AppendSchemaMismatch
+            ))
+        ));
+    }
+
+    #[rstest]
+    fn test_compact_quotes() {
+        let quotes = read_file::<QuoteTick>("../../tests/test_data/nautilus/quotes.parquet", "q");
+        // Split the quotes across two files so that their ranges fully overlap
+        let even: Vec<Data> = quotes.iter().step_by(2).cloned().collect();
+        let odd: Vec<Data> = quotes.iter().skip(1).step_by(2).cloned().collect();
+        let temp_dir = TempDir::new().unwrap();
+        let mut input_paths = Vec::new();
+        for (name, data) in [("odd.parquet", &odd), ("even.parquet", &even)] {
+            let file_path = temp_dir.path().join(name);
+            let file_path = file_path.to_str().unwrap().to_string();
+            write_data_to_parquet(&file_path, data, ParquetCompression::Snappy).unwrap();
+            input_paths.push(file_path);
+        }
+        let output_path = temp_dir.path().join("quotes.parquet");
+        let output_path = output_path.to_str().unwrap();
+
+        compact_parquet(&input_paths, output_path, ParquetCompression::Snappy).unwrap();
+
+        let compacted = read_file::<QuoteTick>(output_path, "q");
+        assert_eq!(compacted.len(), quotes.len());
+        assert!(is_monotonically_increasing_by_init(&compacted));
+        assert_eq!(
+            compacted.iter().map(GetTsInit::ts_init).collect::<Vec<_>>(),
+            quotes.iter().map(GetTsInit::ts_init).collect::<Vec<_>>()
+        );
+    }
+
+    #[rstest]
+    fn test_compact_mixed_data_types_errors() {
+        let quotes = read_file::<QuoteTick>("../../tests/test_data/nautilus/quotes.parquet", "q");
+        let trades = read_file::<TradeTick>("../../tests/test_data/nautilus/trades.parquet", "t");
+        let temp_dir = TempDir::new().unwrap();
+        let quotes_path = temp_dir.path().join("quotes.parquet");
+        let trades_path = temp_dir.path().join("trades.parquet");
+        let quotes_path = quotes_path.to_str().unwrap().to_string();
+        let trades_path = trades_path.to_str().unwrap().to_string();
+        write_data_to_parquet(&quotes_path, &quotes, ParquetCompression::Snappy).unwrap();
+        write_data_to_parquet(&trades_path, &trades, ParquetCompression::Snappy).unwrap();
+        let output_path = temp_dir.path().join("compacted.parquet");
+
+        let result = compact_parquet(
+            &[quotes_path, trades_path],
+            output_path.to_str().unwrap(),
+            ParquetCompression::Snappy,
+        );
+
+        assert!(matches!(result, Err(PersistenceError::MixedDataTypes)));
+        assert!(!output_path.exists());
+    }
+
+    #[rstest]
+    fn test_write_partitioned_by_instrument() {
+        let quotes = read_file::<QuoteTick>("../../tests/test_data/nautilus/quotes.parquet", "q");
+        let gbpusd = InstrumentId::from("GBP/USD.SIM");
+        // Interleave the two instruments, each in ts_init order
+        let data: Vec<Data> = quotes[..100]
+            .iter()
+            .enumerate()
+            .map(|(i, quote)| match quote {
+                Data::Quote(quote) if i % 2 == 1 => Data::Quote(QuoteTick {
+                    instrument_id: gbpusd,
+                    ..*quote
+                }),
+                _ => quote.clone(),
+            })
+            .collect();
+        let temp_dir = TempDir::new().unwrap();
+        let dir_path = temp_dir.path().join("quotes");
+
+        let file_paths = write_parquet_partitioned(
+            dir_path.to_str().unwrap(),
+            &data,
+            PartitionBy::InstrumentId,
+            ParquetCompression::Snappy,
+        )
+        .unwrap();
+
+        assert_eq!(
+            file_paths,
+            [
+                dir_path.join("EURUSD.SIM.parquet"),
+                dir_path.join("GBPUSD.SIM.parquet")
+            ]
+        );
+        assert_eq!(fs::read_dir(&dir_path).unwrap().count(), 2);
+        for (i, file_path) in file_paths.iter().enumerate() {
+            let expected: Vec<Data> = data.iter().skip(i).step_by(2).cloned().collect();
+            assert_eq!(
+                read_file::<QuoteTick>(file_path.to_str().unwrap(), "q"),
+                expected
+            );
+        }
+    }
+}
@@ -13,32 +13,38 @@
 //  limitations under the License.
 // -------------------------------------------------------------------------------------------------
 
-use nautilus_core::{ffi::cvec::CVec, python::to_pyruntime_err};
-use nautilus_model::data::{
-    bar::Bar, delta::OrderBookDelta, depth::OrderBookDepth10, quote::QuoteTick, trade::TradeTick,
+use std::ffi::c_void;
+
+use datafusion::arrow::pyarrow::ToPyArrow;
+use nautilus_core::{
+    ffi::cvec::CVec,
+    python::{to_pyruntime_err, to_pyvalue_err},
+};
+use nautilus_model::{
+    data::{
+        bar::Bar, delta::OrderBookDelta, depth::OrderBookDepth10, quote::QuoteTick,
+        trade::TradeTick, Data,
+    },
+    python::instruments::instrument_any_to_pyobject,
+};
+use pyo3::{
+    prelude::*,
+    types::{PyCapsule, PyList},
+};
+
+pub use crate::backend::session::NautilusDataType;
+use crate::{
+    arrow::encode_batch,
+    backend::session::{DataBackendSession, DataQueryResult, DEFAULT_CHUNK_SIZE},
+    error::PersistenceError,
 };
-use pyo3::{prelude::*, types::PyCapsule};
-
-use crate::backend::session::{DataBackendSession, DataQueryResult};
-
-#[repr(C)]
-#[pyclass]
-#[derive(Clone, Copy, Debug)]
-pub enum NautilusDataType {
-    // Custom = 0,  # First slot reserved for custom data
-    OrderBookDelta = 1,
-    OrderBookDepth10 = 2,
-    QuoteTick = 3,
-    TradeTick = 4,
-    Bar = 5,
-}
 
 #[pymethods]
 impl DataBackendSession {
     #[new]
-    #[pyo3(signature=(chunk_size=5_000))]
-    fn new_session(chunk_size: usize) -> Self {
-        Self::new(chunk_size)
+    #[pyo3(signature=(chunk_size=DEFAULT_CHUNK_SIZE))]
+    fn new_session(chunk_size: usize) -> PyResult<Self> {
+        Self::new(chunk_size).map_err(to_pyvalue_err)
     }
 
     /// Query a file for its records. the caller must specify `T` to indicate
@@ -49,43 +55,99 @@ impl DataBackendSession {
     /// file_path: Path to file
     /// sql_query: A custom sql query to retrieve records from file. If no query is provided a default
     /// query "SELECT * FROM <table_name>" is run.
+    /// chunk_size: Overrides the session chunk size when decoding this file only. If no chunk size
+    /// is provided the session chunk size is used.
+    ///
+    /// A file of `InstrumentAny` is collected by `to_instruments` rather than merged into the
+    /// query result, and ignores `chunk_size`.
     ///
     /// # Safety
     ///
     /// The file data must be ordered by the ts_init in ascending order for this
     /// to work correctly.
     #[pyo3(name = "add_file")]
+    #[pyo3(signature = (data_type, table_name, file_path, sql_query=None, chunk_size=None))]
     fn add_file_py(
         mut slf: PyRefMut<'_, Self>,
         data_type: NautilusDataType,
         table_name: &str,
         file_path: &str,
         sql_query: Option<&str>,
+        chunk_size: Option<usize>,
     ) -> PyResult<()> {
         let _guard = slf.runtime.enter();
 
         match data_type {
             NautilusDataType::OrderBookDelta => slf
-                .add_file::<OrderBookDelta>(table_name, file_path, sql_query)
+                .add_file_with_chunk_size::<OrderBookDelta>(
+                    table_name, file_path, sql_query, chunk_size,
+                )
                 .map_err(to_pyruntime_err),
             NautilusDataType::OrderBookDepth10 => slf
-                .add_file::<OrderBookDepth10>(table_name, file_path, sql_query)
+                .add_file_with_chunk_size::<OrderBookDepth10>(
+                    table_name, file_path, sql_query, chunk_size,
+                )
                 .map_err(to_pyruntime_err),
             NautilusDataType::QuoteTick => slf
-                .add_file::<QuoteTick>(table_name, file_path, sql_query)
+                .add_file_with_chunk_size::<QuoteTick>(table_name, file_path, sql_query, chunk_size)
                 .map_err(to_pyruntime_err),
             NautilusDataType::TradeTick => slf
-                .add_file::<TradeTick>(table_name, file_path, sql_query)
+                .add_file_with_chunk_size::<TradeTick>(table_name, file_path, sql_query, chunk_size)
                 .map_err(to_pyruntime_err),
             NautilusDataType::Bar => slf
-                .add_file::<Bar>(table_name, file_path, sql_query)
+                .add_file_with_chunk_size::<Bar>(table_name, file_path, sql_query, chunk_size)
+                .map_err(to_pyruntime_err),
+            NautilusDataType::InstrumentAny => slf
+                .add_instrument_file(table_name, file_path, sql_query)
                 .map_err(to_pyruntime_err),
         }
     }
 
-    fn to_query_result(mut slf: PyRefMut<'_, Self>) -> DataQueryResult {
-        let query_result = slf.get_query_result();
-        DataQueryResult::new(query_result, slf.chunk_size)
+    /// Consumes the registered queries and returns the merged result.
+    ///
+    /// raw: If each chunk is returned as a `PyCapsule` wrapping a `CVec` of data, otherwise
+    /// each chunk is returned as a list of Nautilus Python objects.
+    #[pyo3(signature = (raw=true))]
+    fn to_query_result(mut slf: PyRefMut<'_, Self>, raw: bool) -> DataQueryResult {
+        let mut result = slf.get_chunked_query_result();
+        result.raw = raw;
+        result
+    }
+
+    /// Consumes the registered instrument queries and returns a list of their Nautilus Python
+    /// instrument objects.
+    fn to_instruments(mut slf: PyRefMut<'_, Self>, py: Python<'_>) -> PyResult<Vec<PyObject>> {
+        slf.get_instruments()
+            .map_err(to_pyruntime_err)?
+            .into_iter()
+            .map(|instrument| instrument_any_to_pyobject(py, instrument))
+            .collect()
+    }
+
+    /// Consumes the registered queries and returns the merged result as a pandas
+    /// `DataFrame`.
+    ///
+    /// The merged data is encoded back into a single Arrow record batch, which
+    /// pyarrow then converts, so the whole result is held in memory and copied
+    /// rather than shared with the scanned batches.
+    ///
+    /// The columns follow the Arrow schema of the data type, as stored in the
+    /// catalog, so prices and sizes are fixed-point integers. An empty result
+    /// gives an empty `DataFrame`.
+    ///
+    /// Raises a `ValueError` if the queries hold more than one data type.
+    fn to_pandas(mut slf: PyRefMut<'_, Self>, py: Python<'_>) -> PyResult<PyObject> {
+        let data = slf
+            .get_query_result()
+            .flatten_results()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(to_pyruntime_err)?;
+        if data.is_empty() {
+            return Ok(py.import_bound("pandas")?.call_method0("DataFrame")?.into());
+        }
+
+        let batch = encode_batch(&data).map_err(|e| to_pyvalue_err(PersistenceError::from(e)))?;
+        batch.to_pyarrow(py)?.call_method0(py, "to_pandas")
     }
 }
 
@@ -97,16 +159,89 @@ impl DataQueryResult {
     }
 
     /// Each iteration returns a chunk of values read from the parquet file.
+    ///
+    /// A raw chunk is a `PyCapsule` which owns its `CVec`, and frees it when the
+    /// capsule is garbage collected. The data must not be accessed through the
+    /// `CVec` once the capsule is released.
     fn __next__(mut slf: PyRefMut<'_, Self>) -> PyResult<Option<PyObject>> {
+        if !slf.raw {
+            return Ok(slf.next().map(|chunk| {
+                Python::with_gil(|py| {
+                    let objects: Vec<PyObject> = chunk
+                        .into_iter()
+                        .map(|data| data_to_pyobject(py, data))
+                        .collect();
+                    PyList::new_bound(py, objects).into_py(py)
+                })
+            }));
+        }
+
         match slf.next() {
-            Some(acc) if !acc.is_empty() => {
-                let cvec = slf.set_chunk(acc);
-                Python::with_gil(|py| match PyCapsule::new_bound::<CVec>(py, cvec, None) {
+            Some(chunk) => Python::with_gil(|py| {
+                let cvec: CVec = chunk.into();
+                match PyCapsule::new_bound_with_destructor::<CVec, _>(py, cvec, None, drop_cvec) {
                     Ok(capsule) => Ok(Some(capsule.into_py(py))),
                     Err(e) => Err(to_pyruntime_err(e)),
-                })
-            }
-            _ => Ok(None),
+                }
+            }),
+            None => Ok(None),
         }
     }
+
+    /// Returns a list of the Nautilus Python objects of all remaining chunks,
+    /// consuming the reader.
+    ///
+    /// The whole result is held in memory at once, both as decoded data and as
+    /// Python objects, so iterating the chunks is preferred for large queries.
+    fn to_object_list(mut slf: PyRefMut<'_, Self>, py: Python<'_>) -> PyObject {
+        let objects: Vec<PyObject> = (&mut *slf)
+            .flatten()
+            .map(|data| data_to_pyobject(py, data))
+            .collect();
+        PyList::new_bound(py, objects).into_py(py)
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    #[pyo3(signature = (_exc_type=None, _exc_value=None, _traceback=None))]
+    fn __exit__(
+        &mut self,
+        _exc_type: Option<PyObject>,
+        _exc_value: Option<PyObject>,
+        _traceback: Option<PyObject>,
+    ) {
+        self.clear();
+    }
+
+    /// Releases the pending queries and any chunk held by the reader, after which
+    /// no more chunks are returned.
+    ///
+    /// Chunks already returned are owned by their capsules and stay valid.
+    #[pyo3(name = "drop")]
+    fn py_drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// Frees the chunk of data owned by a `PyCapsule`, called by its destructor.
+fn drop_cvec(cvec: CVec, _context: *mut c_void) {
+    let CVec { ptr, len, cap } = cvec;
+    if !ptr.is_null() {
+        let data: Vec<Data> = unsafe { Vec::from_raw_parts(ptr.cast::<Data>(), len, cap) };
+        drop(data);
+    }
+}
+
+/// Converts the given `data` into its Nautilus Python object.
+fn data_to_pyobject(py: Python<'_>, data: Data) -> PyObject {
+    match data {
+        Data::Delta(delta) => delta.into_py(py),
+        Data::Deltas(deltas) => (*deltas).clone().into_py(py),
+        Data::Depth10(depth) => depth.into_py(py),
+        Data::Quote(quote) => quote.into_py(py),
+        Data::Trade(trade) => trade.into_py(py),
+        Data::Bar(bar) => bar.into_py(py),
+    }
 }
@@ -15,19 +15,89 @@
 
 #![allow(deprecated)] // TODO: Temporary for pyo3 upgrade
 
-use nautilus_core::ffi::cvec::CVec;
-use nautilus_model::data::{
-    bar::Bar, delta::OrderBookDelta, is_monotonically_increasing_by_init, quote::QuoteTick,
-    trade::TradeTick, Data,
+#[cfg(any(feature = "http", feature = "object_store"))]
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
 };
+
+#[cfg(feature = "object_store")]
+use async_trait::async_trait;
+#[cfg(any(feature = "mmap", feature = "object_store"))]
+use datafusion::execution::object_store::ObjectStoreUrl;
+use datafusion::{
+    arrow::{
+        array::{
+            ArrayRef, Int64Array, StringArray, TimestampMillisecondArray, UInt64Array, UInt8Array,
+        },
+        datatypes::{DataType, Field, Schema, TimeUnit},
+        ipc::writer::FileWriter,
+        record_batch::RecordBatch,
+    },
+    parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder,
+    prelude::lit,
+};
+#[cfg(feature = "object_store")]
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use nautilus_core::{ffi::cvec::CVec, nanos::UnixNanos};
+use nautilus_model::{
+    data::{
+        bar::Bar,
+        delta::OrderBookDelta,
+        depth::OrderBookDepth10,
+        is_monotonically_increasing_by_init,
+        quote::QuoteTick,
+        stubs::{quote_tick_audusd_sim, stub_depth10, trade_tick_audusd_sim},
+        time_range,
+        trade::TradeTick,
+        Data, GetTsInit,
+    },
+    enums::{BarAggregation, BookAction},
+    identifiers::InstrumentId,
+    instruments::{
+        any::InstrumentAny,
+        stubs::{crypto_perpetual_ethusdt, currency_pair_btcusdt, equity_aapl},
+    },
+    types::{price::Price, quantity::Quantity},
+};
+#[cfg(feature = "object_store")]
+use nautilus_persistence::backend::retry::{RetryPolicy, RetryingObjectStore};
 use nautilus_persistence::{
-    backend::session::{DataBackendSession, DataQueryResult, QueryResult},
+    arrow::{
+        bar_schema, decode_batch, delta_schema, encode_batch, quote_schema, trade_schema,
+        ArrowSchemaProvider, EncodeToRecordBatch,
+    },
+    backend::{
+        filter::Filter,
+        json::JSON_TYPE_TAG,
+        session::{
+            compare_catalogs, find_gaps, group_by_instrument, split_at_ts, validate_catalog,
+            ChunkMode, CsvSchema, DataBackendSession, DataQueryResult, DataQueryStream, NullPolicy,
+            PrecisionPolicy, QueryResult, SortKey, ValidationReport,
+        },
+    },
+    error::PersistenceError,
+    parquet::{
+        write_batch_to_parquet, write_data_to_parquet, write_instruments_to_parquet,
+        ParquetCompression,
+    },
     python::backend::session::NautilusDataType,
 };
+#[cfg(feature = "object_store")]
+use object_store::{
+    local::LocalFileSystem, path::Path as ObjectPath, GetOptions, GetResult, ListResult,
+    MultipartUpload, ObjectMeta, ObjectStore, PutMultipartOpts, PutOptions, PutPayload, PutResult,
+};
 #[cfg(target_os = "linux")]
 use procfs::{self, process::Process};
-use pyo3::{types::PyCapsule, IntoPy, Py, PyAny, Python};
+use pyo3::{exceptions::PyValueError, types::PyCapsule, IntoPy, Py, PyAny, Python};
 use rstest::rstest;
+use tempfile::TempDir;
+use tokio_util::sync::CancellationToken;
 
 /// Memory leak test
 ///
@@ -54,12 +124,11 @@ fn mem_leak_test<T>(setup: impl FnOnce() -> T, run: impl Fn(&T), threshold: f64,
 
     let after = me.stat().unwrap().rss * page_size / 1024 - setup_mem;
 
-    if !(after.abs_diff(before) as f64 / (before as f64) < threshold) {
-        println!("Memory leak detected after {iter} iterations");
-        println!("Memory before runs (in KB): {before}");
-        println!("Memory after runs (in KB): {after}");
-        assert!(false);
-    }
+    let ratio = after.abs_diff(before) as f64 / (before as f64);
+    assert!(
+        ratio < threshold,
+        "Memory leak detected after {iter} iterations: before {before} KB, after {after} KB"
+    );
 }
 
 #[cfg(target_os = "linux")]
@@ -70,7 +139,7 @@ fn catalog_query_mem_leak_test() {
         |_args| {
             let file_path = "../../tests/test_data/nautilus/quotes.parquet";
             let expected_length = 9500;
-            let catalog = DataBackendSession::new(1_000_000);
+            let catalog = DataBackendSession::new(1_000_000).unwrap();
             Python::with_gil(|py| {
                 let pycatalog: Py<PyAny> = catalog.into_py(py);
                 pycatalog
@@ -108,7 +177,7 @@ fn catalog_query_mem_leak_test() {
 fn test_quote_tick_cvec_interface() {
     let file_path = "../../tests/test_data/nautilus/quotes.parquet";
     let expected_length = 9500;
-    let mut catalog = DataBackendSession::new(1000);
+    let mut catalog = DataBackendSession::new(1000).unwrap();
     catalog
         .add_file::<QuoteTick>("quote_005", file_path, None)
         .unwrap();
@@ -140,7 +209,7 @@ fn test_quote_tick_python_control_flow() {
 
     let file_path = "../../tests/test_data/nautilus/quotes.parquet";
     let expected_length = 9500;
-    let catalog = DataBackendSession::new(1_000_000);
+    let catalog = DataBackendSession::new(1_000_000).unwrap();
     Python::with_gil(|py| {
         let pycatalog: Py<PyAny> = catalog.into_py(py);
         pycatalog
@@ -169,12 +238,73 @@ fn test_quote_tick_python_control_flow() {
     });
 }
 
+#[rstest]
+fn test_quote_tick_python_capsule_ownership() {
+    pyo3::prepare_freethreaded_python();
+
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let catalog = DataBackendSession::new(1_000).unwrap();
+    Python::with_gil(|py| {
+        let pycatalog: Py<PyAny> = catalog.into_py(py);
+        pycatalog
+            .call_method1(
+                py,
+                "add_file",
+                (NautilusDataType::QuoteTick, "quotes", file_path),
+            )
+            .unwrap();
+        let result = pycatalog.call_method0(py, "to_query_result").unwrap();
+        let mut chunks = Vec::new();
+        while let Ok(chunk) = result.call_method0(py, "__next__") {
+            chunks.push(chunk);
+        }
+
+        // Each capsule owns its chunk, so earlier chunks outlive later iterations
+        let ticks: Vec<Data> = chunks
+            .iter()
+            .flat_map(|chunk| {
+                let capsule: &PyCapsule = chunk.downcast(py).unwrap();
+                let cvec: &CVec = unsafe { &*(capsule.pointer() as *const CVec) };
+                unsafe { std::slice::from_raw_parts(cvec.ptr as *const Data, cvec.len) }.to_vec()
+            })
+            .collect();
+
+        assert_eq!(chunks.len(), 10);
+        assert_eq!(ticks.len(), 9_500);
+        assert!(is_monotonically_increasing_by_init(&ticks));
+    });
+}
+
+#[rstest]
+fn test_quote_tick_python_context_manager() {
+    pyo3::prepare_freethreaded_python();
+
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let catalog = DataBackendSession::new(1_000).unwrap();
+    Python::with_gil(|py| {
+        let pycatalog: Py<PyAny> = catalog.into_py(py);
+        pycatalog
+            .call_method1(
+                py,
+                "add_file",
+                (NautilusDataType::QuoteTick, "quotes", file_path),
+            )
+            .unwrap();
+        let result = pycatalog.call_method0(py, "to_query_result").unwrap();
+        let reader = result.call_method0(py, "__enter__").unwrap();
+        assert!(reader.call_method0(py, "__next__").is_ok());
+        result.call_method0(py, "__exit__").unwrap();
+
+        assert!(result.call_method0(py, "__next__").is_err());
+    });
+}
+
 #[ignore] // TODO: Investigate why this is suddenly failing the monotonically increasing assert?
 #[rstest]
 fn test_order_book_delta_query() {
     let expected_length = 1077;
     let file_path = "../../tests/test_data/nautilus/deltas.parquet";
-    let mut catalog = DataBackendSession::new(1_000);
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
     catalog
         .add_file::<OrderBookDelta>(
             "delta_001",
@@ -194,7 +324,7 @@ fn test_order_book_delta_query_py() {
     pyo3::prepare_freethreaded_python();
 
     let file_path = "../../tests/test_data/nautilus/deltas.parquet";
-    let catalog = DataBackendSession::new(2_000);
+    let catalog = DataBackendSession::new(2_000).unwrap();
     Python::with_gil(|py| {
         let pycatalog: Py<PyAny> = catalog.into_py(py);
         pycatalog
@@ -216,11 +346,176 @@ fn test_order_book_delta_query_py() {
     });
 }
 
+#[rstest]
+fn test_order_book_delta_query_py_objects() {
+    pyo3::prepare_freethreaded_python();
+
+    let file_path = "../../tests/test_data/nautilus/deltas.parquet";
+    let catalog = DataBackendSession::new(2_000).unwrap();
+    Python::with_gil(|py| {
+        let pycatalog: Py<PyAny> = catalog.into_py(py);
+        pycatalog
+            .call_method1(
+                py,
+                "add_file",
+                (
+                    NautilusDataType::OrderBookDelta,
+                    "order_book_deltas",
+                    file_path,
+                ),
+            )
+            .unwrap();
+        let result = pycatalog
+            .call_method1(py, "to_query_result", (false,))
+            .unwrap();
+        let chunk = result.call_method0(py, "__next__").unwrap();
+        let deltas: Vec<OrderBookDelta> = chunk.extract(py).unwrap();
+
+        assert_eq!(deltas.len(), 1077);
+        assert_eq!(
+            deltas[0].instrument_id.to_string(),
+            "1.166564490-60424-0.0.BETFAIR"
+        );
+        assert!(result.call_method0(py, "__next__").is_err());
+    });
+}
+
+#[rstest]
+fn test_order_book_delta_query_py_object_list() {
+    pyo3::prepare_freethreaded_python();
+
+    let file_path = "../../tests/test_data/nautilus/deltas.parquet";
+    let catalog = DataBackendSession::new(500).unwrap();
+    Python::with_gil(|py| {
+        let pycatalog: Py<PyAny> = catalog.into_py(py);
+        pycatalog
+            .call_method1(
+                py,
+                "add_file",
+                (
+                    NautilusDataType::OrderBookDelta,
+                    "order_book_deltas",
+                    file_path,
+                ),
+            )
+            .unwrap();
+        let result = pycatalog
+            .call_method1(py, "to_query_result", (false,))
+            .unwrap();
+        let objects = result.call_method0(py, "to_object_list").unwrap();
+        let deltas: Vec<OrderBookDelta> = objects.extract(py).unwrap();
+
+        assert_eq!(objects.as_ref(py).len().unwrap(), 1077);
+        assert_eq!(deltas.len(), 1077);
+        assert_eq!(
+            deltas[0].instrument_id.to_string(),
+            "1.166564490-60424-0.0.BETFAIR"
+        );
+        assert!(result.call_method0(py, "__next__").is_err());
+    });
+}
+
+#[rstest]
+fn test_trade_tick_python_to_pandas() {
+    pyo3::prepare_freethreaded_python();
+
+    let file_path = "../../tests/test_data/nautilus/trades.parquet";
+    let catalog = DataBackendSession::new(1_000).unwrap();
+    Python::with_gil(|py| {
+        // The conversion needs pandas and pyarrow in the linked Python
+        if py.import_bound("pandas").is_err() || py.import_bound("pyarrow").is_err() {
+            eprintln!("Skipping `to_pandas` test, as pandas or pyarrow is not installed");
+            return;
+        }
+        let pycatalog: Py<PyAny> = catalog.into_py(py);
+        pycatalog
+            .call_method1(
+                py,
+                "add_file",
+                (NautilusDataType::TradeTick, "trades", file_path),
+            )
+            .unwrap();
+        let df = pycatalog.call_method0(py, "to_pandas").unwrap();
+        let shape: (usize, usize) = df.getattr(py, "shape").unwrap().extract(py).unwrap();
+
+        assert_eq!(shape, (100, TradeTick::get_schema(None).fields().len()));
+    });
+}
+
+#[rstest]
+fn test_python_to_pandas_mixed_data_types_errors() {
+    pyo3::prepare_freethreaded_python();
+
+    let catalog = DataBackendSession::new(1_000).unwrap();
+    Python::with_gil(|py| {
+        let pycatalog: Py<PyAny> = catalog.into_py(py);
+        pycatalog
+            .call_method1(
+                py,
+                "add_file",
+                (
+                    NautilusDataType::TradeTick,
+                    "trades",
+                    "../../tests/test_data/nautilus/trades.parquet",
+                ),
+            )
+            .unwrap();
+        pycatalog
+            .call_method1(
+                py,
+                "add_file",
+                (
+                    NautilusDataType::Bar,
+                    "bars",
+                    "../../tests/test_data/nautilus/bars.parquet",
+                ),
+            )
+            .unwrap();
+        let err = pycatalog.call_method0(py, "to_pandas").unwrap_err();
+
+        assert!(err.is_instance_of::<PyValueError>(py));
+    });
+}
+
+#[rstest]
+fn test_add_file_checked_monotonic() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file_checked::<QuoteTick>("quote_005", file_path, None)
+        .unwrap();
+    let ticks: Vec<Data> = catalog.get_query_result().collect();
+
+    assert_eq!(ticks.len(), 9_500);
+}
+
+#[rstest]
+fn test_add_file_checked_not_monotonic() {
+    let file_path = "../../tests/test_data/nautilus/deltas.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    let result = catalog.add_file_checked::<OrderBookDelta>("delta_001", file_path, None);
+
+    match result {
+        Err(PersistenceError::NotMonotonic {
+            sort_key,
+            index,
+            prev_ts,
+            ts,
+        }) => {
+            assert_eq!(sort_key, SortKey::TsInit);
+            assert!(index > 0);
+            assert!(prev_ts > ts);
+        }
+        other => panic!("Expected `NotMonotonic` error, was {other:?}"),
+    }
+    assert!(catalog.registered_tables().is_empty());
+}
+
 #[rstest]
 fn test_quote_tick_query() {
     let expected_length = 9_500;
     let file_path = "../../tests/test_data/nautilus/quotes.parquet";
-    let mut catalog = DataBackendSession::new(10_000);
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
     catalog
         .add_file::<QuoteTick>("quote_005", file_path, None)
         .unwrap();
@@ -237,10 +532,116 @@ fn test_quote_tick_query() {
     assert!(is_monotonically_increasing_by_init(&ticks));
 }
 
+#[rstest]
+fn test_quote_tick_for_instrument() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file_for_instrument::<QuoteTick>(
+            "quote_005",
+            file_path,
+            &InstrumentId::from("EUR/USD.SIM"),
+        )
+        .unwrap();
+    catalog
+        .add_file_for_instrument::<QuoteTick>(
+            "quote_006",
+            file_path,
+            &InstrumentId::from("GBP/USD.SIM"),
+        )
+        .unwrap();
+    let ticks: Vec<Data> = catalog.get_query_result().collect();
+
+    assert_eq!(ticks.len(), 9_500);
+    assert!(ticks
+        .iter()
+        .all(|tick| tick.instrument_id() == InstrumentId::from("EUR/USD.SIM")));
+}
+
+#[rstest]
+fn test_quote_tick_for_instrument_multi_instrument_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = write_multi_instrument_quotes(&temp_dir, &["EUR/USD.SIM", "GBP/USD.SIM"]);
+    let file_path = file_path.as_str();
+
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file_for_instrument::<QuoteTick>(
+            "quote_multi",
+            file_path,
+            &InstrumentId::from("GBP/USD.SIM"),
+        )
+        .unwrap();
+    let ticks: Vec<Data> = catalog.get_query_result().collect();
+
+    assert_eq!(ticks.len(), 4_750);
+    assert!(ticks
+        .iter()
+        .all(|tick| tick.instrument_id() == InstrumentId::from("GBP/USD.SIM")));
+    assert!(is_monotonically_increasing_by_init(&ticks));
+}
+
+#[rstest]
+fn test_quote_tick_for_instruments_multi_instrument_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path =
+        write_multi_instrument_quotes(&temp_dir, &["EUR/USD.SIM", "GBP/USD.SIM", "USD/JPY.SIM"]);
+    let file_path = file_path.as_str();
+
+    let selected = [
+        InstrumentId::from("GBP/USD.SIM"),
+        InstrumentId::from("USD/JPY.SIM"),
+    ];
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file_for_instruments::<QuoteTick>("quote_multi", file_path, &selected)
+        .unwrap();
+    let ticks: Vec<Data> = catalog.get_query_result().collect();
+
+    assert_eq!(ticks.len(), 6_333);
+    assert!(ticks
+        .iter()
+        .all(|tick| selected.contains(&tick.instrument_id())));
+    assert!(ticks.iter().any(|tick| tick.instrument_id() == selected[0]));
+    assert!(ticks.iter().any(|tick| tick.instrument_id() == selected[1]));
+    assert!(is_monotonically_increasing_by_init(&ticks));
+
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file_for_instruments::<QuoteTick>("quote_none", file_path, &[])
+        .unwrap();
+    assert_eq!(catalog.get_query_result().count(), 0);
+}
+
+#[rstest]
+fn test_group_by_instrument() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = write_multi_instrument_quotes(&temp_dir, &["EUR/USD.SIM", "GBP/USD.SIM"]);
+    let eurusd = InstrumentId::from("EUR/USD.SIM");
+    let gbpusd = InstrumentId::from("GBP/USD.SIM");
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file_for_instruments::<QuoteTick>("quote_multi", &file_path, &[eurusd, gbpusd])
+        .unwrap();
+    let ticks: Vec<Data> = catalog.get_query_result().collect();
+
+    let groups = group_by_instrument(&ticks);
+
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[&eurusd].len(), 4_750);
+    assert_eq!(groups[&gbpusd].len(), 4_750);
+    for (instrument_id, group) in &groups {
+        assert!(group
+            .iter()
+            .all(|tick| tick.instrument_id() == *instrument_id));
+        assert!(is_monotonically_increasing_by_init(group));
+    }
+}
+
 #[rstest]
 fn test_quote_tick_multiple_query() {
     let expected_length = 9_600;
-    let mut catalog = DataBackendSession::new(5_000);
+    let mut catalog = DataBackendSession::new(5_000).unwrap();
     catalog
         .add_file::<QuoteTick>(
             "quote_tick",
@@ -262,11 +663,43 @@ fn test_quote_tick_multiple_query() {
     assert!(is_monotonically_increasing_by_init(&ticks));
 }
 
+#[rstest]
+#[case(1)]
+#[case(4)]
+fn test_trade_tick_parallel_decode(#[case] decode_threads: usize) {
+    let file_path = "../../tests/test_data/nautilus/trades.parquet";
+    let copies = 8;
+    let mut catalog = DataBackendSession::new(64).unwrap();
+    catalog.set_decode_threads(decode_threads).unwrap();
+    for i in 0..copies {
+        catalog
+            .add_file::<TradeTick>(&format!("trade_{i:03}"), file_path, None)
+            .unwrap();
+    }
+    let mut ticks: Vec<Data> = catalog.get_query_result().collect();
+
+    let mut catalog = DataBackendSession::new(64).unwrap();
+    catalog
+        .add_file::<TradeTick>("trade_000", file_path, None)
+        .unwrap();
+    let mut expected: Vec<Data> = catalog
+        .get_query_result()
+        .flat_map(|tick| vec![tick; copies])
+        .collect();
+
+    assert_eq!(ticks.len(), 100 * copies);
+    assert!(is_monotonically_increasing_by_init(&ticks));
+    // Ties on `ts_init` across files may merge in either order
+    ticks.sort_by_cached_key(|tick| (tick.ts_init(), format!("{tick:?}")));
+    expected.sort_by_cached_key(|tick| (tick.ts_init(), format!("{tick:?}")));
+    assert_eq!(ticks, expected);
+}
+
 #[rstest]
 fn test_trade_tick_query() {
     let expected_length = 100;
     let file_path = "../../tests/test_data/nautilus/trades.parquet";
-    let mut catalog = DataBackendSession::new(10_000);
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
     catalog
         .add_file::<TradeTick>("trade_001", file_path, None)
         .unwrap();
@@ -283,11 +716,55 @@ fn test_trade_tick_query() {
     assert!(is_monotonically_increasing_by_init(&ticks));
 }
 
+#[rstest]
+fn test_trade_tick_collect() {
+    let file_path = "../../tests/test_data/nautilus/trades.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    let ticks = catalog
+        .collect::<TradeTick>("trade_001", file_path)
+        .unwrap();
+
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file::<TradeTick>("trade_001", file_path, None)
+        .unwrap();
+    let expected: Vec<Data> = catalog.get_query_result().collect();
+    assert_eq!(ticks.len(), 100);
+    assert_eq!(ticks, expected);
+}
+
+#[rstest]
+fn test_bar_query_write_json() {
+    let file_path = "../../tests/test_data/nautilus/bars.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog.add_file::<Bar>("bar_001", file_path, None).unwrap();
+    let expected: Vec<Data> = catalog.get_query_result().take(5).collect();
+
+    catalog.add_file::<Bar>("bar_002", file_path, None).unwrap();
+    let temp_dir = TempDir::new().unwrap();
+    let json_path = temp_dir.path().join("bars.json");
+    catalog
+        .get_query_result()
+        .write_json(&json_path, Some(5))
+        .unwrap();
+
+    let json = std::fs::read_to_string(&json_path).unwrap();
+    let objects: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+    assert_eq!(objects.len(), 5);
+    for (mut object, data) in objects.into_iter().zip(expected) {
+        assert_eq!(object[JSON_TYPE_TAG], "Bar");
+        object.as_object_mut().unwrap().remove(JSON_TYPE_TAG);
+        // Prices deserialize from borrowed strings, so parse from the text
+        let bar: Bar = serde_json::from_str(&object.to_string()).unwrap();
+        assert_eq!(Data::Bar(bar), data);
+    }
+}
+
 #[rstest]
 fn test_bar_query() {
     let expected_length = 10;
     let file_path = "../../tests/test_data/nautilus/bars.parquet";
-    let mut catalog = DataBackendSession::new(10_000);
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
     catalog.add_file::<Bar>("bar_001", file_path, None).unwrap();
     let query_result: QueryResult = catalog.get_query_result();
     let ticks: Vec<Data> = query_result.collect();
@@ -301,3 +778,3389 @@ fn test_bar_query() {
     assert_eq!(ticks.len(), expected_length);
     assert!(is_monotonically_increasing_by_init(&ticks));
 }
+
+#[rstest]
+fn test_bar_query_reusable() {
+    let file_path = "../../tests/test_data/nautilus/bars.parquet";
+    let mut catalog = DataBackendSession::new(4).unwrap();
+    catalog.add_file::<Bar>("bar_001", file_path, None).unwrap();
+    let result = catalog.get_query_result().into_reusable().unwrap();
+
+    let first: Vec<Data> = result.iter().cloned().collect();
+    let second: Vec<Data> = result.iter().cloned().collect();
+
+    assert_eq!(first.len(), 10);
+    assert_eq!(result.len(), 10);
+    assert_eq!(
+        result.chunks().iter().map(Vec::len).collect::<Vec<_>>(),
+        [4, 4, 2]
+    );
+    assert_eq!(first, second);
+    assert!(is_monotonically_increasing_by_init(&first));
+}
+
+#[rstest]
+fn test_bar_split_at_ts() {
+    let file_path = "../../tests/test_data/nautilus/bars.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog.add_file::<Bar>("bar_001", file_path, None).unwrap();
+    let bars: Vec<Data> = catalog.get_query_result().collect();
+    let ts = bars[4].ts_init();
+
+    let (before, after) = split_at_ts(bars.clone(), ts);
+
+    assert_eq!(before.len(), 4);
+    assert_eq!(after.len(), 6);
+    assert!(before.iter().all(|bar| bar.ts_init() < ts));
+    assert_eq!(after[0].ts_init(), ts);
+    assert_eq!([before, after].concat(), bars);
+    assert_eq!(split_at_ts(bars.clone(), UnixNanos::default()).0.len(), 0);
+    assert_eq!(split_at_ts(bars, u64::MAX.into()).1.len(), 0);
+}
+
+#[rstest]
+fn test_bar_find_gaps() {
+    let file_path = "../../tests/test_data/nautilus/bars.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog.add_file::<Bar>("bar_001", file_path, None).unwrap();
+    let mut bars: Vec<Data> = catalog.get_query_result().collect();
+    let interval_ns = 60_000_000_000;
+    assert!(find_gaps(&bars, interval_ns).is_empty());
+
+    // Remove two bars from the middle of the one minute bars
+    let expected = (bars[3].ts_init().as_u64(), bars[6].ts_init().as_u64());
+    bars.drain(4..6);
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("bars_gap.parquet");
+    let file_path = file_path.to_str().unwrap();
+    write_data_to_parquet(file_path, &bars, ParquetCompression::Snappy).unwrap();
+    catalog.add_file::<Bar>("bar_gap", file_path, None).unwrap();
+    let bars: Vec<Data> = catalog.get_query_result().collect();
+
+    assert_eq!(find_gaps(&bars, interval_ns), [expected]);
+    assert_eq!(expected.1 - expected.0, 3 * interval_ns);
+}
+
+#[rstest]
+fn test_quote_tick_range_query() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file::<QuoteTick>("quote_005", file_path, None)
+        .unwrap();
+    let all_ticks: Vec<Data> = catalog.get_query_result().collect();
+    let start = all_ticks[1_000].ts_init();
+    let end = all_ticks[2_999].ts_init();
+    let expected_length = all_ticks
+        .iter()
+        .filter(|tick| tick.ts_init() >= start && tick.ts_init() <= end)
+        .count();
+
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file_range_query::<QuoteTick>("quote_005", file_path, Some(start), Some(end))
+        .unwrap();
+    let ticks: Vec<Data> = catalog.get_query_result().collect();
+
+    assert!(expected_length < all_ticks.len());
+    assert_eq!(ticks.len(), expected_length);
+    assert_eq!(ticks.first().unwrap().ts_init(), start);
+    assert_eq!(ticks.last().unwrap().ts_init(), end);
+    assert!(is_monotonically_increasing_by_init(&ticks));
+}
+
+#[rstest]
+fn test_explain_range_query() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file_range_query::<QuoteTick>(
+            "quotes",
+            file_path,
+            Some(UnixNanos::from(1)),
+            Some(UnixNanos::from(2)),
+        )
+        .unwrap();
+    catalog
+        .add_file_paged_query::<QuoteTick>("quotes_page", file_path, 10, 0)
+        .unwrap();
+
+    let plan = catalog.explain("quotes").unwrap();
+    assert!(plan.contains("FilterExec"), "{plan}");
+    assert!(plan.contains("predicate="), "{plan}");
+    let plan = catalog.explain("quotes_page").unwrap();
+    assert!(plan.contains("limit=10"), "{plan}");
+
+    catalog.get_query_result();
+    assert!(matches!(
+        catalog.explain("quotes"),
+        Err(PersistenceError::QueryNotFound(_))
+    ));
+}
+
+#[rstest]
+fn test_quote_tick_count() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file::<QuoteTick>("quote_005", file_path, None)
+        .unwrap();
+
+    assert_eq!(catalog.count("quote_005").unwrap(), 9_500);
+}
+
+#[rstest]
+fn test_quote_tick_count_range() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file::<QuoteTick>("quote_005", file_path, None)
+        .unwrap();
+    let start = catalog.registered_tables()[0].min_ts_init.unwrap();
+    let all_ticks: Vec<Data> = catalog.get_query_result().collect();
+    let end = all_ticks[2_999].ts_init();
+    let expected_length = all_ticks
+        .iter()
+        .filter(|tick| tick.ts_init() <= end)
+        .count() as u64;
+
+    assert_eq!(
+        catalog
+            .count_range("quote_005", Some(start), Some(end))
+            .unwrap(),
+        expected_length
+    );
+    assert_eq!(
+        catalog.count_range("quote_005", None, Some(end)).unwrap(),
+        expected_length
+    );
+    assert_eq!(
+        catalog
+            .count_range("quote_005", Some(end + 1), None)
+            .unwrap(),
+        9_500 - expected_length
+    );
+}
+
+#[rstest]
+fn test_quote_tick_range_query_open_bounds() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file_range_query::<QuoteTick>("quote_005", file_path, None, None)
+        .unwrap();
+    let ticks: Vec<Data> = catalog.get_query_result().collect();
+    let start = ticks[5_000].ts_init();
+
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file_range_query::<QuoteTick>("quote_005", file_path, Some(start), None)
+        .unwrap();
+    let tail: Vec<Data> = catalog.get_query_result().collect();
+
+    assert_eq!(ticks.len(), 9_500);
+    assert!(tail.len() < ticks.len());
+    assert!(tail.iter().all(|tick| tick.ts_init() >= start));
+    assert!(is_monotonically_increasing_by_init(&tail));
+}
+
+#[rstest]
+fn test_order_book_delta_custom_query() {
+    let file_path = "../../tests/test_data/nautilus/deltas.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file::<OrderBookDelta>("delta_001", file_path, None)
+        .unwrap();
+    let all_deltas: Vec<Data> = catalog.get_query_result().collect();
+    let expected_length = all_deltas
+        .iter()
+        .filter(|data| matches!(data, Data::Delta(delta) if delta.action == BookAction::Delete))
+        .count();
+
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file::<OrderBookDelta>(
+            "delta_001",
+            file_path,
+            Some("SELECT * FROM delta_001 WHERE action = 3"),
+        )
+        .unwrap();
+    let deltas: Vec<Data> = catalog.get_query_result().collect();
+
+    assert!(expected_length > 0);
+    assert_eq!(deltas.len(), expected_length);
+    assert!(deltas
+        .iter()
+        .all(|data| matches!(data, Data::Delta(delta) if delta.action == BookAction::Delete)));
+}
+
+#[rstest]
+fn test_custom_query_reordered_columns() {
+    let file_path = "../../tests/test_data/nautilus/trades.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file::<TradeTick>(
+            "trade_001",
+            file_path,
+            Some("SELECT ts_init, ts_event, trade_id, aggressor_side, size, price FROM trade_001"),
+        )
+        .unwrap();
+    let ticks: Vec<Data> = catalog.get_query_result().collect();
+
+    assert_eq!(ticks.len(), 100);
+    assert!(is_monotonically_increasing_by_init(&ticks));
+}
+
+#[rstest]
+fn test_custom_query_missing_column() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    let result = catalog.add_file::<QuoteTick>(
+        "quote_005",
+        file_path,
+        Some("SELECT bid, ask, ts_init FROM quote_005"),
+    );
+
+    let err = result.unwrap_err();
+    assert!(err.to_string().contains("`bid_size`"));
+}
+
+#[rstest]
+fn test_schema_mismatch_errors() {
+    let file_path = "../../tests/test_data/nautilus/trades.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    let result = catalog.add_file::<QuoteTick>("trade_001", file_path, None);
+
+    match result {
+        Err(PersistenceError::SchemaMismatch { expected, found }) => {
+            assert_eq!(expected, "`ask_price` (Int64)");
+            assert_eq!(found, "`size` (UInt64)");
+        }
+        other => panic!("Expected `SchemaMismatch` error, was {other:?}"),
+    }
+    assert!(catalog.registered_tables().is_empty());
+}
+
+#[rstest]
+fn test_schema_mismatch_deregisters_table() {
+    let file_path = "../../tests/test_data/nautilus/trades.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    let result = catalog.add_file::<QuoteTick>("trade_001", file_path, None);
+    assert!(matches!(
+        result,
+        Err(PersistenceError::SchemaMismatch { .. })
+    ));
+
+    catalog
+        .add_file::<TradeTick>("trade_001", file_path, None)
+        .unwrap();
+    let query_result: QueryResult = catalog.get_query_result();
+    let ticks: Vec<Data> = query_result.collect();
+
+    assert_eq!(ticks.len(), 100);
+}
+
+#[rstest]
+fn test_collect_typed_quotes() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    let quotes: Vec<QuoteTick> = catalog.collect_typed("quote_005", file_path).unwrap();
+
+    assert_eq!(quotes.len(), 9_500);
+    assert!(is_monotonically_increasing_by_init(&quotes));
+    let tables = catalog.registered_tables();
+    assert_eq!(tables.len(), 1);
+    assert_eq!(tables[0].data_type, NautilusDataType::QuoteTick);
+    assert_eq!(tables[0].row_count, Some(9_500));
+}
+
+#[rstest]
+fn test_collect_typed_mismatched_type_errors() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    let result = catalog.collect_typed::<TradeTick>("quote_005", file_path);
+
+    assert!(matches!(
+        result,
+        Err(PersistenceError::SchemaMismatch { .. })
+    ));
+    assert!(catalog.registered_tables().is_empty());
+    let quotes: Vec<QuoteTick> = catalog.collect_typed("quote_005", file_path).unwrap();
+    assert_eq!(quotes.len(), 9_500);
+}
+
+#[rstest]
+fn test_instruments_round_trip() {
+    let mut instruments = vec![
+        InstrumentAny::Equity(equity_aapl()),
+        InstrumentAny::CurrencyPair(currency_pair_btcusdt()),
+        InstrumentAny::CryptoPerpetual(crypto_perpetual_ethusdt()),
+    ];
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("instruments.parquet");
+    let file_path = file_path.to_str().unwrap();
+    write_instruments_to_parquet(file_path, &instruments, ParquetCompression::Snappy).unwrap();
+
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    let result = catalog
+        .collect_instruments("instruments", file_path)
+        .unwrap();
+
+    instruments.sort_by_key(|instrument| instrument.id().to_string());
+    assert_eq!(result, instruments);
+    assert_eq!(
+        result[1].price_precision(),
+        instruments[1].price_precision()
+    );
+    assert_eq!(result[1].size_increment(), instruments[1].size_increment());
+    assert!(matches!(
+        catalog.collect_instruments("quotes", "../../tests/test_data/nautilus/quotes.parquet"),
+        Err(PersistenceError::SchemaMismatch { .. })
+    ));
+}
+
+#[rstest]
+fn test_instrument_file_alongside_data() {
+    let instruments = vec![
+        InstrumentAny::Equity(equity_aapl()),
+        InstrumentAny::CurrencyPair(currency_pair_btcusdt()),
+    ];
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("instruments.parquet");
+    let file_path = file_path.to_str().unwrap();
+    write_instruments_to_parquet(file_path, &instruments, ParquetCompression::Snappy).unwrap();
+
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .add_instrument_file(
+            "instruments",
+            file_path,
+            Some("SELECT * FROM instruments WHERE instrument_id = 'AAPL.XNAS'"),
+        )
+        .unwrap();
+    catalog
+        .add_file::<QuoteTick>(
+            "quotes",
+            "../../tests/test_data/nautilus/quotes.parquet",
+            None,
+        )
+        .unwrap();
+
+    let data_types: Vec<_> = catalog
+        .registered_tables()
+        .iter()
+        .map(|table| table.data_type)
+        .collect();
+    assert_eq!(
+        data_types,
+        [NautilusDataType::InstrumentAny, NautilusDataType::QuoteTick]
+    );
+    assert_eq!(catalog.get_query_result().count(), 9_500);
+    assert_eq!(catalog.get_instruments().unwrap(), instruments[..1]);
+    assert!(catalog.get_instruments().unwrap().is_empty());
+}
+
+#[rstest]
+fn test_instrument_file_python() {
+    pyo3::prepare_freethreaded_python();
+
+    let instruments = vec![
+        InstrumentAny::Equity(equity_aapl()),
+        InstrumentAny::CurrencyPair(currency_pair_btcusdt()),
+    ];
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("instruments.parquet");
+    let file_path = file_path.to_str().unwrap();
+    write_instruments_to_parquet(file_path, &instruments, ParquetCompression::Snappy).unwrap();
+
+    let catalog = DataBackendSession::new(1_000).unwrap();
+    Python::with_gil(|py| {
+        let pycatalog: Py<PyAny> = catalog.into_py(py);
+        pycatalog
+            .call_method1(
+                py,
+                "add_file",
+                (NautilusDataType::InstrumentAny, "instruments", file_path),
+            )
+            .unwrap();
+        let result: Vec<Py<PyAny>> = pycatalog
+            .call_method0(py, "to_instruments")
+            .unwrap()
+            .extract(py)
+            .unwrap();
+
+        assert_eq!(result.len(), 2);
+    });
+}
+
+#[rstest]
+fn test_target_partitions_same_results() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let mut results = Vec::new();
+    for n in [1, 8] {
+        let mut catalog = DataBackendSession::new(1_000).unwrap();
+        catalog.set_target_partitions(n).unwrap();
+        catalog
+            .add_file::<QuoteTick>("quote_005", file_path, None)
+            .unwrap();
+        let ticks: Vec<Data> = catalog.get_query_result().collect();
+        assert_eq!(ticks.len(), 9_500);
+        assert!(is_monotonically_increasing_by_init(&ticks));
+        results.push(ticks);
+    }
+
+    assert_eq!(results[0], results[1]);
+    assert!(matches!(
+        DataBackendSession::new(1_000)
+            .unwrap()
+            .set_target_partitions(0),
+        Err(PersistenceError::InvalidTargetPartitions)
+    ));
+}
+
+/// Returns the number of chunks decoded for the quotes file, and the lengths
+/// of the chunks of the result.
+fn quote_chunks(chunk_size: usize, reader_batch_size: Option<usize>) -> (u64, Vec<usize>) {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let session = || {
+        let mut catalog = DataBackendSession::new(chunk_size).unwrap();
+        if let Some(n) = reader_batch_size {
+            catalog.set_reader_batch_size(n).unwrap();
+        }
+        catalog
+            .add_file::<QuoteTick>("quote_005", file_path, None)
+            .unwrap();
+        catalog
+    };
+
+    let mut query_result = session().get_query_result();
+    assert_eq!(query_result.by_ref().count(), 9_500);
+    let chunks: Vec<Vec<Data>> = session().get_chunked_query_result().collect();
+    let lengths = chunks.iter().map(Vec::len).collect();
+    (query_result.metrics().chunks, lengths)
+}
+
+#[rstest]
+fn test_reader_batch_size_independent_of_chunk_size() {
+    let (default_decoded, default_chunks) = quote_chunks(10_000, None);
+    let (small_batch_decoded, small_batch_chunks) = quote_chunks(10_000, Some(100));
+    let (_, small_chunks) = quote_chunks(1_000, Some(10_000));
+
+    // The batch size changes how the file is decoded, but not the result chunks
+    assert!(small_batch_decoded >= 95);
+    assert!(default_decoded < small_batch_decoded);
+    assert_eq!(default_chunks, [9_500]);
+    assert_eq!(small_batch_chunks, [9_500]);
+    // The chunk size changes the result chunks regardless of the batch size
+    assert_eq!(small_chunks.len(), 10);
+    assert_eq!(small_chunks.iter().sum::<usize>(), 9_500);
+    assert!(matches!(
+        DataBackendSession::new(1_000)
+            .unwrap()
+            .set_reader_batch_size(0),
+        Err(PersistenceError::InvalidReaderBatchSize)
+    ));
+}
+
+#[rstest]
+#[case("trades.parquet", trade_schema())]
+#[case("bars.parquet", bar_schema())]
+#[case("deltas.parquet", delta_schema())]
+fn test_sample_file_schemas(#[case] file_name: &str, #[case] expected: Schema) {
+    let file_path = format!("../../tests/test_data/nautilus/{file_name}");
+    let builder =
+        ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(file_path).unwrap()).unwrap();
+
+    assert_eq!(builder.schema().fields(), expected.fields());
+}
+
+#[rstest]
+fn test_sample_quote_file_schema() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let builder =
+        ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(file_path).unwrap()).unwrap();
+    let schema = builder.schema();
+    let expected = quote_schema();
+
+    // The file predates the current names of the price columns, `bid` and
+    // `ask`, which are decoded by position
+    assert_eq!(schema.fields().len(), expected.fields().len());
+    for (field, expected) in schema.fields().iter().zip(expected.fields()) {
+        assert_eq!(field.data_type(), expected.data_type());
+        assert_eq!(field.is_nullable(), expected.is_nullable());
+    }
+    assert_eq!(&schema.fields()[2..], &expected.fields()[2..]);
+}
+
+#[rstest]
+fn test_encode_batch_of_decoded_quotes() {
+    let quotes = DataBackendSession::new(1_000)
+        .unwrap()
+        .collect::<QuoteTick>("quotes", "../../tests/test_data/nautilus/quotes.parquet")
+        .unwrap();
+
+    let batch = encode_batch(&quotes).unwrap();
+
+    assert_eq!(batch.num_rows(), 9500);
+    assert_eq!(batch.schema().fields(), quote_schema().fields());
+    assert_eq!(
+        batch.schema().metadata()["instrument_id"],
+        quotes[0].instrument_id().to_string()
+    );
+    assert_eq!(decode_batch::<QuoteTick>(&batch).unwrap(), quotes);
+}
+
+#[rstest]
+fn test_encode_batch_of_mixed_types_errors() {
+    let data = vec![
+        Data::Quote(quote_tick_audusd_sim()),
+        Data::Trade(trade_tick_audusd_sim()),
+    ];
+
+    let result = encode_batch(&data);
+
+    assert!(matches!(result, Err(PersistenceError::MixedDataTypes)));
+}
+
+#[rstest]
+fn test_quote_tick_chunked_query() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .add_file::<QuoteTick>("quote_005", file_path, None)
+        .unwrap();
+    let chunks: Vec<Vec<Data>> = catalog.get_chunked_query_result().collect();
+
+    assert_eq!(chunks.len(), 10);
+    assert!(chunks.iter().all(|chunk| chunk.len() <= 1_000));
+    assert_eq!(chunks.iter().map(Vec::len).sum::<usize>(), 9_500);
+    assert!(chunks
+        .iter()
+        .all(|chunk| is_monotonically_increasing_by_init(chunk)));
+}
+
+#[rstest]
+fn test_quote_tick_row_group_chunks() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let metadata = DataBackendSession::file_metadata(file_path).unwrap();
+    let mut catalog = DataBackendSession::builder()
+        .chunk_mode(ChunkMode::RowGroup)
+        .build()
+        .unwrap();
+    catalog
+        .add_file::<QuoteTick>("quote_005", file_path, None)
+        .unwrap();
+    let chunks: Vec<Vec<Data>> = catalog.get_chunked_query_result().collect();
+
+    assert_eq!(chunks.len(), metadata.row_groups.len());
+    assert!(chunks
+        .iter()
+        .zip(&metadata.row_groups)
+        .all(|(chunk, row_group)| chunk.len() as u64 == row_group.num_rows));
+}
+
+#[rstest]
+fn test_quote_tick_next_chunk() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let mut catalog = DataBackendSession::new(4_000).unwrap();
+    catalog
+        .add_file::<QuoteTick>("quote_005", file_path, None)
+        .unwrap();
+    let mut query_result = catalog.get_chunked_query_result();
+
+    let mut lengths = Vec::new();
+    while let Some(cvec) = query_result.next_chunk() {
+        lengths.push(cvec.len);
+    }
+
+    assert_eq!(lengths, vec![4_000, 4_000, 1_500]);
+}
+
+#[rstest]
+fn test_quote_tick_query_stream() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let mut catalog = DataBackendSession::new(4_000).unwrap();
+    catalog
+        .add_file::<QuoteTick>("quote_005", file_path, None)
+        .unwrap();
+    let mut stream = catalog.get_query_stream();
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let chunks: Vec<Vec<Data>> = runtime.block_on(async {
+        let mut chunks = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            chunks.push(chunk);
+        }
+        chunks
+    });
+
+    assert_eq!(
+        chunks.iter().map(Vec::len).collect::<Vec<_>>(),
+        vec![4_000, 4_000, 1_500]
+    );
+    assert!(is_monotonically_increasing_by_init(&chunks.concat()));
+}
+
+#[rstest]
+fn test_quote_tick_query_stream_cancelled() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .add_file::<QuoteTick>("quote_005", file_path, None)
+        .unwrap();
+    let mut query_result = catalog.get_query_result();
+    let token = CancellationToken::new();
+    query_result.set_cancellation_token(token.clone());
+    let mut stream = DataQueryStream::new(query_result, 1_000);
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let (first, rest) = runtime.block_on(async {
+        let first = stream.next().await.unwrap();
+        token.cancel();
+        let mut rest = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            rest.push(chunk);
+        }
+        (first, rest)
+    });
+
+    assert_eq!(first.len(), 1_000);
+    assert!(is_monotonically_increasing_by_init(&first));
+    assert!(rest.is_empty());
+}
+
+#[rstest]
+fn test_bar_size_hint_total() {
+    let file_path = "../../tests/test_data/nautilus/bars.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog.add_file::<Bar>("bar_001", file_path, None).unwrap();
+    let query_result: QueryResult = catalog.get_query_result();
+
+    assert_eq!(query_result.size_hint_total(), Some(10));
+    assert_eq!(query_result.count(), 10);
+}
+
+#[rstest]
+fn test_order_book_delta_estimated_memory_bytes() {
+    let file_path = "../../tests/test_data/nautilus/deltas.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file::<OrderBookDelta>("delta_001", file_path, None)
+        .unwrap();
+    let query_result: QueryResult = catalog.get_query_result();
+
+    // Each delta is held within the `Data` wrapper
+    let expected = 1_077 * std::mem::size_of::<Data>() as u64;
+    assert!(std::mem::size_of::<Data>() >= std::mem::size_of::<OrderBookDelta>());
+    assert_eq!(query_result.estimated_memory_bytes(), Some(expected));
+}
+
+#[rstest]
+fn test_size_hint_total_multiple_files() {
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file::<QuoteTick>(
+            "quote_005",
+            "../../tests/test_data/nautilus/quotes.parquet",
+            None,
+        )
+        .unwrap();
+    catalog
+        .add_file::<Bar>(
+            "bar_001",
+            "../../tests/test_data/nautilus/bars.parquet",
+            None,
+        )
+        .unwrap();
+
+    assert_eq!(catalog.get_query_result().size_hint_total(), Some(9_510));
+}
+
+#[rstest]
+fn test_size_hint_total_unknown_with_filter() {
+    let file_path = "../../tests/test_data/nautilus/bars.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file::<Bar>(
+            "bar_001",
+            file_path,
+            Some("SELECT * FROM bar_001 WHERE volume > 0"),
+        )
+        .unwrap();
+    let query_result = catalog.get_query_result();
+
+    assert_eq!(query_result.size_hint_total(), None);
+    assert_eq!(query_result.estimated_memory_bytes(), None);
+}
+
+#[rstest]
+fn test_quote_tick_batch_query(quote_tick_audusd_sim: QuoteTick) {
+    let quotes: Vec<QuoteTick> = (0..10)
+        .map(|i| {
+            let mut quote = quote_tick_audusd_sim;
+            quote.ts_event = (1_000 + i).into();
+            quote.ts_init = (2_000 + i).into();
+            quote
+        })
+        .collect();
+    let metadata = QuoteTick::get_metadata(
+        &quotes[0].instrument_id,
+        quotes[0].bid_price.precision,
+        quotes[0].bid_size.precision,
+    );
+    let batch = QuoteTick::encode_batch(&metadata, &quotes).unwrap();
+
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_batch::<QuoteTick>("quote_batch", batch)
+        .unwrap();
+    catalog
+        .add_file::<Bar>(
+            "bar_001",
+            "../../tests/test_data/nautilus/bars.parquet",
+            None,
+        )
+        .unwrap();
+    let tables = catalog.registered_tables();
+    let result: Vec<Data> = catalog.get_query_result().collect();
+    let result_quotes: Vec<Data> = result
+        .iter()
+        .filter(|data| matches!(data, Data::Quote(_)))
+        .cloned()
+        .collect();
+    let expected: Vec<Data> = quotes.into_iter().map(Data::Quote).collect();
+
+    assert_eq!(result.len(), 20);
+    assert!(is_monotonically_increasing_by_init(&result));
+    assert_eq!(result_quotes, expected);
+    assert_eq!(tables[0].row_count, Some(10));
+    assert_eq!(tables[0].min_ts_init, Some(2_000.into()));
+    assert_eq!(tables[0].max_ts_init, Some(2_009.into()));
+}
+
+#[rstest]
+fn test_empty_file_query() {
+    let instrument_id = InstrumentId::from("EUR/USD.SIM");
+    let metadata = QuoteTick::get_metadata(&instrument_id, 5, 0);
+    let batch = QuoteTick::encode_batch(&metadata, &[]).unwrap();
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("quotes_empty.parquet");
+    let file_path = file_path.to_str().unwrap();
+    write_batch_to_parquet(file_path, &batch, ParquetCompression::Snappy).unwrap();
+
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file::<QuoteTick>("quote_empty", file_path, None)
+        .unwrap();
+    let tables = catalog.registered_tables();
+    let ticks: Vec<Data> = catalog.get_query_result().collect();
+
+    assert!(ticks.is_empty());
+    assert!(is_monotonically_increasing_by_init(&ticks));
+    assert_eq!(tables[0].row_count, Some(0));
+    assert_eq!(tables[0].min_ts_init, None);
+    assert_eq!(tables[0].max_ts_init, None);
+}
+
+#[rstest]
+fn test_empty_file_chunked_query() {
+    let instrument_id = InstrumentId::from("EUR/USD.SIM");
+    let metadata = QuoteTick::get_metadata(&instrument_id, 5, 0);
+    let batch = QuoteTick::encode_batch(&metadata, &[]).unwrap();
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("quotes_empty.parquet");
+    let file_path = file_path.to_str().unwrap();
+    write_batch_to_parquet(file_path, &batch, ParquetCompression::Snappy).unwrap();
+
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file_checked::<QuoteTick>("quote_empty", file_path, None)
+        .unwrap();
+    let mut result = catalog.get_chunked_query_result();
+
+    assert!(result.next_chunk().is_none());
+}
+
+#[rstest]
+fn test_clear_and_reregister() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file::<QuoteTick>("quote_005", file_path, None)
+        .unwrap();
+    catalog
+        .add_file::<Bar>(
+            "bar_001",
+            "../../tests/test_data/nautilus/bars.parquet",
+            None,
+        )
+        .unwrap();
+
+    catalog.clear();
+
+    assert!(catalog.registered_tables().is_empty());
+    assert_eq!(catalog.get_query_result().count(), 0);
+
+    catalog
+        .add_file::<QuoteTick>("quote_005", file_path, None)
+        .unwrap();
+    let ticks: Vec<Data> = catalog.get_query_result().collect();
+
+    assert_eq!(ticks.len(), 9_500);
+    assert_eq!(catalog.registered_tables().len(), 1);
+}
+
+#[rstest]
+fn test_deregister() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file::<QuoteTick>("quote_005", file_path, None)
+        .unwrap();
+    catalog
+        .add_file::<Bar>(
+            "bar_001",
+            "../../tests/test_data/nautilus/bars.parquet",
+            None,
+        )
+        .unwrap();
+
+    assert!(catalog.deregister("quote_005"));
+    assert!(!catalog.deregister("quote_005"));
+    let tables = catalog.registered_tables();
+    let result: Vec<Data> = catalog.get_query_result().collect();
+
+    assert_eq!(tables.len(), 1);
+    assert_eq!(tables[0].table_name, "bar_001");
+    assert_eq!(result.len(), 10);
+    catalog
+        .add_file::<QuoteTick>("quote_005", file_path, None)
+        .unwrap();
+}
+
+#[rstest]
+fn test_bar_resampled_query() {
+    let file_path = "../../tests/test_data/nautilus/bars.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog.add_file::<Bar>("bar_001", file_path, None).unwrap();
+    let bars: Vec<Bar> = catalog
+        .get_query_result()
+        .map(|data| match data {
+            Data::Bar(bar) => bar,
+            _ => panic!("Expected bar"),
+        })
+        .collect();
+
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file_resampled_bars("bar_001", file_path, 5, BarAggregation::Minute)
+        .unwrap();
+    let resampled: Vec<Bar> = catalog
+        .get_query_result()
+        .map(|data| match data {
+            Data::Bar(bar) => bar,
+            _ => panic!("Expected bar"),
+        })
+        .collect();
+
+    let interval_ns = 300_000_000_000;
+    assert!(!resampled.is_empty());
+    assert!(resampled.len() < bars.len());
+    for bar in &resampled {
+        assert_eq!(
+            bar.bar_type.to_string(),
+            "ADABTC.BINANCE-5-MINUTE-LAST-EXTERNAL"
+        );
+        assert_eq!(bar.ts_event.as_u64() % interval_ns, 0);
+        assert_eq!(bar.ts_init, bar.ts_event);
+
+        let window: Vec<&Bar> = bars
+            .iter()
+            .filter(|b| b.ts_event > bar.ts_event - interval_ns && b.ts_event <= bar.ts_event)
+            .collect();
+        assert!(!window.is_empty());
+        assert_eq!(bar.open, window[0].open);
+        assert_eq!(bar.high, window.iter().map(|b| b.high).max().unwrap());
+        assert_eq!(bar.low, window.iter().map(|b| b.low).min().unwrap());
+        assert_eq!(bar.close, window[window.len() - 1].close);
+        assert_eq!(
+            bar.volume.raw,
+            window.iter().map(|b| b.volume.raw).sum::<u64>()
+        );
+    }
+    // Every source bar falls in a resampled window, including a partial final one
+    assert!(resampled.last().unwrap().ts_event >= bars.last().unwrap().ts_event);
+    assert!(is_monotonically_increasing_by_init(
+        &resampled.into_iter().map(Data::Bar).collect::<Vec<_>>()
+    ));
+}
+
+#[rstest]
+#[case(7, BarAggregation::Second)]
+#[case(5, BarAggregation::Tick)]
+fn test_bar_resampled_query_invalid_interval(
+    #[case] step: usize,
+    #[case] aggregation: BarAggregation,
+) {
+    let file_path = "../../tests/test_data/nautilus/bars.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    let result = catalog.add_file_resampled_bars("bar_001", file_path, step, aggregation);
+
+    assert!(matches!(
+        result,
+        Err(PersistenceError::InvalidBarInterval { .. }
+            | PersistenceError::UnsupportedAggregation(_))
+    ));
+
+    // The failed query leaves the table name free to register again
+    catalog
+        .add_file_resampled_bars("bar_001", file_path, 5, BarAggregation::Minute)
+        .unwrap();
+    assert_eq!(catalog.registered_tables().len(), 1);
+}
+
+#[rstest]
+fn test_trades_to_minute_bars() {
+    let file_path = "../../tests/test_data/nautilus/trades.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    // The trades of the test data share a zero timestamp, so restamp them every
+    // ten seconds with a five minute gap after the first 49 trades
+    let trades: Vec<TradeTick> = catalog
+        .collect::<TradeTick>("trade_001", file_path)
+        .unwrap()
+        .into_iter()
+        .zip(1_u64..)
+        .map(|(data, i)| match data {
+            Data::Trade(mut trade) => {
+                let secs = if i < 50 { i * 10 } else { i * 10 + 300 };
+                trade.ts_event = (secs * 1_000_000_000).into();
+                trade.ts_init = trade.ts_event;
+                trade
+            }
+            _ => panic!("Expected trade"),
+        })
+        .collect();
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("trades_restamped.parquet");
+    let file_path = file_path.to_str().unwrap();
+    let data: Vec<Data> = trades.iter().copied().map(Data::Trade).collect();
+    write_data_to_parquet(file_path, &data, ParquetCompression::Snappy).unwrap();
+
+    catalog
+        .trades_to_bars("trade_002", file_path, 1, BarAggregation::Minute)
+        .unwrap();
+    let bars: Vec<Bar> = catalog
+        .get_query_result()
+        .map(|data| match data {
+            Data::Bar(bar) => bar,
+            _ => panic!("Expected bar"),
+        })
+        .collect();
+
+    // The minutes of the gap without trades are skipped
+    let interval_ns = 60_000_000_000;
+    assert_eq!(bars.len(), 18);
+    assert_eq!(bars[8].ts_event.as_u64(), 9 * interval_ns);
+    assert_eq!(bars[9].ts_event.as_u64(), 14 * interval_ns);
+    for bar in &bars {
+        assert_eq!(
+            bar.bar_type.to_string(),
+            format!("{}-1-MINUTE-LAST-INTERNAL", trades[0].instrument_id)
+        );
+        assert_eq!(bar.ts_init, bar.ts_event);
+
+        let window: Vec<&TradeTick> = trades
+            .iter()
+            .filter(|t| t.ts_event > bar.ts_event - interval_ns && t.ts_event <= bar.ts_event)
+            .collect();
+        assert_eq!(bar.open, window[0].price);
+        assert_eq!(bar.high, window.iter().map(|t| t.price).max().unwrap());
+        assert_eq!(bar.low, window.iter().map(|t| t.price).min().unwrap());
+        assert_eq!(bar.close, window[window.len() - 1].price);
+        assert_eq!(
+            bar.volume.raw,
+            window.iter().map(|t| t.size.raw).sum::<u64>()
+        );
+    }
+}
+
+#[rstest]
+fn test_registered_tables() {
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    assert!(catalog.registered_tables().is_empty());
+
+    catalog
+        .add_file::<QuoteTick>(
+            "quote_005",
+            "../../tests/test_data/nautilus/quotes.parquet",
+            None,
+        )
+        .unwrap();
+    catalog
+        .add_file::<Bar>(
+            "bar_001",
+            "../../tests/test_data/nautilus/bars.parquet",
+            None,
+        )
+        .unwrap();
+    let tables = catalog.registered_tables();
+    let bars: Vec<Data> = catalog.get_query_result().collect();
+    let bars: Vec<Data> = bars
+        .into_iter()
+        .filter(|data| matches!(data, Data::Bar(_)))
+        .collect();
+
+    assert_eq!(tables.len(), 2);
+    assert_eq!(tables[0].table_name, "quote_005");
+    assert_eq!(tables[0].data_type, NautilusDataType::QuoteTick);
+    assert_eq!(tables[0].row_count, Some(9_500));
+    assert_eq!(tables[1].table_name, "bar_001");
+    assert_eq!(tables[1].data_type, NautilusDataType::Bar);
+    assert_eq!(tables[1].row_count, Some(10));
+    assert_eq!(tables[1].min_ts_init, Some(bars[0].ts_init()));
+    assert_eq!(tables[1].max_ts_init, Some(bars[9].ts_init()));
+}
+
+#[rstest]
+fn test_per_table_chunk_size() {
+    let quotes_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let trades_path = "../../tests/test_data/nautilus/trades.parquet";
+    let mut expected_catalog = DataBackendSession::new(1_000).unwrap();
+    expected_catalog
+        .add_file::<QuoteTick>("quote_005", quotes_path, None)
+        .unwrap();
+    expected_catalog
+        .add_file::<TradeTick>("trade_001", trades_path, None)
+        .unwrap();
+    let expected: Vec<Data> = expected_catalog.get_query_result().collect();
+
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .add_file_with_chunk_size::<QuoteTick>("quote_005", quotes_path, None, Some(7))
+        .unwrap();
+    catalog
+        .add_file_with_chunk_size::<TradeTick>("trade_001", trades_path, None, None)
+        .unwrap();
+    let tables = catalog.registered_tables();
+    let chunks: Vec<Vec<Data>> = catalog.get_chunked_query_result().collect();
+
+    assert_eq!(tables[0].chunk_size, 7);
+    assert_eq!(tables[1].chunk_size, 1_000);
+    assert!(chunks.iter().all(|chunk| chunk.len() <= 1_000));
+    let result = chunks.concat();
+    assert!(is_monotonically_increasing_by_init(&result));
+    // Ties on `ts_init` across tables may merge in either order
+    let quotes = |data: &[Data]| -> Vec<Data> {
+        data.iter()
+            .filter(|data| matches!(data, Data::Quote(_)))
+            .cloned()
+            .collect()
+    };
+    assert_eq!(result.len(), expected.len());
+    assert_eq!(quotes(&result), quotes(&expected));
+}
+
+#[rstest]
+fn test_order_book_depth10_round_trip(stub_depth10: OrderBookDepth10) {
+    let depths: Vec<Data> = (0..5)
+        .map(|i| {
+            let mut depth = stub_depth10;
+            depth.ts_event = (100 + i).into();
+            depth.ts_init = (200 + i).into();
+            // Order IDs are not persisted for depth levels
+            for order in depth.bids.iter_mut().chain(depth.asks.iter_mut()) {
+                order.order_id = 0;
+            }
+            Data::Depth10(depth)
+        })
+        .collect();
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("depth10.parquet");
+    let file_path = file_path.to_str().unwrap();
+    write_data_to_parquet(file_path, &depths, ParquetCompression::Snappy).unwrap();
+
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file::<OrderBookDepth10>("depth_001", file_path, None)
+        .unwrap();
+    let result: Vec<Data> = catalog.get_query_result().collect();
+
+    assert_eq!(result, depths);
+    assert_eq!(
+        catalog.registered_tables()[0].data_type,
+        NautilusDataType::OrderBookDepth10
+    );
+}
+
+#[rstest]
+fn test_interleaved_files_query_is_globally_sorted() {
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file::<QuoteTick>(
+            "quotes",
+            "../../tests/test_data/nautilus/quotes.parquet",
+            None,
+        )
+        .unwrap();
+    let quotes: Vec<Data> = catalog.get_query_result().collect();
+
+    // Split the quotes across two files so that their ranges fully overlap
+    let (even, odd): (Vec<_>, Vec<_>) = quotes
+        .iter()
+        .cloned()
+        .enumerate()
+        .partition(|(i, _)| i % 2 == 0);
+    let even: Vec<Data> = even.into_iter().map(|(_, data)| data).collect();
+    let odd: Vec<Data> = odd.into_iter().map(|(_, data)| data).collect();
+    let temp_dir = TempDir::new().unwrap();
+    let even_path = temp_dir.path().join("quotes_even.parquet");
+    let odd_path = temp_dir.path().join("quotes_odd.parquet");
+    write_data_to_parquet(
+        even_path.to_str().unwrap(),
+        &even,
+        ParquetCompression::Snappy,
+    )
+    .unwrap();
+    write_data_to_parquet(odd_path.to_str().unwrap(), &odd, ParquetCompression::Snappy).unwrap();
+
+    // Register the later file first so the result does not depend on input order
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .add_file::<QuoteTick>("quotes_odd", odd_path.to_str().unwrap(), None)
+        .unwrap();
+    catalog
+        .add_file::<TradeTick>(
+            "trades",
+            "../../tests/test_data/nautilus/trades.parquet",
+            None,
+        )
+        .unwrap();
+    catalog
+        .add_file::<QuoteTick>("quotes_even", even_path.to_str().unwrap(), None)
+        .unwrap();
+    let result: Vec<Data> = catalog.get_query_result().collect();
+
+    assert_eq!(result.len(), quotes.len() + 100);
+    assert!(is_monotonically_increasing_by_init(&result));
+    let result_quotes: Vec<Data> = result
+        .into_iter()
+        .filter(|data| matches!(data, Data::Quote(_)))
+        .collect();
+    assert_eq!(result_quotes, quotes);
+}
+
+#[rstest]
+fn test_directory_query() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file::<QuoteTick>("quotes", file_path, None)
+        .unwrap();
+    let quotes: Vec<Data> = catalog.get_query_result().collect();
+
+    // Partition the quotes into one file per half, alongside files to be skipped
+    let (first, second) = quotes.split_at(quotes.len() / 2);
+    let temp_dir = TempDir::new().unwrap();
+    for (name, data) in [
+        ("quotes_2.parquet", second),
+        ("quotes_1.parquet", first),
+        (".quotes_hidden.parquet", first),
+    ] {
+        let path = temp_dir.path().join(name);
+        write_data_to_parquet(path.to_str().unwrap(), data, ParquetCompression::Snappy).unwrap();
+    }
+    std::fs::write(temp_dir.path().join("README.txt"), "not parquet").unwrap();
+
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .add_directory::<QuoteTick>("quotes_dir", temp_dir.path().to_str().unwrap())
+        .unwrap();
+    let tables = catalog.registered_tables();
+    let result: Vec<Data> = catalog.get_query_result().collect();
+
+    assert_eq!(tables.len(), 1);
+    assert_eq!(tables[0].row_count, Some(9_500));
+    assert_eq!(tables[0].min_ts_init, Some(quotes[0].ts_init()));
+    assert_eq!(tables[0].max_ts_init, Some(quotes[9_499].ts_init()));
+    assert_eq!(result.len(), 9_500);
+    assert!(is_monotonically_increasing_by_init(&result));
+    assert_eq!(result, quotes);
+}
+
+#[rstest]
+fn test_directory_range_query_opens_overlapping_files() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file::<QuoteTick>("quotes", file_path, None)
+        .unwrap();
+    let quotes: Vec<Data> = catalog.get_query_result().collect();
+
+    // Partition the quotes into three daily files
+    let days: Vec<&[Data]> = quotes.chunks(quotes.len().div_ceil(3)).collect();
+    let temp_dir = TempDir::new().unwrap();
+    for (i, data) in days.iter().enumerate() {
+        let path = temp_dir.path().join(format!("day_{}.parquet", i + 1));
+        write_data_to_parquet(path.to_str().unwrap(), data, ParquetCompression::Snappy).unwrap();
+    }
+    let start = days[1][100].ts_init();
+    let end = days[1][days[1].len() - 100].ts_init();
+    assert!(days[0].last().unwrap().ts_init() < start);
+    assert!(days[2][0].ts_init() > end);
+
+    let dir_path = temp_dir.path().to_str().unwrap();
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .add_directory_range_query::<QuoteTick>("quotes_dir", dir_path, Some(start), Some(end))
+        .unwrap();
+    let plan = catalog.explain("quotes_dir").unwrap();
+    let tables = catalog.registered_tables();
+    let result: Vec<Data> = catalog.get_query_result().collect();
+
+    let expected: Vec<Data> = quotes
+        .iter()
+        .filter(|tick| tick.ts_init() >= start && tick.ts_init() <= end)
+        .cloned()
+        .collect();
+    assert!(plan.contains("day_2.parquet"), "{plan}");
+    assert!(!plan.contains("day_1.parquet"), "{plan}");
+    assert!(!plan.contains("day_3.parquet"), "{plan}");
+    assert_eq!(tables[0].row_count, Some(days[1].len() as u64));
+    assert_eq!(result, expected);
+
+    // A range beyond all the files gives an empty result
+    let after = days[2].last().unwrap().ts_init() + 1;
+    catalog
+        .add_directory_range_query::<QuoteTick>("quotes_after", dir_path, Some(after), None)
+        .unwrap();
+    assert_eq!(catalog.get_query_result().count(), 0);
+}
+
+#[rstest]
+fn test_partitioned_dataset_query() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file::<QuoteTick>("quotes", file_path, None)
+        .unwrap();
+    let quotes: Vec<Data> = catalog.get_query_result().collect();
+
+    // Partition the quotes into one date directory per half
+    let (first, second) = quotes.split_at(quotes.len() / 2);
+    let temp_dir = TempDir::new().unwrap();
+    for (date, data) in [("2023-01-01", first), ("2023-01-02", second)] {
+        let dir_path = temp_dir.path().join(format!("date={date}"));
+        std::fs::create_dir(&dir_path).unwrap();
+        let path = dir_path.join("part.parquet");
+        write_data_to_parquet(path.to_str().unwrap(), data, ParquetCompression::Snappy).unwrap();
+    }
+
+    let root_path = temp_dir.path().to_str().unwrap();
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .add_partitioned_dataset::<QuoteTick>(
+            "quotes_day",
+            root_path,
+            &["date"],
+            Some("SELECT * FROM quotes_day WHERE date = '2023-01-02'"),
+        )
+        .unwrap();
+    // Only the file of the filtered partition is scanned
+    let plan = catalog.explain("quotes_day").unwrap();
+    let day: Vec<Data> = catalog.get_query_result().collect();
+
+    assert!(plan.contains("date=2023-01-02/part.parquet"), "{plan}");
+    assert!(!plan.contains("date=2023-01-01"), "{plan}");
+    assert_eq!(day, second);
+
+    catalog
+        .add_partitioned_dataset::<QuoteTick>("quotes_all", root_path, &["date"], None)
+        .unwrap();
+    let all: Vec<Data> = catalog.get_query_result().collect();
+
+    assert_eq!(all, quotes);
+}
+
+#[rstest]
+fn test_file_metadata() {
+    let file_path = "../../tests/test_data/nautilus/deltas.parquet";
+    let metadata = DataBackendSession::file_metadata(file_path).unwrap();
+
+    assert_eq!(metadata.num_rows, 1_077);
+    assert_eq!(metadata.row_groups.len(), 1_077);
+    assert_eq!(
+        metadata
+            .row_groups
+            .iter()
+            .map(|row_group| row_group.num_rows)
+            .sum::<u64>(),
+        1_077
+    );
+    assert!(metadata.column_names.iter().any(|name| name == "ts_init"));
+    assert!(metadata
+        .row_groups
+        .iter()
+        .all(|row_group| row_group.min_ts_init.is_some()
+            && row_group.min_ts_init <= row_group.max_ts_init));
+}
+
+#[rstest]
+fn test_equal_ts_init_tiebreak_by_data_type(
+    quote_tick_audusd_sim: QuoteTick,
+    trade_tick_audusd_sim: TradeTick,
+) {
+    let quotes: Vec<Data> = (1..=5_u64)
+        .map(|i| {
+            let mut quote = quote_tick_audusd_sim;
+            quote.ts_event = i.into();
+            quote.ts_init = i.into();
+            Data::Quote(quote)
+        })
+        .collect();
+    let trades: Vec<Data> = (1..=5_u64)
+        .map(|i| {
+            let mut trade = trade_tick_audusd_sim;
+            trade.ts_event = i.into();
+            trade.ts_init = i.into();
+            Data::Trade(trade)
+        })
+        .collect();
+    let temp_dir = TempDir::new().unwrap();
+    let quotes_path = temp_dir.path().join("quotes.parquet");
+    let trades_path = temp_dir.path().join("trades.parquet");
+    write_data_to_parquet(
+        quotes_path.to_str().unwrap(),
+        &quotes,
+        ParquetCompression::Snappy,
+    )
+    .unwrap();
+    write_data_to_parquet(
+        trades_path.to_str().unwrap(),
+        &trades,
+        ParquetCompression::Snappy,
+    )
+    .unwrap();
+
+    // Register the trades first so the order does not follow registration
+    let mut catalog = DataBackendSession::new(2).unwrap();
+    catalog
+        .add_file::<TradeTick>("trades", trades_path.to_str().unwrap(), None)
+        .unwrap();
+    catalog
+        .add_file::<QuoteTick>("quotes", quotes_path.to_str().unwrap(), None)
+        .unwrap();
+    let result: Vec<Data> = catalog.get_query_result().collect();
+
+    let expected: Vec<Data> = quotes
+        .into_iter()
+        .zip(trades)
+        .flat_map(|(quote, trade)| [quote, trade])
+        .collect();
+    assert_eq!(result, expected);
+}
+
+#[rstest]
+fn test_equal_ts_init_tiebreak_by_registration_order(quote_tick_audusd_sim: QuoteTick) {
+    let first = quote_tick_audusd_sim;
+    let mut second = quote_tick_audusd_sim;
+    second.bid_size = Quantity::from(1);
+    let temp_dir = TempDir::new().unwrap();
+    let first_path = temp_dir.path().join("first.parquet");
+    let first_path = first_path.to_str().unwrap();
+    let second_path = temp_dir.path().join("second.parquet");
+    let second_path = second_path.to_str().unwrap();
+    write_data_to_parquet(
+        first_path,
+        &[Data::Quote(first)],
+        ParquetCompression::Snappy,
+    )
+    .unwrap();
+    write_data_to_parquet(
+        second_path,
+        &[Data::Quote(second)],
+        ParquetCompression::Snappy,
+    )
+    .unwrap();
+
+    for (paths, expected) in [
+        ([first_path, second_path], [first, second]),
+        ([second_path, first_path], [second, first]),
+    ] {
+        let mut catalog = DataBackendSession::new(1_000).unwrap();
+        for (i, path) in paths.into_iter().enumerate() {
+            catalog
+                .add_file::<QuoteTick>(&format!("quotes_{i}"), path, None)
+                .unwrap();
+        }
+        let result: Vec<Data> = catalog.get_query_result().collect();
+
+        assert_eq!(result, expected.map(Data::Quote));
+    }
+}
+
+#[rstest]
+fn test_session_builder() {
+    let file_path = "../../tests/test_data/nautilus/bars.parquet";
+    let mut catalog = DataBackendSession::builder()
+        .chunk_size(3)
+        .decode_threads(2)
+        .build()
+        .unwrap();
+    catalog.add_file::<Bar>("bars", file_path, None).unwrap();
+    let chunks: Vec<Vec<Data>> = catalog.get_chunked_query_result().collect();
+
+    assert_eq!(catalog.chunk_size, 3);
+    assert_eq!(
+        chunks.iter().map(Vec::len).collect::<Vec<_>>(),
+        vec![3, 3, 3, 1]
+    );
+    assert!(is_monotonically_increasing_by_init(&chunks.concat()));
+}
+
+#[rstest]
+#[case("../../tests/test_data/nautilus/quotes.csv")]
+#[case("../../tests/test_data/nautilus/quotes.csv.gz")]
+fn test_csv_quote_query(#[case] file_path: &str) {
+    let instrument_id = InstrumentId::from("EUR/USD.SIM");
+    let csv_schema = CsvSchema::new(QuoteTick::get_metadata(&instrument_id, 5, 0))
+        .column("bid_price", "bid")
+        .column("ask_price", "ask")
+        .column("bid_size", "bid_qty")
+        .column("ask_size", "ask_qty")
+        .column("ts_event", "timestamp")
+        .column("ts_init", "timestamp");
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .add_csv_file::<QuoteTick>("quotes", file_path, &csv_schema)
+        .unwrap();
+
+    let result: Vec<Data> = catalog.get_query_result().collect();
+
+    assert_eq!(result.len(), 3);
+    assert!(is_monotonically_increasing_by_init(&result));
+    let Data::Quote(quote) = result[0] else {
+        panic!("Expected a quote, found {:?}", result[0]);
+    };
+    assert_eq!(quote.instrument_id, instrument_id);
+    assert_eq!(quote.bid_price, Price::from("1.12000"));
+    assert_eq!(quote.ask_price, Price::from("1.12015"));
+    assert_eq!(quote.bid_size, Quantity::from(1_500_000));
+    assert_eq!(quote.ask_size, Quantity::from(1_000_000));
+    assert_eq!(quote.ts_event.as_u64(), 1_577_919_599_000_000_000);
+    assert_eq!(quote.ts_init, quote.ts_event);
+}
+
+#[rstest]
+fn test_ipc_quote_round_trip() {
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file::<QuoteTick>(
+            "quotes",
+            "../../tests/test_data/nautilus/quotes.parquet",
+            None,
+        )
+        .unwrap();
+    let expected: Vec<Data> = catalog.get_query_result().collect();
+    let quotes: Vec<QuoteTick> = expected
+        .iter()
+        .map(|data| *data.as_quote().unwrap())
+        .collect();
+
+    let metadata = QuoteTick::get_metadata(&quotes[0].instrument_id, 5, 0);
+    let batch = QuoteTick::encode_batch(&metadata, &quotes).unwrap();
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("quotes.feather");
+    let file = std::fs::File::create(&file_path).unwrap();
+    let mut writer = FileWriter::try_new(file, &batch.schema()).unwrap();
+    writer.write(&batch).unwrap();
+    writer.finish().unwrap();
+
+    catalog
+        .add_ipc_file::<QuoteTick>("quotes_ipc", file_path.to_str().unwrap())
+        .unwrap();
+    let ticks: Vec<Data> = catalog.get_query_result().collect();
+
+    assert_eq!(ticks.len(), 9_500);
+    assert_eq!(ticks, expected);
+}
+
+#[rstest]
+fn test_repeated_quote_query_served_from_cache() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog.enable_cache(64 * 1024 * 1024);
+
+    catalog
+        .add_file::<QuoteTick>("quotes", file_path, None)
+        .unwrap();
+    let first: Vec<Data> = catalog.get_query_result().collect();
+    catalog
+        .add_table_query::<QuoteTick>("quotes", None)
+        .unwrap();
+    let second: Vec<Data> = catalog.get_query_result().collect();
+
+    let stats = catalog.cache_stats().unwrap();
+    assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+    assert_eq!(first.len(), 9500);
+    assert_eq!(second, first);
+
+    // Deregistering the table invalidates its cached results
+    catalog.deregister("quotes");
+    catalog
+        .add_file::<QuoteTick>("quotes", file_path, None)
+        .unwrap();
+    let third: Vec<Data> = catalog.get_query_result().collect();
+    let stats = catalog.cache_stats().unwrap();
+    assert_eq!((stats.hits, stats.misses), (1, 2));
+    assert_eq!(third, first);
+}
+
+#[rstest]
+fn test_bar_query_time_range() {
+    let file_path = "../../tests/test_data/nautilus/bars.parquet";
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog.add_file::<Bar>("bars", file_path, None).unwrap();
+
+    let query_result = catalog.get_query_result();
+    let expected = Some((1_637_971_200_000_000_000, 1_637_971_740_000_000_000));
+    assert_eq!(query_result.time_range(), expected);
+    let bars: Vec<Data> = query_result.collect();
+    assert_eq!(time_range(&bars), expected);
+}
+
+#[rstest]
+fn test_time_range_unknown_without_stats() {
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .deltas_to_snapshots(
+            "snapshots",
+            "../../tests/test_data/nautilus/deltas.parquet",
+            60_000_000_000,
+            5,
+        )
+        .unwrap();
+    catalog
+        .add_file::<Bar>("bars", "../../tests/test_data/nautilus/bars.parquet", None)
+        .unwrap();
+
+    assert_eq!(catalog.get_query_result().time_range(), None);
+}
+
+#[rstest]
+fn test_quote_query_price_only_projection() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let mut full = DataBackendSession::new(1_000).unwrap();
+    full.add_file::<QuoteTick>("quotes", file_path, None)
+        .unwrap();
+    let expected: Vec<Data> = full.get_query_result().collect();
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .add_file_projected::<QuoteTick>(
+            "quotes",
+            file_path,
+            &["bid_price", "ask_price", "ts_init"],
+        )
+        .unwrap();
+
+    let result: Vec<Data> = catalog.get_query_result().collect();
+
+    assert_eq!(result.len(), expected.len());
+    for (data, expected) in result.iter().zip(&expected) {
+        let (quote, expected) = (data.as_quote().unwrap(), expected.as_quote().unwrap());
+        assert_eq!(quote.instrument_id, expected.instrument_id);
+        assert_eq!(quote.bid_price, expected.bid_price);
+        assert_eq!(quote.ask_price, expected.ask_price);
+        assert_eq!(quote.ts_init, expected.ts_init);
+        assert_eq!(quote.bid_size.raw, 0);
+        assert_eq!(quote.ask_size.raw, 0);
+        assert_eq!(quote.ts_event.as_u64(), 0);
+    }
+}
+
+#[rstest]
+fn test_quote_tick_column_map() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = write_renamed_quotes(
+        &temp_dir,
+        &[
+            ("bid_price", "bid"),
+            ("ask_price", "ask"),
+            ("ts_init", "timestamp"),
+        ],
+    );
+    let mut full = DataBackendSession::new(10_000).unwrap();
+    full.add_file::<QuoteTick>(
+        "quotes",
+        "../../tests/test_data/nautilus/quotes.parquet",
+        None,
+    )
+    .unwrap();
+    let expected: Vec<Data> = full.get_query_result().collect();
+    let column_map = HashMap::from([
+        ("bid".to_string(), "bid_price".to_string()),
+        ("ask".to_string(), "ask_price".to_string()),
+        ("timestamp".to_string(), "ts_init".to_string()),
+    ]);
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file_with_column_map::<QuoteTick>("quotes", &file_path, &column_map)
+        .unwrap();
+    let result: Vec<Data> = catalog.get_query_result().collect();
+
+    assert_eq!(result.len(), 9_500);
+    assert_eq!(result, expected);
+}
+
+#[rstest]
+fn test_column_map_missing_fields_errors() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = write_renamed_quotes(
+        &temp_dir,
+        &[
+            ("bid_price", "bid"),
+            ("ask_price", "ask"),
+            ("ts_init", "timestamp"),
+        ],
+    );
+    let column_map = HashMap::from([("bid".to_string(), "bid_price".to_string())]);
+    let unknown = HashMap::from([("bid_px".to_string(), "bid_price".to_string())]);
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+
+    let missing = catalog.add_file_with_column_map::<QuoteTick>("quotes", &file_path, &column_map);
+    let unknown = catalog.add_file_with_column_map::<QuoteTick>("quotes", &file_path, &unknown);
+
+    assert!(matches!(
+        missing,
+        Err(PersistenceError::MissingColumns(fields)) if fields == "ask_price, ts_init"
+    ));
+    assert!(matches!(
+        unknown,
+        Err(PersistenceError::InvalidProjection { column, .. }) if column == "bid_px"
+    ));
+    assert!(catalog.registered_tables().is_empty());
+}
+
+#[rstest]
+fn test_projection_without_required_key_errors() {
+    let quote = quote_tick_audusd_sim();
+    let metadata = QuoteTick::get_metadata(&quote.instrument_id, 5, 0);
+    let batch = QuoteTick::encode_batch(&metadata, &[quote]).unwrap();
+    let mut fields = batch.schema().fields().to_vec();
+    fields.push(Arc::new(Field::new("instrument_id", DataType::Utf8, false)));
+    let mut columns = batch.columns().to_vec();
+    columns.push(Arc::new(StringArray::from(vec!["AUD/USD.SIM"])));
+    let schema = Schema::new_with_metadata(fields, batch.schema().metadata().clone());
+    let batch = RecordBatch::try_new(Arc::new(schema), columns).unwrap();
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("quotes_multi.parquet");
+    let file_path = file_path.to_str().unwrap();
+    write_batch_to_parquet(file_path, &batch, ParquetCompression::Snappy).unwrap();
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+
+    let missing_key =
+        catalog.add_file_projected::<QuoteTick>("quotes", file_path, &["bid_price", "ts_init"]);
+    let missing_ts_init =
+        catalog.add_file_projected::<QuoteTick>("quotes", file_path, &["bid_price"]);
+    let unknown = catalog.add_file_projected::<QuoteTick>("quotes", file_path, &["price"]);
+
+    assert!(matches!(
+        missing_key,
+        Err(PersistenceError::InvalidProjection { column, .. }) if column == "instrument_id"
+    ));
+    assert!(matches!(
+        missing_ts_init,
+        Err(PersistenceError::InvalidProjection { column, .. }) if column == "ts_init"
+    ));
+    assert!(matches!(
+        unknown,
+        Err(PersistenceError::InvalidProjection { column, .. }) if column == "price"
+    ));
+    catalog
+        .add_file_projected::<QuoteTick>(
+            "quotes",
+            file_path,
+            &["instrument_id", "bid_price", "ts_init"],
+        )
+        .unwrap();
+    assert_eq!(catalog.get_query_result().count(), 1);
+}
+
+#[rstest]
+#[case(NautilusDataType::OrderBookDelta, "order_book_delta")]
+#[case(NautilusDataType::OrderBookDepth10, "order_book_depth10")]
+#[case(NautilusDataType::QuoteTick, "quote_tick")]
+#[case(NautilusDataType::TradeTick, "trade_tick")]
+#[case(NautilusDataType::Bar, "bar")]
+#[case(NautilusDataType::InstrumentAny, "instrument_any")]
+fn test_data_type_string_round_trip(#[case] data_type: NautilusDataType, #[case] name: &str) {
+    assert_eq!(data_type.to_string(), name);
+    assert_eq!(NautilusDataType::from_str(name).unwrap(), data_type);
+}
+
+#[rstest]
+#[case("QuoteTick")]
+#[case("quote")]
+#[case("")]
+fn test_data_type_from_unknown_string_errors(#[case] name: &str) {
+    assert!(matches!(
+        NautilusDataType::from_str(name),
+        Err(PersistenceError::UnknownDataType(unknown)) if unknown == name
+    ));
+}
+
+#[rstest]
+fn test_quote_paged_query() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file::<QuoteTick>("quotes", file_path, None)
+        .unwrap();
+    let expected: Vec<Data> = catalog.get_query_result().collect();
+
+    let mut pages: Vec<Vec<Data>> = Vec::new();
+    for i in 0..10 {
+        let table_name = format!("quotes_{i}");
+        catalog
+            .add_file_paged_query::<QuoteTick>(&table_name, file_path, 1_000, i * 1_000)
+            .unwrap();
+        pages.push(catalog.get_query_result().collect());
+    }
+
+    assert_eq!(
+        pages.iter().map(Vec::len).collect::<Vec<_>>(),
+        [1_000, 1_000, 1_000, 1_000, 1_000, 1_000, 1_000, 1_000, 1_000, 500]
+    );
+    assert_eq!(pages.concat(), expected);
+}
+
+#[rstest]
+fn test_paged_query_beyond_end_is_empty() {
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .add_file_paged_query::<QuoteTick>(
+            "quotes",
+            "../../tests/test_data/nautilus/quotes.parquet",
+            1_000,
+            20_000,
+        )
+        .unwrap();
+
+    assert_eq!(catalog.get_query_result().count(), 0);
+}
+
+#[rstest]
+fn test_quote_tail_query() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file::<QuoteTick>("quotes", file_path, None)
+        .unwrap();
+    let all_ticks: Vec<Data> = catalog.get_query_result().collect();
+
+    catalog
+        .add_file_tail_query::<QuoteTick>("quotes_tail", file_path, 10)
+        .unwrap();
+    let ticks: Vec<Data> = catalog.get_query_result().collect();
+
+    assert_eq!(ticks.len(), 10);
+    assert!(is_monotonically_increasing_by_init(&ticks));
+    assert_eq!(
+        ticks.iter().map(GetTsInit::ts_init).collect::<Vec<_>>(),
+        all_ticks[all_ticks.len() - 10..]
+            .iter()
+            .map(GetTsInit::ts_init)
+            .collect::<Vec<_>>()
+    );
+
+    catalog
+        .add_file_tail_query::<QuoteTick>("quotes_all", file_path, 20_000)
+        .unwrap();
+    assert_eq!(catalog.get_query_result().count(), all_ticks.len());
+}
+
+#[rstest]
+fn test_dedup_drops_exact_duplicate() {
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .add_file::<QuoteTick>(
+            "quotes",
+            "../../tests/test_data/nautilus/quotes.parquet",
+            None,
+        )
+        .unwrap();
+    let mut quotes: Vec<Data> = catalog.get_query_result().take(100).collect();
+    // An exact duplicate, and a distinct quote sharing its timestamp
+    let mut same_ts = *quotes[50].as_quote().unwrap();
+    same_ts.bid_size = Quantity::from(1);
+    quotes.insert(51, quotes[50].clone());
+    quotes.insert(52, Data::Quote(same_ts));
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("quotes_dup.parquet");
+    let file_path = file_path.to_str().unwrap();
+    write_data_to_parquet(file_path, &quotes, ParquetCompression::Snappy).unwrap();
+
+    let mut catalog = DataBackendSession::builder().dedup(true).build().unwrap();
+    catalog
+        .add_file::<QuoteTick>("quotes", file_path, None)
+        .unwrap();
+    let result: Vec<Data> = catalog.get_query_result().collect();
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .add_file::<QuoteTick>("quotes", file_path, None)
+        .unwrap();
+    let result_with_dups: Vec<Data> = catalog.get_query_result().collect();
+
+    assert_eq!(result_with_dups.len(), 102);
+    assert_eq!(result.len(), 101);
+    assert_eq!(result[51], Data::Quote(same_ts));
+}
+
+#[rstest]
+fn test_progress_callback_called_per_chunk() {
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .add_file::<QuoteTick>(
+            "quotes",
+            "../../tests/test_data/nautilus/quotes.parquet",
+            None,
+        )
+        .unwrap();
+    let mut query_result = catalog.get_query_result();
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let calls_clone = calls.clone();
+    query_result.set_progress_callback(Box::new(move |rows, total| {
+        calls_clone.lock().unwrap().push((rows, total));
+    }));
+
+    assert_eq!(query_result.count(), 9_500);
+    let calls = calls.lock().unwrap();
+    assert_eq!(calls.len(), 10);
+    assert_eq!(calls[0], (1_000, Some(9_500)));
+    assert_eq!(calls[9], (9_500, Some(9_500)));
+}
+
+#[rstest]
+fn test_progress_callback_called_per_row_group() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let metadata = DataBackendSession::file_metadata(file_path).unwrap();
+    let mut catalog = DataBackendSession::builder()
+        .chunk_mode(ChunkMode::RowGroup)
+        .build()
+        .unwrap();
+    catalog
+        .add_file::<QuoteTick>("quotes", file_path, None)
+        .unwrap();
+    let mut query_result = catalog.get_chunked_query_result();
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let calls_clone = calls.clone();
+    query_result
+        .result
+        .set_progress_callback(Box::new(move |rows, _| {
+            calls_clone.lock().unwrap().push(rows);
+        }));
+
+    let chunk_ends: Vec<u64> = query_result
+        .scan(0, |rows, chunk| {
+            *rows += chunk.len() as u64;
+            Some(*rows)
+        })
+        .collect();
+    let row_group_ends: Vec<u64> = metadata
+        .row_groups
+        .iter()
+        .scan(0, |rows, row_group| {
+            *rows += row_group.num_rows;
+            Some(*rows)
+        })
+        .collect();
+
+    assert_eq!(*calls.lock().unwrap(), chunk_ends);
+    assert_eq!(chunk_ends, row_group_ends);
+}
+
+#[rstest]
+fn test_trade_tick_bounds() {
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .add_file::<TradeTick>(
+            "trades",
+            "../../tests/test_data/nautilus/trades.parquet",
+            None,
+        )
+        .unwrap();
+    let query_result = catalog.get_query_result();
+
+    let (first, last) = query_result.bounds().unwrap().unwrap();
+
+    assert_eq!(first.instrument_id(), InstrumentId::from("EUR/USD.SIM"));
+    assert!(last.ts_init() >= first.ts_init());
+    assert_eq!(query_result.count(), 100);
+}
+
+#[rstest]
+fn test_quote_expected_precision() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let quotes = DataBackendSession::new(1_000)
+        .unwrap()
+        .collect_typed::<QuoteTick>("quotes", file_path)
+        .unwrap();
+    let price_precision = quotes[0].bid_price.precision;
+    let size_precision = quotes[0].bid_size.precision;
+    let session = |precision_policy| {
+        DataBackendSession::builder()
+            .precision_policy(precision_policy)
+            .build()
+            .unwrap()
+    };
+
+    let ticks = session(PrecisionPolicy::Expect {
+        price_precision,
+        size_precision,
+    })
+    .collect_typed::<QuoteTick>("quotes", file_path)
+    .unwrap();
+    let result = session(PrecisionPolicy::Expect {
+        price_precision: price_precision + 1,
+        size_precision,
+    })
+    .add_file::<QuoteTick>("quotes", file_path, None);
+
+    assert_eq!(ticks, quotes);
+    assert!(matches!(
+        result,
+        Err(PersistenceError::PrecisionMismatch { key: "price_precision", expected, found })
+            if expected == price_precision + 1 && found == price_precision
+    ));
+}
+
+#[rstest]
+fn test_quote_override_precision() {
+    let mut catalog = DataBackendSession::builder()
+        .precision_policy(PrecisionPolicy::Override {
+            price_precision: 7,
+            size_precision: 2,
+        })
+        .build()
+        .unwrap();
+    catalog
+        .add_file::<QuoteTick>(
+            "quotes",
+            "../../tests/test_data/nautilus/quotes.parquet",
+            None,
+        )
+        .unwrap();
+
+    let ticks: Vec<Data> = catalog.get_query_result().collect();
+
+    assert_eq!(ticks.len(), 9500);
+    assert!(ticks.iter().all(|tick| matches!(
+        tick,
+        Data::Quote(quote) if quote.ask_price.precision == 7 && quote.ask_size.precision == 2
+    )));
+}
+
+#[rstest]
+fn test_precision_policy_above_max_errors() {
+    let result = DataBackendSession::builder()
+        .precision_policy(PrecisionPolicy::Override {
+            price_precision: 10,
+            size_precision: 0,
+        })
+        .build();
+
+    assert!(matches!(
+        result,
+        Err(PersistenceError::InvalidPrecision(10))
+    ));
+}
+
+#[rstest]
+fn test_sort_on_read_orders_unordered_file() {
+    let file_path = "../../tests/test_data/nautilus/deltas.parquet";
+    let mut expected = DataBackendSession::new(1_000)
+        .unwrap()
+        .collect::<OrderBookDelta>("deltas", file_path)
+        .unwrap();
+    assert!(!is_monotonically_increasing_by_init(&expected));
+    expected.sort_by_key(Data::ts_init);
+    let mut catalog = DataBackendSession::builder()
+        .sort_on_read(true)
+        .build()
+        .unwrap();
+    catalog
+        .add_file::<OrderBookDelta>("deltas", file_path, None)
+        .unwrap();
+
+    let deltas: Vec<Data> = catalog.get_query_result().collect();
+
+    assert_eq!(deltas.len(), 1077);
+    assert!(is_monotonically_increasing_by_init(&deltas));
+    assert_eq!(deltas, expected);
+}
+
+#[rstest]
+fn test_extra_column_ignored_unless_strict() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = write_quotes_with_venue_seq(&temp_dir);
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+
+    let ticks = catalog.collect::<QuoteTick>("quotes", &file_path).unwrap();
+
+    let expected = DataBackendSession::new(1_000)
+        .unwrap()
+        .collect::<QuoteTick>("quotes", "../../tests/test_data/nautilus/quotes.parquet")
+        .unwrap();
+    assert_eq!(ticks, expected);
+
+    let mut strict = DataBackendSession::builder()
+        .allow_extra_columns(false)
+        .build()
+        .unwrap();
+    let result = strict.add_file::<QuoteTick>("quotes", &file_path, None);
+    assert!(matches!(
+        result,
+        Err(PersistenceError::UnexpectedColumns(columns)) if columns == "venue_seq"
+    ));
+    let result = strict.collect_typed::<QuoteTick>("quotes", &file_path);
+    assert!(matches!(
+        result,
+        Err(PersistenceError::UnexpectedColumns(columns)) if columns == "venue_seq"
+    ));
+    // A rejected file leaves its table name free for a valid one
+    assert!(strict.registered_tables().is_empty());
+    strict
+        .add_file::<QuoteTick>(
+            "quotes",
+            "../../tests/test_data/nautilus/quotes.parquet",
+            None,
+        )
+        .unwrap();
+    let tables = strict.registered_tables();
+    assert_eq!(tables.len(), 1);
+    assert_eq!(tables[0].table_name, "quotes");
+}
+
+#[rstest]
+fn test_same_file_under_two_table_names() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let expected = DataBackendSession::new(1_000)
+        .unwrap()
+        .collect::<QuoteTick>("quotes", file_path)
+        .unwrap();
+    let split_ts = expected[5_000].ts_init();
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .add_file::<QuoteTick>(
+            "early_quotes",
+            file_path,
+            Some(&format!(
+                "SELECT * FROM early_quotes WHERE ts_init < {split_ts}"
+            )),
+        )
+        .unwrap();
+    catalog
+        .add_file::<QuoteTick>(
+            "late_quotes",
+            file_path,
+            Some(&format!(
+                "SELECT * FROM late_quotes WHERE ts_init >= {split_ts}"
+            )),
+        )
+        .unwrap();
+
+    let tables = catalog.registered_tables();
+    let ticks: Vec<Data> = catalog.get_query_result().collect();
+
+    assert_eq!(tables.len(), 2);
+    assert_eq!(tables[0].row_count, Some(9500));
+    assert_eq!(tables[1].row_count, Some(9500));
+    assert_eq!(ticks.len(), 9500);
+    assert_eq!(ticks, expected);
+    assert!(ticks[..5_000].iter().all(|tick| tick.ts_init() < split_ts));
+    assert!(catalog
+        .add_file::<QuoteTick>("late_quotes", file_path, None)
+        .is_err());
+}
+
+#[rstest]
+fn test_collect_to_spill_round_trip() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path().to_str().unwrap();
+    let register = |catalog: &mut DataBackendSession| {
+        catalog
+            .add_file::<QuoteTick>(
+                "quotes",
+                "../../tests/test_data/nautilus/quotes.parquet",
+                None,
+            )
+            .unwrap();
+        catalog
+            .add_file::<Bar>("bars", "../../tests/test_data/nautilus/bars.parquet", None)
+            .unwrap();
+    };
+    let mut expected = DataBackendSession::new(1_000).unwrap();
+    register(&mut expected);
+    let expected: Vec<Data> = expected.get_query_result().collect();
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    register(&mut catalog);
+
+    let spilled = catalog
+        .get_query_result()
+        .collect_to_spill(dir, 1_000 * std::mem::size_of::<Data>())
+        .unwrap();
+
+    assert_eq!(spilled.len(), 9510);
+    assert!(spilled.num_spilled_files() >= 9);
+    let data: Vec<Data> = spilled.iter().map(Result::unwrap).collect();
+    assert_eq!(data, expected);
+    assert_eq!(spilled.iter().count(), 9510);
+    assert_eq!(std::fs::read_dir(dir).unwrap().count(), 1);
+    drop(spilled);
+    assert_eq!(std::fs::read_dir(dir).unwrap().count(), 0);
+}
+
+#[rstest]
+fn test_fold_total_trade_volume() {
+    let file_path = "../../tests/test_data/nautilus/trades.parquet";
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .add_file::<TradeTick>("trades", file_path, None)
+        .unwrap();
+
+    let volume = catalog
+        .get_query_result()
+        .fold(0_u64, |volume, data| match data {
+            Data::Trade(trade) => volume + trade.size.raw,
+            _ => volume,
+        })
+        .unwrap();
+
+    let trades = DataBackendSession::new(1_000)
+        .unwrap()
+        .collect_typed::<TradeTick>("trades", file_path)
+        .unwrap();
+    assert_eq!(trades.len(), 100);
+    assert_eq!(
+        volume,
+        trades.iter().map(|trade| trade.size.raw).sum::<u64>()
+    );
+    assert!(volume > 0);
+}
+
+#[rstest]
+fn test_bounds_of_empty_result() {
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .add_file_range_query::<Bar>(
+            "bars",
+            "../../tests/test_data/nautilus/bars.parquet",
+            Some(UnixNanos::from(0)),
+            Some(UnixNanos::from(1)),
+        )
+        .unwrap();
+
+    assert!(catalog.get_query_result().bounds().unwrap().is_none());
+}
+
+#[rstest]
+fn test_quote_query_metrics() {
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .add_file::<QuoteTick>(
+            "quotes",
+            "../../tests/test_data/nautilus/quotes.parquet",
+            None,
+        )
+        .unwrap();
+    let mut query_result = catalog.get_query_result();
+
+    assert_eq!(query_result.by_ref().count(), 9_500);
+    let metrics = query_result.metrics();
+    assert_eq!(metrics.rows, 9_500);
+    assert!(metrics.chunks >= 10);
+    assert!(metrics.bytes >= 9_500 * 4 * 8);
+    assert!(metrics.scan_time > Duration::ZERO);
+    assert!(metrics.decode_time > Duration::ZERO);
+}
+
+#[rstest]
+fn test_compare_catalogs() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    let quotes = catalog.collect::<QuoteTick>("quotes", file_path).unwrap();
+    let temp_dir = TempDir::new().unwrap();
+    let copy_path = temp_dir.path().join("copy.parquet");
+    let copy_path = copy_path.to_str().unwrap();
+    std::fs::copy(file_path, copy_path).unwrap();
+    // A single row group, unlike the source file
+    let rewritten_path = temp_dir.path().join("rewritten.parquet");
+    let rewritten_path = rewritten_path.to_str().unwrap();
+    write_data_to_parquet(rewritten_path, &quotes, ParquetCompression::Zstd(3)).unwrap();
+    let truncated_path = temp_dir.path().join("truncated.parquet");
+    let truncated_path = truncated_path.to_str().unwrap();
+    write_data_to_parquet(truncated_path, &quotes[..9_000], ParquetCompression::Snappy).unwrap();
+
+    let copy = compare_catalogs::<QuoteTick>(file_path, copy_path).unwrap();
+    let rewritten = compare_catalogs::<QuoteTick>(file_path, rewritten_path).unwrap();
+    let truncated = compare_catalogs::<QuoteTick>(file_path, truncated_path).unwrap();
+
+    assert!(copy.is_equal());
+    assert!(rewritten.is_equal());
+    assert!(!truncated.is_equal());
+    assert_eq!(truncated.first_difference, Some(9_000));
+}
+
+#[rstest]
+fn test_validate_catalog() {
+    let file_names = ["bars", "quotes", "trades", "deltas", "missing"];
+    let paths: Vec<String> = file_names
+        .iter()
+        .map(|name| format!("../../tests/test_data/nautilus/{name}.parquet"))
+        .collect();
+
+    let reports = validate_catalog(&paths);
+
+    assert_eq!(
+        reports
+            .iter()
+            .map(|report| report.file_path.as_str())
+            .collect::<Vec<_>>(),
+        paths
+    );
+    assert!(reports[..3].iter().all(ValidationReport::passed));
+    // The deltas sample has a single out of order row
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    let index = match catalog.add_file_checked::<OrderBookDelta>("deltas", &paths[3], None) {
+        Err(PersistenceError::NotMonotonic { index, .. }) => index,
+        _ => panic!("expected a `NotMonotonic` error"),
+    };
+    assert_eq!(reports[3].first_offending_index(), Some(index));
+    assert!(matches!(reports[4].result, Err(PersistenceError::Io(_))));
+    assert_eq!(reports[4].first_offending_index(), None);
+}
+
+#[rstest]
+fn test_missing_file_io_error(quote_tick_audusd_sim: QuoteTick) {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("missing").join("quotes.parquet");
+    let file_path = file_path.to_str().unwrap();
+
+    let read_result = DataBackendSession::file_metadata(file_path);
+    let write_result = write_data_to_parquet(
+        file_path,
+        &[Data::Quote(quote_tick_audusd_sim)],
+        ParquetCompression::Snappy,
+    );
+
+    assert!(matches!(read_result, Err(PersistenceError::Io(_))));
+    assert!(matches!(write_result, Err(PersistenceError::Io(_))));
+}
+
+#[rstest]
+fn test_quote_query_first_row_group() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let metadata = DataBackendSession::file_metadata(file_path).unwrap();
+    let row_group = &metadata.row_groups[0];
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .add_file_row_groups::<QuoteTick>("quotes", file_path, 0, 1)
+        .unwrap();
+    let ticks: Vec<Data> = catalog.get_query_result().collect();
+
+    assert_eq!(ticks.len() as u64, row_group.num_rows);
+    assert_eq!(ticks.first().map(GetTsInit::ts_init), row_group.min_ts_init);
+    assert_eq!(ticks.last().map(GetTsInit::ts_init), row_group.max_ts_init);
+}
+
+#[rstest]
+fn test_row_group_range_out_of_bounds_errors() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let count = DataBackendSession::file_metadata(file_path)
+        .unwrap()
+        .row_groups
+        .len();
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+
+    for (start, end) in [(0, 0), (0, count + 1), (count, count + 1)] {
+        let result = catalog.add_file_row_groups::<QuoteTick>("quotes", file_path, start, end);
+
+        assert!(matches!(
+            result,
+            Err(PersistenceError::InvalidRowGroupRange { num_row_groups, .. })
+                if num_row_groups == count
+        ));
+    }
+}
+
+#[rstest]
+fn test_millisecond_timestamps_decode_to_nanos(quote_tick_audusd_sim: QuoteTick) {
+    let quotes: Vec<QuoteTick> = (1..=5_u64)
+        .map(|i| QuoteTick {
+            ts_event: (i * 1_000_000).into(),
+            ts_init: (i * 1_000_000).into(),
+            ..quote_tick_audusd_sim
+        })
+        .collect();
+    let metadata = QuoteTick::get_metadata(&quotes[0].instrument_id, 5, 0);
+    let batch = QuoteTick::encode_batch(&metadata, &quotes).unwrap();
+    // Store the timestamps as milliseconds, as written by some producers
+    let millis: ArrayRef = Arc::new(TimestampMillisecondArray::from(
+        (1..=5_i64).collect::<Vec<_>>(),
+    ));
+    let fields: Vec<_> = batch
+        .schema()
+        .fields()
+        .iter()
+        .map(|field| match field.name().as_str() {
+            "ts_event" | "ts_init" => Arc::new(Field::new(
+                field.name(),
+                DataType::Timestamp(TimeUnit::Millisecond, None),
+                false,
+            )),
+            _ => field.clone(),
+        })
+        .collect();
+    let columns: Vec<_> = batch
+        .schema()
+        .fields()
+        .iter()
+        .zip(batch.columns())
+        .map(|(field, column)| match field.name().as_str() {
+            "ts_event" | "ts_init" => millis.clone(),
+            _ => column.clone(),
+        })
+        .collect();
+    let schema = Schema::new_with_metadata(fields, batch.schema().metadata().clone());
+    let batch = RecordBatch::try_new(Arc::new(schema), columns).unwrap();
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("quotes_ms.parquet");
+    let file_path = file_path.to_str().unwrap();
+    write_batch_to_parquet(file_path, &batch, ParquetCompression::Snappy).unwrap();
+
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .add_file_checked::<QuoteTick>("quotes", file_path, None)
+        .unwrap();
+    let table = &catalog.registered_tables()[0];
+    assert_eq!(table.min_ts_init, Some(1_000_000.into()));
+    assert_eq!(table.max_ts_init, Some(5_000_000.into()));
+    let result: Vec<Data> = catalog.get_query_result().collect();
+
+    assert_eq!(
+        result,
+        quotes.into_iter().map(Data::Quote).collect::<Vec<_>>()
+    );
+}
+
+/// An object store whose first `failures` reads fail with a transient error,
+/// delegating all other operations to a [`LocalFileSystem`].
+#[cfg(feature = "object_store")]
+#[derive(Debug)]
+struct FlakyStore {
+    inner: LocalFileSystem,
+    failures: AtomicUsize,
+    reads: AtomicUsize,
+}
+
+#[cfg(feature = "object_store")]
+impl FlakyStore {
+    fn new(inner: LocalFileSystem, failures: usize) -> Self {
+        Self {
+            inner,
+            failures: AtomicUsize::new(failures),
+            reads: AtomicUsize::new(0),
+        }
+    }
+}
+
+#[cfg(feature = "object_store")]
+impl std::fmt::Display for FlakyStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "FlakyStore")
+    }
+}
+
+#[cfg(feature = "object_store")]
+#[async_trait]
+impl ObjectStore for FlakyStore {
+    async fn put_opts(
+        &self,
+        location: &ObjectPath,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> object_store::Result<PutResult> {
+        self.inner.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &ObjectPath,
+        opts: PutMultipartOpts,
+    ) -> object_store::Result<Box<dyn MultipartUpload>> {
+        self.inner.put_multipart_opts(location, opts).await
+    }
+
+    async fn get_opts(
+        &self,
+        location: &ObjectPath,
+        options: GetOptions,
+    ) -> object_store::Result<GetResult> {
+        let failing = self
+            .failures
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_ok();
+        if failing {
+            return Err(object_store::Error::Generic {
+                store: "FlakyStore",
+                source: Box::new(std::io::Error::from(std::io::ErrorKind::ConnectionReset)),
+            });
+        }
+        let result = self.inner.get_opts(location, options).await?;
+        self.reads.fetch_add(1, Ordering::SeqCst);
+        Ok(result)
+    }
+
+    async fn delete(&self, location: &ObjectPath) -> object_store::Result<()> {
+        self.inner.delete(location).await
+    }
+
+    fn list(&self, prefix: Option<&ObjectPath>) -> BoxStream<'_, object_store::Result<ObjectMeta>> {
+        self.inner.list(prefix)
+    }
+
+    async fn list_with_delimiter(
+        &self,
+        prefix: Option<&ObjectPath>,
+    ) -> object_store::Result<ListResult> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &ObjectPath, to: &ObjectPath) -> object_store::Result<()> {
+        self.inner.copy(from, to).await
+    }
+
+    async fn copy_if_not_exists(
+        &self,
+        from: &ObjectPath,
+        to: &ObjectPath,
+    ) -> object_store::Result<()> {
+        self.inner.copy_if_not_exists(from, to).await
+    }
+}
+
+/// Writes the sample quotes to a parquet file with an `instrument_id` column
+/// cycling through `symbols`.
+fn write_multi_instrument_quotes(temp_dir: &TempDir, symbols: &[&str]) -> String {
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    catalog
+        .add_file::<QuoteTick>(
+            "quote_005",
+            "../../tests/test_data/nautilus/quotes.parquet",
+            None,
+        )
+        .unwrap();
+    let quotes: Vec<QuoteTick> = catalog
+        .get_query_result()
+        .map(|data| match data {
+            Data::Quote(quote) => quote,
+            _ => panic!("Expected quote"),
+        })
+        .collect();
+
+    let metadata = QuoteTick::get_metadata(&quotes[0].instrument_id, 5, 0);
+    let batch = QuoteTick::encode_batch(&metadata, &quotes).unwrap();
+    let instrument_ids: StringArray = (0..batch.num_rows())
+        .map(|i| Some(symbols[i % symbols.len()]))
+        .collect();
+    let mut fields = batch.schema().fields().to_vec();
+    fields.push(Arc::new(Field::new("instrument_id", DataType::Utf8, false)));
+    let mut columns = batch.columns().to_vec();
+    columns.push(Arc::new(instrument_ids));
+    let schema = Schema::new_with_metadata(fields, batch.schema().metadata().clone());
+    let batch = RecordBatch::try_new(Arc::new(schema), columns).unwrap();
+    let file_path = temp_dir.path().join("quotes_multi.parquet");
+    let file_path = file_path.to_str().unwrap().to_string();
+    write_batch_to_parquet(&file_path, &batch, ParquetCompression::Snappy).unwrap();
+    file_path
+}
+
+/// Writes the sample quotes to a parquet file with the columns of `renames`
+/// given their vendor names.
+fn write_renamed_quotes(temp_dir: &TempDir, renames: &[(&str, &str)]) -> String {
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    let quotes: Vec<QuoteTick> = catalog
+        .collect_typed("quotes", "../../tests/test_data/nautilus/quotes.parquet")
+        .unwrap();
+    let metadata = QuoteTick::get_metadata(&quotes[0].instrument_id, 5, 0);
+    let batch = QuoteTick::encode_batch(&metadata, &quotes).unwrap();
+    let fields: Vec<_> = batch
+        .schema()
+        .fields()
+        .iter()
+        .map(|field| {
+            let name = renames
+                .iter()
+                .find(|(name, _)| name == field.name())
+                .map_or(field.name().as_str(), |(_, renamed)| renamed);
+            Arc::new(field.as_ref().clone().with_name(name))
+        })
+        .collect();
+    let schema = Schema::new_with_metadata(fields, batch.schema().metadata().clone());
+    let batch = RecordBatch::try_new(Arc::new(schema), batch.columns().to_vec()).unwrap();
+    let file_path = temp_dir.path().join("quotes_renamed.parquet");
+    let file_path = file_path.to_str().unwrap().to_string();
+    write_batch_to_parquet(&file_path, &batch, ParquetCompression::Snappy).unwrap();
+    file_path
+}
+
+/// Writes the quotes of the test file to a parquet file with an extra `venue_seq`
+/// column.
+fn write_quotes_with_venue_seq(temp_dir: &TempDir) -> String {
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    let quotes: Vec<QuoteTick> = catalog
+        .collect_typed("quotes", "../../tests/test_data/nautilus/quotes.parquet")
+        .unwrap();
+    let metadata = QuoteTick::get_metadata(&quotes[0].instrument_id, 5, 0);
+    let batch = QuoteTick::encode_batch(&metadata, &quotes).unwrap();
+    let mut fields = batch.schema().fields().to_vec();
+    fields.push(Arc::new(Field::new("venue_seq", DataType::UInt64, false)));
+    let mut columns = batch.columns().to_vec();
+    columns.push(Arc::new(UInt64Array::from_iter_values(
+        0..quotes.len() as u64,
+    )));
+    let schema = Schema::new_with_metadata(fields, batch.schema().metadata().clone());
+    let batch = RecordBatch::try_new(Arc::new(schema), columns).unwrap();
+    let file_path = temp_dir.path().join("quotes_venue_seq.parquet");
+    let file_path = file_path.to_str().unwrap().to_string();
+    write_batch_to_parquet(&file_path, &batch, ParquetCompression::Snappy).unwrap();
+    file_path
+}
+
+/// Writes the stub quote to a parquet file without the column `dropped`.
+fn write_quote_without_column(temp_dir: &TempDir, quote: QuoteTick, dropped: &str) -> String {
+    let metadata = QuoteTick::get_metadata(&quote.instrument_id, 5, 0);
+    let batch = QuoteTick::encode_batch(&metadata, &[quote]).unwrap();
+    let index = batch.schema().index_of(dropped).unwrap();
+    let mut fields = batch.schema().fields().to_vec();
+    fields.remove(index);
+    let mut columns = batch.columns().to_vec();
+    columns.remove(index);
+    let schema = Schema::new_with_metadata(fields, batch.schema().metadata().clone());
+    let batch = RecordBatch::try_new(Arc::new(schema), columns).unwrap();
+    let file_path = temp_dir
+        .path()
+        .join(format!("quotes_without_{dropped}.parquet"));
+    let file_path = file_path.to_str().unwrap().to_string();
+    write_batch_to_parquet(&file_path, &batch, ParquetCompression::Snappy).unwrap();
+    file_path
+}
+
+#[rstest]
+fn test_missing_optional_column_decoded_as_zero(quote_tick_audusd_sim: QuoteTick) {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = write_quote_without_column(&temp_dir, quote_tick_audusd_sim, "ask_size");
+
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    let strict_result = catalog.add_file::<QuoteTick>("quotes", &file_path, None);
+    let mut catalog = DataBackendSession::builder()
+        .allow_missing_optional(true)
+        .build()
+        .unwrap();
+    catalog
+        .add_file::<QuoteTick>("quotes", &file_path, None)
+        .unwrap();
+    let result: Vec<Data> = catalog.get_query_result().collect();
+
+    assert!(matches!(
+        strict_result,
+        Err(PersistenceError::SchemaMismatch { .. })
+    ));
+    let expected = QuoteTick {
+        ask_size: Quantity::from_raw(0, 0),
+        ..quote_tick_audusd_sim
+    };
+    assert_eq!(result, [Data::Quote(expected)]);
+}
+
+#[rstest]
+fn test_collect_typed_missing_optional_column(quote_tick_audusd_sim: QuoteTick) {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = write_quote_without_column(&temp_dir, quote_tick_audusd_sim, "ask_size");
+    let mut catalog = DataBackendSession::builder()
+        .allow_missing_optional(true)
+        .build()
+        .unwrap();
+
+    let quotes: Vec<QuoteTick> = catalog.collect_typed("quotes", &file_path).unwrap();
+
+    let expected = QuoteTick {
+        ask_size: Quantity::from_raw(0, 0),
+        ..quote_tick_audusd_sim
+    };
+    assert_eq!(quotes, [expected]);
+}
+
+#[rstest]
+fn test_missing_required_column_errors_when_allowing_optional(quote_tick_audusd_sim: QuoteTick) {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = write_quote_without_column(&temp_dir, quote_tick_audusd_sim, "ts_init");
+    let mut catalog = DataBackendSession::builder()
+        .allow_missing_optional(true)
+        .build()
+        .unwrap();
+
+    let result = catalog.add_file::<QuoteTick>("quotes", &file_path, None);
+
+    assert!(matches!(
+        result,
+        Err(PersistenceError::SchemaMismatch { .. })
+    ));
+}
+
+#[rstest]
+fn test_quote_query_filtered_by_price(quote_tick_audusd_sim: QuoteTick) {
+    let quotes: Vec<Data> = (0..10_u64)
+        .map(|i| {
+            Data::Quote(QuoteTick {
+                bid_price: Price::new(1.0 + i as f64 * 0.01, 5),
+                ts_event: i.into(),
+                ts_init: i.into(),
+                ..quote_tick_audusd_sim
+            })
+        })
+        .collect();
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("quotes.parquet");
+    let file_path = file_path.to_str().unwrap();
+    write_data_to_parquet(file_path, &quotes, ParquetCompression::Snappy).unwrap();
+
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    let filter = Filter::col("bid_price").gt(lit(1.05));
+    catalog
+        .add_file_filtered::<QuoteTick>("quotes", file_path, &filter)
+        .unwrap();
+    let result: Vec<Data> = catalog.get_query_result().collect();
+
+    let threshold = Price::new(1.05, 5);
+    assert_eq!(result.len(), 4);
+    assert!(result
+        .iter()
+        .all(|data| data.as_quote().unwrap().bid_price > threshold));
+}
+
+#[rstest]
+#[case(NullPolicy::Error)]
+#[case(NullPolicy::Skip)]
+#[case(NullPolicy::Zero)]
+fn test_null_bid_price_policy(quote_tick_audusd_sim: QuoteTick, #[case] null_policy: NullPolicy) {
+    let quotes: Vec<QuoteTick> = (0..3_u64)
+        .map(|i| QuoteTick {
+            ts_event: i.into(),
+            ts_init: i.into(),
+            ..quote_tick_audusd_sim
+        })
+        .collect();
+    let metadata = QuoteTick::get_metadata(&quote_tick_audusd_sim.instrument_id, 5, 0);
+    let batch = QuoteTick::encode_batch(&metadata, &quotes).unwrap();
+
+    // Replace the bid prices with a nullable column missing the second price
+    let index = batch.schema().index_of("bid_price").unwrap();
+    let bid_price = quote_tick_audusd_sim.bid_price.raw;
+    let mut fields = batch.schema().fields().to_vec();
+    fields[index] = Arc::new(Field::new("bid_price", DataType::Int64, true));
+    let mut columns = batch.columns().to_vec();
+    columns[index] = Arc::new(Int64Array::from(vec![
+        Some(bid_price),
+        None,
+        Some(bid_price),
+    ]));
+    let schema = Schema::new_with_metadata(fields, batch.schema().metadata().clone());
+    let batch = RecordBatch::try_new(Arc::new(schema), columns).unwrap();
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("quotes_null.parquet");
+    let file_path = file_path.to_str().unwrap();
+    write_batch_to_parquet(file_path, &batch, ParquetCompression::Snappy).unwrap();
+
+    let mut catalog = DataBackendSession::builder()
+        .null_policy(null_policy)
+        .build()
+        .unwrap();
+    catalog
+        .add_file::<QuoteTick>("quotes", file_path, None)
+        .unwrap();
+    let results: Vec<_> = catalog.get_query_result().flatten_results().collect();
+
+    match null_policy {
+        NullPolicy::Error => {
+            assert_eq!(results.len(), 1);
+            assert!(matches!(
+                &results[0],
+                Err(PersistenceError::NullValue(column)) if column == "bid_price"
+            ));
+        }
+        NullPolicy::Skip => {
+            let ticks: Vec<Data> = results.into_iter().map(Result::unwrap).collect();
+            assert_eq!(
+                ticks.iter().map(GetTsInit::ts_init).collect::<Vec<_>>(),
+                [UnixNanos::from(0), UnixNanos::from(2)]
+            );
+        }
+        NullPolicy::Zero => {
+            let ticks: Vec<Data> = results.into_iter().map(Result::unwrap).collect();
+            assert_eq!(
+                ticks
+                    .iter()
+                    .map(|data| data.as_quote().unwrap().bid_price.raw)
+                    .collect::<Vec<_>>(),
+                [bid_price, 0, bid_price]
+            );
+        }
+    }
+}
+
+#[rstest]
+fn test_session_zero_chunk_size_errors() {
+    assert!(matches!(
+        DataBackendSession::new(0),
+        Err(PersistenceError::InvalidChunkSize)
+    ));
+    assert!(matches!(
+        DataBackendSession::builder().chunk_size(0).build(),
+        Err(PersistenceError::InvalidChunkSize)
+    ));
+    assert!(matches!(
+        DataBackendSession::builder().decode_threads(0).build(),
+        Err(PersistenceError::InvalidDecodeThreads)
+    ));
+}
+
+#[rstest]
+fn test_set_zero_decode_threads_errors() {
+    let mut catalog = DataBackendSession::builder()
+        .decode_threads(2)
+        .build()
+        .unwrap();
+
+    assert!(matches!(
+        catalog.set_decode_threads(0),
+        Err(PersistenceError::InvalidDecodeThreads)
+    ));
+    assert_eq!(catalog.decode_threads(), Some(2));
+    catalog.set_decode_threads(4).unwrap();
+    assert_eq!(catalog.decode_threads(), Some(4));
+}
+
+#[cfg(feature = "mmap")]
+#[rstest]
+fn test_quote_query_with_mmap() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let mut buffered = DataBackendSession::new(1_000).unwrap();
+    buffered
+        .add_file::<QuoteTick>("quotes", file_path, None)
+        .unwrap();
+    let mut mapped = DataBackendSession::builder()
+        .chunk_size(1_000)
+        .use_mmap(true)
+        .build()
+        .unwrap();
+    mapped
+        .add_file::<QuoteTick>("quotes", file_path, None)
+        .unwrap();
+    let store = mapped
+        .context()
+        .runtime_env()
+        .object_store(ObjectStoreUrl::local_filesystem())
+        .unwrap();
+
+    assert!(mapped.uses_mmap());
+    assert_eq!(store.to_string(), "MmapFileSystem");
+    assert_eq!(mapped.count("quotes").unwrap(), 9500);
+    let expected: Vec<Data> = buffered.get_query_result().collect();
+    let result: Vec<Data> = mapped.get_query_result().collect();
+    assert_eq!(result.len(), 9500);
+    assert_eq!(result, expected);
+}
+
+#[cfg(feature = "object_store")]
+#[rstest]
+fn test_object_store_read_retried_after_transient_errors() {
+    let root = std::fs::canonicalize("../../tests/test_data/nautilus").unwrap();
+    let flaky = Arc::new(FlakyStore::new(
+        LocalFileSystem::new_with_prefix(root).unwrap(),
+        2,
+    ));
+    let policy = RetryPolicy {
+        max_retries: 3,
+        backoff: Duration::from_millis(1),
+    };
+    let store = RetryingObjectStore::new(flaky.clone(), policy);
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    let url = ObjectStoreUrl::parse("s3://bucket").unwrap();
+    catalog
+        .context()
+        .register_object_store(url.as_ref(), Arc::new(store));
+
+    catalog
+        .add_file::<QuoteTick>("quotes", "s3://bucket/quotes.parquet", None)
+        .unwrap();
+    let ticks: Vec<Data> = catalog.get_query_result().collect();
+
+    assert_eq!(ticks.len(), 9_500);
+    assert_eq!(flaky.failures.load(Ordering::SeqCst), 0);
+}
+
+#[cfg(feature = "object_store")]
+#[rstest]
+fn test_object_store_not_found_fails_fast() {
+    let root = std::fs::canonicalize("../../tests/test_data/nautilus").unwrap();
+    let flaky = Arc::new(FlakyStore::new(
+        LocalFileSystem::new_with_prefix(root).unwrap(),
+        0,
+    ));
+    let policy = RetryPolicy {
+        max_retries: 3,
+        backoff: Duration::from_secs(60),
+    };
+    let store = RetryingObjectStore::new(flaky.clone(), policy);
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    let url = ObjectStoreUrl::parse("s3://bucket").unwrap();
+    catalog
+        .context()
+        .register_object_store(url.as_ref(), Arc::new(store));
+
+    let result = catalog.add_file::<QuoteTick>("quotes", "s3://bucket/missing.parquet", None);
+
+    assert!(result.is_err());
+    assert_eq!(flaky.reads.load(Ordering::SeqCst), 0);
+}
+
+/// Serves `body` over HTTP on a local port, answering `HEAD` requests and
+/// ranged `GET` requests, and returns the base URL with the number of ranged
+/// requests served.
+#[cfg(feature = "http")]
+fn serve_http_file(body: Vec<u8>) -> (String, Arc<AtomicUsize>) {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let body = Arc::new(body);
+    let ranged_requests = Arc::new(AtomicUsize::new(0));
+    let ranged = ranged_requests.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let (body, ranged) = (body.clone(), ranged.clone());
+            thread::spawn(move || {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut stream = stream;
+                loop {
+                    let mut request_line = String::new();
+                    if reader.read_line(&mut request_line).unwrap_or(0) == 0 {
+                        return;
+                    }
+                    let mut range = None;
+                    loop {
+                        let mut header = String::new();
+                        reader.read_line(&mut header).unwrap();
+                        let header = header.trim_end();
+                        if header.is_empty() {
+                            break;
+                        }
+                        if let Some((name, value)) = header.split_once(": ") {
+                            if name.eq_ignore_ascii_case("range") {
+                                range = value.strip_prefix("bytes=").map(str::to_string);
+                            }
+                        }
+                    }
+
+                    let len = body.len();
+                    let (status, start, end) = match range.as_deref().map(|r| r.split_once('-')) {
+                        Some(Some(("", suffix))) => {
+                            (206, len - suffix.parse::<usize>().unwrap().min(len), len)
+                        }
+                        Some(Some((start, ""))) => (206, start.parse().unwrap(), len),
+                        Some(Some((start, end))) => (
+                            206,
+                            start.parse().unwrap(),
+                            (end.parse::<usize>().unwrap() + 1).min(len),
+                        ),
+                        _ => (200, 0, len),
+                    };
+                    let mut response = format!(
+                        "HTTP/1.1 {status} {}\r\nContent-Length: {}\r\n",
+                        if status == 206 {
+                            "Partial Content"
+                        } else {
+                            "OK"
+                        },
+                        end - start
+                    );
+                    if status == 206 {
+                        ranged.fetch_add(1, Ordering::SeqCst);
+                        response.push_str(&format!(
+                            "Content-Range: bytes {start}-{}/{len}\r\n",
+                            end - 1
+                        ));
+                    }
+                    response.push_str("\r\n");
+                    stream.write_all(response.as_bytes()).unwrap();
+                    if request_line.starts_with("GET") {
+                        stream.write_all(&body[start..end]).unwrap();
+                    }
+                }
+            });
+        }
+    });
+    (url, ranged_requests)
+}
+
+#[cfg(feature = "http")]
+#[rstest]
+fn test_http_file_read_with_range_requests() {
+    let file_path = "../../tests/test_data/nautilus/quotes.parquet";
+    let (url, ranged_requests) = serve_http_file(std::fs::read(file_path).unwrap());
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .add_http_file::<QuoteTick>("quotes", &format!("{url}/quotes.parquet"))
+        .unwrap();
+
+    let ticks: Vec<Data> = catalog.get_query_result().collect();
+
+    let mut expected = DataBackendSession::new(1_000).unwrap();
+    expected
+        .add_file::<QuoteTick>("quotes", file_path, None)
+        .unwrap();
+    let expected: Vec<Data> = expected.get_query_result().collect();
+    assert_eq!(ticks.len(), 9500);
+    assert_eq!(ticks, expected);
+    assert!(ranged_requests.load(Ordering::SeqCst) > 1);
+}
+
+#[cfg(feature = "http")]
+#[rstest]
+fn test_http_file_invalid_scheme_errors() {
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+
+    let result = catalog.add_http_file::<QuoteTick>("quotes", "s3://bucket/quotes.parquet");
+
+    assert!(matches!(result, Err(PersistenceError::InvalidHttpUrl(_))));
+}
+
+#[rstest]
+fn test_decoded_identifiers_are_interned() {
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .add_file::<QuoteTick>(
+            "quotes",
+            "../../tests/test_data/nautilus/quotes.parquet",
+            None,
+        )
+        .unwrap();
+    catalog
+        .add_file::<Bar>("bars", "../../tests/test_data/nautilus/bars.parquet", None)
+        .unwrap();
+    let result: Vec<Data> = catalog.get_query_result().collect();
+
+    let quotes: Vec<&QuoteTick> = result.iter().filter_map(Data::as_quote).collect();
+    let bars: Vec<&Bar> = result.iter().filter_map(Data::as_bar).collect();
+    let (first, last) = (quotes[0], quotes[quotes.len() - 1]);
+    assert_eq!(quotes.len(), 9_500);
+    // Quotes decoded from different batches share the interned symbol
+    assert_eq!(
+        first.instrument_id.symbol.as_str().as_ptr(),
+        last.instrument_id.symbol.as_str().as_ptr()
+    );
+    assert_eq!(
+        first.instrument_id.venue.as_str().as_ptr(),
+        last.instrument_id.venue.as_str().as_ptr()
+    );
+    assert_eq!(
+        bars[0].bar_type.instrument_id().symbol.as_str().as_ptr(),
+        bars[9].bar_type.instrument_id().symbol.as_str().as_ptr()
+    );
+}
+
+#[rstest]
+fn test_query_sorted_by_ts_event(quote_tick_audusd_sim: QuoteTick) {
+    // Events arrive in the reverse order to which they are initialized
+    let quotes: Vec<Data> = (0..100_u64)
+        .map(|i| {
+            let mut quote = quote_tick_audusd_sim;
+            quote.ts_event = (1_000 - i).into();
+            quote.ts_init = (2_000 + i).into();
+            Data::Quote(quote)
+        })
+        .collect();
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("quotes.parquet");
+    let file_path = file_path.to_str().unwrap();
+    write_data_to_parquet(file_path, &quotes, ParquetCompression::Snappy).unwrap();
+
+    let mut catalog = DataBackendSession::builder()
+        .chunk_size(10)
+        .sort_key(SortKey::TsEvent)
+        .build()
+        .unwrap();
+    catalog
+        .add_file::<QuoteTick>("quotes", file_path, None)
+        .unwrap();
+    let result: Vec<Data> = catalog.get_query_result().collect();
+
+    assert_eq!(catalog.sort_key(), SortKey::TsEvent);
+    assert_eq!(result.len(), 100);
+    assert!(SortKey::TsEvent.is_monotonically_increasing(&result));
+    assert!(!SortKey::TsInit.is_monotonically_increasing(&result));
+    assert_eq!(result, quotes.into_iter().rev().collect::<Vec<_>>());
+
+    let result = catalog.add_file_checked::<QuoteTick>("quotes_checked", file_path, None);
+    assert!(matches!(
+        result,
+        Err(PersistenceError::NotMonotonic {
+            sort_key: SortKey::TsEvent,
+            index: 1,
+            ..
+        })
+    ));
+}
+
+/// Returns 30 trades, and a record batch of them with the aggressor side of the
+/// second ten rows corrupted.
+fn malformed_trades_batch(trade_tick_audusd_sim: TradeTick) -> (Vec<TradeTick>, RecordBatch) {
+    let trades: Vec<TradeTick> = (0..30)
+        .map(|i| {
+            let mut trade = trade_tick_audusd_sim;
+            trade.ts_event = (1_000 + i).into();
+            trade.ts_init = (2_000 + i).into();
+            trade
+        })
+        .collect();
+    let metadata = TradeTick::get_metadata(
+        &trades[0].instrument_id,
+        trades[0].price.precision,
+        trades[0].size.precision,
+    );
+    let batch = TradeTick::encode_batch(&metadata, &trades).unwrap();
+    // Corrupt the aggressor side of every row of the second chunk
+    let aggressor_sides: UInt8Array = (0..30)
+        .map(|i| if (10..20).contains(&i) { 9 } else { 1 })
+        .collect();
+    let mut columns = batch.columns().to_vec();
+    columns[2] = Arc::new(aggressor_sides) as ArrayRef;
+    let batch = RecordBatch::try_new(batch.schema(), columns).unwrap();
+    (trades, batch)
+}
+
+#[rstest]
+fn test_flatten_results_skips_malformed_batch(trade_tick_audusd_sim: TradeTick) {
+    let (trades, batch) = malformed_trades_batch(trade_tick_audusd_sim);
+    let mut catalog = DataBackendSession::new(10).unwrap();
+    catalog
+        .add_batch::<TradeTick>("trades_malformed", batch)
+        .unwrap();
+    let results: Vec<Result<Data, PersistenceError>> =
+        catalog.get_query_result().flatten_results().collect();
+    let (ok, err): (Vec<_>, Vec<_>) = results.into_iter().partition(Result::is_ok);
+    let ticks: Vec<Data> = ok.into_iter().map(Result::unwrap).collect();
+
+    assert_eq!(err.len(), 1);
+    assert!(matches!(err[0], Err(PersistenceError::Encoding(_))));
+    assert_eq!(ticks.len(), 20);
+    let expected: Vec<Data> = trades[..10]
+        .iter()
+        .chain(&trades[20..])
+        .map(|trade| Data::Trade(*trade))
+        .collect();
+    assert_eq!(ticks, expected);
+}
+
+#[rstest]
+#[should_panic(expected = "Failed to decode record batch of query result")]
+fn test_iter_malformed_batch_panics(trade_tick_audusd_sim: TradeTick) {
+    let (_, batch) = malformed_trades_batch(trade_tick_audusd_sim);
+    let mut catalog = DataBackendSession::new(10).unwrap();
+    catalog
+        .add_batch::<TradeTick>("trades_malformed", batch)
+        .unwrap();
+    let _: Vec<Data> = catalog.get_query_result().collect();
+}
+
+#[rstest]
+fn test_deltas_to_snapshots() {
+    let file_path = "../../tests/test_data/nautilus/deltas.parquet";
+    let interval_ns = 3_600_000_000_000; // 1 hour
+    let metadata = DataBackendSession::file_metadata(file_path).unwrap();
+    let first = metadata
+        .row_groups
+        .iter()
+        .filter_map(|r| r.min_ts_init)
+        .min();
+    let last = metadata
+        .row_groups
+        .iter()
+        .filter_map(|r| r.max_ts_init)
+        .max();
+    let (first, last) = (first.unwrap().as_u64(), last.unwrap().as_u64());
+
+    let mut catalog = DataBackendSession::new(100).unwrap();
+    catalog
+        .deltas_to_snapshots("snapshots", file_path, interval_ns, 5)
+        .unwrap();
+    let snapshots: Vec<Data> = catalog.get_query_result().collect();
+
+    // A snapshot for each boundary between the first and last delta
+    let expected_count = (last - 1) / interval_ns - first / interval_ns;
+    assert_eq!(snapshots.len() as u64, expected_count);
+    assert!((9..=11).contains(&expected_count));
+    assert!(is_monotonically_increasing_by_init(&snapshots));
+    for snapshot in &snapshots {
+        let depth = snapshot.as_depth10().unwrap();
+        assert_eq!(depth.ts_init.as_u64() % interval_ns, 0);
+        assert!(depth.bids[5..].iter().all(|order| order.size.raw == 0));
+        assert!(depth.asks[5..].iter().all(|order| order.size.raw == 0));
+        assert!(depth.bid_counts[0] > 0 || depth.ask_counts[0] > 0);
+    }
+}
+
+#[rstest]
+fn test_deltas_to_snapshots_invalid_args() {
+    let file_path = "../../tests/test_data/nautilus/deltas.parquet";
+    let mut catalog = DataBackendSession::new(100).unwrap();
+
+    assert!(matches!(
+        catalog.deltas_to_snapshots("snapshots", file_path, 0, 5),
+        Err(PersistenceError::InvalidSnapshotInterval)
+    ));
+    assert!(matches!(
+        catalog.deltas_to_snapshots("snapshots", file_path, 1_000, 11),
+        Err(PersistenceError::InvalidSnapshotDepth(11))
+    ));
+    assert!(catalog.registered_tables().is_empty());
+}
+
+#[rstest]
+fn test_delta_sequence_range() {
+    let mut catalog = DataBackendSession::new(10_000).unwrap();
+    let mut deltas = catalog
+        .collect::<OrderBookDelta>("deltas", "../../tests/test_data/nautilus/deltas.parquet")
+        .unwrap();
+    deltas.sort_by_key(GetTsInit::ts_init);
+    // Give the deltas out of order sequences, as a permutation of 0..1077
+    let deltas: Vec<Data> = deltas
+        .into_iter()
+        .enumerate()
+        .map(|(i, data)| {
+            let mut delta = *data.as_delta().unwrap();
+            delta.sequence = (i as u64 * 7) % 1_077;
+            Data::Delta(delta)
+        })
+        .collect();
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("deltas.parquet");
+    let file_path = file_path.to_str().unwrap();
+    write_data_to_parquet(file_path, &deltas, ParquetCompression::Snappy).unwrap();
+
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .add_file_sequence_range("deltas", file_path, 100, 199)
+        .unwrap();
+    let result: Vec<Data> = catalog.get_query_result().collect();
+
+    assert_eq!(result.len(), 100);
+    assert!(result.iter().all(|data| {
+        let sequence = data.as_delta().unwrap().sequence;
+        (100..=199).contains(&sequence)
+    }));
+    assert!(is_monotonically_increasing_by_init(&result));
+}
+
+#[rstest]
+fn test_delta_top_five_book_depth() {
+    let file_path = "../../tests/test_data/nautilus/deltas.parquet";
+    let mut catalog = DataBackendSession::new(100).unwrap();
+    catalog.add_file_book_depth("depth", file_path, 5).unwrap();
+    let snapshots: Vec<_> = catalog
+        .get_query_result()
+        .flatten_results()
+        .map(Result::unwrap)
+        .collect();
+
+    assert_eq!(snapshots.len(), 1077);
+    assert!(snapshots.iter().all(|data| {
+        let depth = data.as_depth10().unwrap();
+        depth.bids[5..].iter().all(|order| order.size.raw == 0)
+            && depth.asks[5..].iter().all(|order| order.size.raw == 0)
+            && depth.bid_counts[5..].iter().all(|count| *count == 0)
+            && depth.ask_counts[5..].iter().all(|count| *count == 0)
+    }));
+    let last = snapshots.last().unwrap().as_depth10().unwrap();
+    assert!(last.bids[0].size.raw > 0);
+    assert!(last.asks[0].size.raw > 0);
+    assert!(matches!(
+        catalog.add_file_book_depth("invalid", file_path, 0),
+        Err(PersistenceError::InvalidSnapshotDepth(0))
+    ));
+}
+
+#[rstest]
+fn test_raw_sql_via_context() {
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .add_file::<QuoteTick>(
+            "quotes",
+            "../../tests/test_data/nautilus/quotes.parquet",
+            None,
+        )
+        .unwrap();
+    catalog
+        .add_file::<TradeTick>(
+            "trades",
+            "../../tests/test_data/nautilus/trades.parquet",
+            None,
+        )
+        .unwrap();
+
+    let query = catalog
+        .runtime
+        .block_on(
+            catalog
+                .context()
+                .sql("SELECT COUNT(*) AS count FROM quotes"),
+        )
+        .unwrap();
+    let batches = catalog.runtime.block_on(query.collect()).unwrap();
+    let counts = batches[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap();
+    assert_eq!(counts.value(0), 9_500);
+
+    let batches = catalog
+        .sql("SELECT (SELECT COUNT(*) FROM quotes) + (SELECT COUNT(*) FROM trades)")
+        .unwrap();
+    let counts = batches[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap();
+    assert_eq!(counts.value(0), 9_600);
+    // Raw queries are not added to the query result
+    assert_eq!(catalog.get_query_result().count(), 9_600);
+}
+
+#[rstest]
+fn test_bar_query_for_bar_type() {
+    let file_path = "../../tests/test_data/nautilus/bars.parquet";
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog.add_file::<Bar>("bars", file_path, None).unwrap();
+    let bars: Vec<Bar> = catalog
+        .get_query_result()
+        .filter_map(|data| data.as_bar().copied())
+        .collect();
+
+    // Add a `bar_type` column alternating between two bar types
+    let minute_type = bars[0].bar_type.to_string();
+    let five_minute_type = "ADABTC.BINANCE-5-MINUTE-LAST-EXTERNAL";
+    let metadata = Bar::get_metadata(
+        &bars[0].bar_type,
+        bars[0].open.precision,
+        bars[0].volume.precision,
+    );
+    let batch = Bar::encode_batch(&metadata, &bars).unwrap();
+    let bar_types: StringArray = (0..batch.num_rows())
+        .map(|i| {
+            Some(if i % 2 == 0 {
+                minute_type.as_str()
+            } else {
+                five_minute_type
+            })
+        })
+        .collect();
+    let mut fields = batch.schema().fields().to_vec();
+    fields.push(Arc::new(Field::new("bar_type", DataType::Utf8, false)));
+    let mut columns = batch.columns().to_vec();
+    columns.push(Arc::new(bar_types));
+    let schema = Schema::new_with_metadata(fields, batch.schema().metadata().clone());
+    let batch = RecordBatch::try_new(Arc::new(schema), columns).unwrap();
+    let temp_dir = TempDir::new().unwrap();
+    let multi_path = temp_dir.path().join("bars_multi.parquet");
+    let multi_path = multi_path.to_str().unwrap();
+    write_batch_to_parquet(multi_path, &batch, ParquetCompression::Snappy).unwrap();
+
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .add_file_for_bar_type("bars_multi", multi_path, five_minute_type)
+        .unwrap();
+    catalog
+        .add_file_for_bar_type("bars_single", file_path, &minute_type)
+        .unwrap();
+    catalog
+        .add_file_for_bar_type("bars_other", file_path, five_minute_type)
+        .unwrap();
+    let result: Vec<Data> = catalog.get_query_result().collect();
+    let (five_minute, minute): (Vec<&Bar>, Vec<&Bar>) = result
+        .iter()
+        .filter_map(Data::as_bar)
+        .partition(|bar| bar.bar_type.to_string() == five_minute_type);
+
+    assert_eq!(five_minute.len(), 5);
+    assert_eq!(minute.len(), 10);
+    assert!(is_monotonically_increasing_by_init(&result));
+
+    let result =
+        catalog.add_file_for_bar_type("bars_invalid", file_path, "ADABTC.BINANCE-1-MINUTE");
+    assert!(matches!(result, Err(PersistenceError::InvalidBarType(_))));
+    assert_eq!(catalog.registered_tables().len(), 3);
+}
+
+#[cfg(feature = "polars")]
+#[rstest]
+fn test_trades_to_polars() {
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .add_file::<TradeTick>(
+            "trades",
+            "../../tests/test_data/nautilus/trades.parquet",
+            None,
+        )
+        .unwrap();
+
+    let df = catalog.get_query_result().to_polars().unwrap();
+
+    assert_eq!(df.height(), 100);
+    assert_eq!(
+        df.get_column_names(),
+        vec![
+            "instrument_id",
+            "price",
+            "size",
+            "aggressor_side",
+            "trade_id",
+            "ts_event",
+            "ts_init"
+        ]
+    );
+}
+
+#[cfg(feature = "polars")]
+#[rstest]
+fn test_mixed_data_to_polars_errors() {
+    let mut catalog = DataBackendSession::new(1_000).unwrap();
+    catalog
+        .add_file::<TradeTick>(
+            "trades",
+            "../../tests/test_data/nautilus/trades.parquet",
+            None,
+        )
+        .unwrap();
+    catalog
+        .add_file::<Bar>("bars", "../../tests/test_data/nautilus/bars.parquet", None)
+        .unwrap();
+
+    let result = catalog.get_query_result().to_polars();
+
+    assert!(matches!(result, Err(PersistenceError::MixedDataTypes)));
+}
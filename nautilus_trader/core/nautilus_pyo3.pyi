@@ -9,6 +9,8 @@ from enum import Enum
 from os import PathLike
 from typing import Any, TypeAlias, Union
 
+import pandas as pd
+
 from nautilus_trader.core.data import Data
 
 # Python Interface typing:
@@ -2626,6 +2628,7 @@ class NautilusDataType(Enum):
     QuoteTick = 3
     TradeTick = 4
     Bar = 5
+    InstrumentAny = 6
 
 class DataBackendSession:
     def __init__(self, chunk_size: int = 5000) -> None: ...
@@ -2635,17 +2638,23 @@ class DataBackendSession:
         table_name: str,
         file_path: str,
         sql_query: str | None = None,
+        chunk_size: int | None = None,
     ) -> None: ...
-    def to_query_result(self) -> DataQueryResult: ...
+    def to_query_result(self, raw: bool = True) -> DataQueryResult: ...
+    def to_instruments(self) -> list[Instrument]: ...
+    def to_pandas(self) -> pd.DataFrame: ...
 
 class QueryResult:
     def next(self) -> Data | None: ...
 
 class DataQueryResult:
     def __init__(self, result: QueryResult, size: int) -> None: ...
-    def drop_chunk(self) -> None: ...
+    def drop(self) -> None: ...
     def __iter__(self) -> DataQueryResult: ...
     def __next__(self) -> Any | None: ...
+    def to_object_list(self) -> list[Data]: ...
+    def __enter__(self) -> DataQueryResult: ...
+    def __exit__(self, exc_type: Any = None, exc_value: Any = None, traceback: Any = None) -> None: ...
 
 class DataTransformer:
     @staticmethod